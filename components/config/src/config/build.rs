@@ -0,0 +1,9 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Build {
+    /// How many threads to use when loading and building the site.
+    /// Defaults to the number of CPUs, like rayon does.
+    pub threads: Option<usize>,
+}
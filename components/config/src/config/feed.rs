@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use globset::GlobMatcher;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Feed {
+    /// Also write the default language feed under `/{default_language}/` on multilingual
+    /// sites, in addition to the root one. `false` by default.
+    pub default_language_in_subdir: bool,
+    /// Render a feed for every section, not only the ones with `generate_feed = true`.
+    /// Index sections are left alone as the site feed already covers them. `false` by default.
+    pub all_sections: bool,
+    /// A feed is only written if it would contain at least that many pages. `1` by default.
+    pub min_items: usize,
+    /// Only include the pages dated within that many days of the build. Pages without a date
+    /// are left out either way. `None` by default.
+    pub max_age_days: Option<u32>,
+    /// How to order the pages having the same date. `permalink` by default.
+    pub sort_ties_by: SortTiesBy,
+}
+
+/// The order of pages with the same date in a feed, so it doesn't change between builds
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortTiesBy {
+    Permalink,
+    /// By title then by permalink for pages having the same title
+    Title,
+}
+
+impl Default for SortTiesBy {
+    fn default() -> SortTiesBy {
+        SortTiesBy::Permalink
+    }
+}
+
+impl Default for Feed {
+    fn default() -> Feed {
+        Feed {
+            default_language_in_subdir: false,
+            all_sections: false,
+            min_items: 1,
+            max_age_days: None,
+            sort_ties_by: SortTiesBy::Permalink,
+        }
+    }
+}
+
+/// A taxonomy term an extra feed can filter on, see `ExtraFeed::taxonomy_terms`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxonomyTerm {
+    pub taxonomy: String,
+    pub term: String,
+}
+
+/// How the `taxonomy_terms` of an extra feed are combined
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TermsMatch {
+    /// A page needs to have at least one of the terms
+    Any,
+    /// A page needs to have every term
+    All,
+}
+
+impl Default for TermsMatch {
+    fn default() -> TermsMatch {
+        TermsMatch::Any
+    }
+}
+
+/// A feed rendered at a custom path for a subset of the pages, see `Config::extra_feeds`.
+/// A page needs to match all the filters that are set to be included.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtraFeed {
+    /// The directory to write the feed in, relative to the output directory, eg `rust`
+    pub path: String,
+    /// Only include the pages of that section, eg `posts/_index.md`
+    pub section: Option<String>,
+    /// Only include the pages that have `term` in that taxonomy, eg `tags`
+    pub taxonomy: Option<String>,
+    /// The term to look for in `taxonomy`
+    pub term: Option<String>,
+    /// Only include the pages having any or all of those terms, depending on
+    /// `taxonomy_terms_match`
+    pub taxonomy_terms: Vec<TaxonomyTerm>,
+    /// Whether a page needs any (the default) or all of the `taxonomy_terms`
+    pub taxonomy_terms_match: TermsMatch,
+    /// Only include the pages whose path relative to the `content` directory matches that glob
+    pub glob: Option<String>,
+    /// `glob` compiled when parsing the config
+    #[serde(skip_serializing, skip_deserializing)]
+    pub glob_matcher: Option<GlobMatcher>,
+}
+
+impl ExtraFeed {
+    /// Whether a page matches the taxonomy and glob filters. The section filter needs the library
+    /// and is handled by the site.
+    pub fn matches(&self, relative_path: &str, taxonomies: &HashMap<String, Vec<String>>) -> bool {
+        if let Some(ref matcher) = self.glob_matcher {
+            if !matcher.is_match(relative_path) {
+                return false;
+            }
+        }
+
+        let has_term = |taxonomy: &str, term: &str| {
+            taxonomies.get(taxonomy).map_or(false, |terms| terms.iter().any(|t| t == term))
+        };
+
+        if let (Some(taxonomy), Some(term)) = (&self.taxonomy, &self.term) {
+            if !has_term(taxonomy, term) {
+                return false;
+            }
+        }
+
+        if self.taxonomy_terms.is_empty() {
+            return true;
+        }
+        let mut terms = self.taxonomy_terms.iter();
+        match self.taxonomy_terms_match {
+            TermsMatch::Any => terms.any(|t| has_term(&t.taxonomy, &t.term)),
+            TermsMatch::All => terms.all(|t| has_term(&t.taxonomy, &t.term)),
+        }
+    }
+}
+
+impl Default for ExtraFeed {
+    fn default() -> ExtraFeed {
+        ExtraFeed {
+            path: String::new(),
+            section: None,
+            taxonomy: None,
+            term: None,
+            taxonomy_terms: Vec::new(),
+            taxonomy_terms_match: TermsMatch::Any,
+            glob: None,
+            glob_matcher: None,
+        }
+    }
+}
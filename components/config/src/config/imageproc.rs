@@ -0,0 +1,40 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageProc {
+    /// Where the processed images are written, relative to the `static` directory and to the
+    /// base URL. `processed_images` by default.
+    pub output_dir: String,
+    /// The largest width or height a resize operation can ask for. Unlimited by default.
+    pub max_dimension: Option<u32>,
+    /// Whether the operations going over `max_dimension` are scaled down to it with a warning
+    /// instead of failing the build. `false` by default.
+    pub clamp_to_max_dimension: bool,
+    /// Whether images can be resized to be bigger than the source. If `false`, the operations
+    /// are scaled down to the size of the source. `true` by default.
+    pub allow_upscale: bool,
+    /// Whether the filenames of the processed images start with the name of the source image
+    /// and the requested size, eg `photo_800x600_{hash}.jpg`, rather than being only a hash.
+    /// `false` by default.
+    pub readable_filenames: bool,
+}
+
+impl ImageProc {
+    /// The output directory without its leading and trailing slashes, eg `img/derived`
+    pub fn output_dir(&self) -> &str {
+        self.output_dir.trim_matches('/')
+    }
+}
+
+impl Default for ImageProc {
+    fn default() -> ImageProc {
+        ImageProc {
+            output_dir: "processed_images".to_string(),
+            max_dimension: None,
+            clamp_to_max_dimension: false,
+            allow_upscale: true,
+            readable_filenames: false,
+        }
+    }
+}
@@ -11,6 +11,9 @@ pub struct Language {
     pub feed: bool,
     /// Whether to generate search index for that language, defaults to `false`
     pub search: bool,
+    /// The base url to use for that language instead of the site one, eg when each language
+    /// is hosted on its own domain
+    pub base_url: Option<String>,
 }
 
 pub type TranslateTerm = HashMap<String, String>;
@@ -1,5 +1,22 @@
+use std::collections::HashMap;
+
 use serde_derive::{Deserialize, Serialize};
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkCheckerLevel {
+    /// External links are only checked by `zola check`, where a dead link is an error
+    Error,
+    /// External links are also checked by `zola build`, reporting dead links as warnings
+    Warn,
+}
+
+impl Default for LinkCheckerLevel {
+    fn default() -> Self {
+        LinkCheckerLevel::Error
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct LinkChecker {
@@ -7,10 +24,27 @@ pub struct LinkChecker {
     pub skip_prefixes: Vec<String>,
     /// Skip anchor checking for these URL prefixes
     pub skip_anchor_prefixes: Vec<String>,
+    /// Check all the internal links before rendering the markdown and report all the broken ones
+    /// at once instead of stopping at the first one
+    pub check_internal_links: bool,
+    /// The User-Agent to send when checking external links. Defaults to the name and version of
+    /// the link checker, eg `link_checker/0.1.0`
+    pub user_agent: Option<String>,
+    /// Additional headers to send when checking links to a given host, by host
+    pub headers: HashMap<String, HashMap<String, String>>,
+    /// How dead external links are reported outside of `zola check`, see `LinkCheckerLevel`
+    pub external_level: LinkCheckerLevel,
 }
 
 impl Default for LinkChecker {
     fn default() -> LinkChecker {
-        LinkChecker { skip_prefixes: Vec::new(), skip_anchor_prefixes: Vec::new() }
+        LinkChecker {
+            skip_prefixes: Vec::new(),
+            skip_anchor_prefixes: Vec::new(),
+            check_internal_links: false,
+            user_agent: None,
+            headers: HashMap::new(),
+            external_level: LinkCheckerLevel::default(),
+        }
     }
 }
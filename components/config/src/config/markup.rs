@@ -2,6 +2,7 @@ use serde_derive::{Deserialize, Serialize};
 use syntect::parsing::SyntaxSet;
 
 pub const DEFAULT_HIGHLIGHT_THEME: &str = "base16-ocean-dark";
+pub const DEFAULT_ANCHOR_SYMBOL: &str = "🔗";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
@@ -13,6 +14,9 @@ pub struct Markdown {
     pub highlight_theme: String,
     /// Whether to render emoji aliases (e.g.: :smile: => 😄) in the markdown files
     pub render_emoji: bool,
+    /// Whether to render the `$...$` and `$$...$$` LaTeX expressions of the markdown files
+    /// to MathML. Defaults to false
+    pub render_math: bool,
     /// Whether external links are to be opened in a new tab
     /// If this is true, a `rel="noopener"` will always automatically be added for security reasons
     pub external_links_target_blank: bool,
@@ -22,6 +26,11 @@ pub struct Markdown {
     pub external_links_no_referrer: bool,
     /// Whether smart punctuation is enabled (changing quotes, dashes, dots etc in their typographic form)
     pub smart_punctuation: bool,
+    /// The text of the anchor links inserted next to headings, can be HTML. Defaults to "🔗"
+    pub anchor_symbol: String,
+    /// The deepest heading level included in the table of contents, eg `2` for only `h1` and `h2`.
+    /// Defaults to all of them
+    pub toc_max_depth: Option<u32>,
 
     /// A list of directories to search for additional `.sublime-syntax` files in.
     pub extra_syntaxes: Vec<String>,
@@ -69,10 +78,13 @@ impl Default for Markdown {
             highlight_code: false,
             highlight_theme: DEFAULT_HIGHLIGHT_THEME.to_owned(),
             render_emoji: false,
+            render_math: false,
             external_links_target_blank: false,
             external_links_no_follow: false,
             external_links_no_referrer: false,
             smart_punctuation: false,
+            anchor_symbol: DEFAULT_ANCHOR_SYMBOL.to_owned(),
+            toc_max_depth: None,
             extra_syntaxes: vec![],
             extra_syntax_set: None,
         }
@@ -1,15 +1,24 @@
+pub mod build;
+pub mod feed;
+pub mod imageproc;
 pub mod languages;
 pub mod link_checker;
 pub mod markup;
+pub mod schema;
 pub mod search;
+pub mod serve;
+pub mod sitemap;
 pub mod slugify;
 pub mod taxonomies;
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as Json;
 use syntect::parsing::SyntaxSetBuilder;
 use toml::Value as Toml;
 
@@ -28,6 +37,42 @@ pub enum Mode {
     Check,
 }
 
+/// What is written at `{paginate_path}/1/` of paginated sections and taxonomy terms,
+/// the first pager being always rendered at the index of the section or term
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaginatePageOne {
+    /// A page redirecting to the index, the default
+    Redirect,
+    /// The same content as the index
+    Duplicate,
+}
+
+impl Default for PaginatePageOne {
+    fn default() -> Self {
+        PaginatePageOne::Redirect
+    }
+}
+
+/// The line endings the rendered files can be normalized to
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Replaces every line ending of the text with this one
+    pub fn normalize(self, text: &str) -> String {
+        let lf = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => lf,
+            LineEnding::Crlf => lf.replace('\n', "\r\n"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
@@ -36,6 +81,8 @@ pub struct Config {
 
     /// Theme to use
     pub theme: Option<String>,
+    /// If set, the SHA-256 the theme directory must have, see `site::compute_theme_hash`
+    pub theme_expected_hash: Option<String>,
     /// Title of the site. Defaults to None
     pub title: Option<String>,
     /// Description of the site
@@ -45,6 +92,16 @@ pub struct Config {
     pub default_language: String,
     /// The list of supported languages outside of the default one
     pub languages: Vec<languages::Language>,
+    /// If set, the directory inside `content` holding the content of each language, eg
+    /// `{en = "en", fr = "fr"}`. The language of a file is then the one of the directory it is
+    /// in rather than the one in its filename. Every language needs a directory if set.
+    pub language_content_dirs: HashMap<String, String>,
+    /// Whether pages missing in a language are rendered at their translated URL with the
+    /// content of the default language, `is_fallback` being set on them. `false` by default
+    pub translation_fallback: bool,
+    /// The timezone of the dates without an offset, eg `Europe/Paris`, used in feeds and
+    /// the sitemap. Those dates are considered to be in UTC if it isn't set
+    pub timezone: Option<String>,
 
     /// Languages list and translated strings
     ///
@@ -68,15 +125,55 @@ pub struct Config {
     /// The filename to use for feeds. Used to find the template, too.
     /// Defaults to "atom.xml", with "rss.xml" also having a template provided out of the box.
     pub feed_filename: String,
+    /// Additional feed options, see `feed::Feed`
+    pub feed: feed::Feed,
+    /// Feeds of a subset of the pages written at custom paths, see `feed::ExtraFeed`
+    pub extra_feeds: Vec<feed::ExtraFeed>,
+    /// Sitemap options, see `sitemap::Sitemap`
+    pub sitemap: sitemap::Sitemap,
+    /// Image processing options, see `imageproc::ImageProc`
+    pub imageproc: imageproc::ImageProc,
+    /// Build options, see `build::Build`
+    pub build: build::Build,
     /// If set, files from static/ will be hardlinked instead of copied to the output dir.
     pub hard_link_static: bool,
+    /// If set, colocated assets that are byte-identical to one already written are hard-linked
+    /// to it instead of being copied again. Only applies to `zola build`.
+    pub dedupe_assets: bool,
 
     pub taxonomies: Vec<taxonomies::Taxonomy>,
+    /// What to do when different terms of a taxonomy have the same slug, a warning by default
+    pub term_slug_collisions: taxonomies::TermSlugCollisions,
 
     /// Whether to compile the `sass` directory and output the css files into the static folder
     pub compile_sass: bool,
     /// Whether to minify the html output
     pub minify_html: bool,
+    /// The line endings to convert the rendered files to, if any. Left as they are by default.
+    pub normalize_line_endings: Option<LineEnding>,
+    /// Whether pages are written as `about/index.html` and linked as `/about/`. When `false`,
+    /// they are written as `about.html` and linked as `/about.html` instead. Only pages are
+    /// affected: sections and taxonomies always use `index.html` files. Defaults to true.
+    pub pretty_urls: bool,
+    /// Whether to write the aliases of pages and sections in a `_redirects` file, in the format
+    /// read by Netlify and Cloudflare Pages, instead of HTML redirect pages
+    pub aliases_as_redirects_file: bool,
+    /// Raw HTML inserted before the `</head>` of every rendered HTML page, eg analytics or meta tags
+    pub extra_head: Option<String>,
+    /// Raw HTML inserted before the `</body>` of every rendered HTML page
+    pub extra_body_end: Option<String>,
+    /// What to write at `{paginate_path}/1/` of paginated sections and terms, a redirect to the
+    /// index by default
+    pub paginate_page_one: PaginatePageOne,
+    /// Whether to write a `_headers` file with preload hints for the stylesheets and scripts of
+    /// every page, in the format read by Netlify and Cloudflare Pages
+    pub generate_headers: bool,
+    /// Whether to give the list of every page, with its title and permalink, to the 404 template
+    /// as `pages`, eg for "did you mean" suggestions. `false` by default.
+    pub pages_in_404: bool,
+    /// Whether to create an index section for the site, and for each language, when the
+    /// corresponding `_index.md` doesn't exist. `true` by default.
+    pub auto_index_section: bool,
     /// Whether to build the search index for the content
     pub build_search_index: bool,
     /// A list of file glob patterns to ignore when processing the content folder. Defaults to none.
@@ -85,6 +182,10 @@ pub struct Config {
     pub ignored_content: Vec<String>,
     #[serde(skip_serializing, skip_deserializing)] // not a typo, 2 are needed
     pub ignored_content_globset: Option<GlobSet>,
+    /// Whether each pattern of `ignored_content_globset` is a negation (`!pattern`),
+    /// in the same order as the patterns
+    #[serde(skip_serializing, skip_deserializing)]
+    ignored_content_negated: Vec<bool>,
 
     /// The mode Zola is currently being ran on. Some logging/feature can differ depending on the
     /// command being used.
@@ -94,8 +195,16 @@ pub struct Config {
     /// A list of directories to search for additional `.sublime-syntax` files in.
     pub extra_syntaxes: Vec<String>,
 
+    /// Directories of templates shared with other sites, relative to the site directory.
+    /// The site templates override them and later directories override earlier ones.
+    pub extra_template_dirs: Vec<String>,
+
     pub output_dir: String,
 
+    /// Top-level entries of the output directory that are kept when cleaning it before a build,
+    /// eg `.git` or `CNAME`. Defaults to none, meaning the whole directory is deleted.
+    pub preserve_on_clean: Vec<String>,
+
     pub link_checker: link_checker::LinkChecker,
 
     /// The setup for which slugification strategies to use for paths, taxonomies and anchors
@@ -107,8 +216,20 @@ pub struct Config {
     /// The config for the Markdown rendering: syntax highlighting and everything
     pub markdown: markup::Markdown,
 
+    /// The options only used by `zola serve`
+    pub serve: serve::Serve,
+
+    /// Front matter schemas, keyed by the path of the section in the `content` directory,
+    /// eg `blog` or `docs/api`. The pages of those sections are validated against it.
+    pub schema: HashMap<String, schema::Schema>,
+
     /// All user params set in [extra] in the config
     pub extra: HashMap<String, Toml>,
+
+    /// The parsed content of the `data` directory, keyed by filename.
+    /// Filled by the site when loading and available as `data` in templates.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub data: HashMap<String, Json>,
 }
 
 impl Config {
@@ -132,24 +253,122 @@ impl Config {
             bail!("Default language `{}` should not appear both in `config.default_language` and `config.languages`", config.default_language)
         }
 
+        if !config.language_content_dirs.is_empty() {
+            let mut codes = config.languages_codes();
+            codes.push(&config.default_language);
+            for code in &codes {
+                if !config.language_content_dirs.contains_key(*code) {
+                    bail!("The language `{}` has no directory in `language_content_dirs`", code);
+                }
+            }
+            let mut dirs = Vec::new();
+            for (code, dir) in &config.language_content_dirs {
+                if !codes.contains(&code.as_str()) {
+                    bail!(
+                        "`language_content_dirs` has a directory for `{}` which isn't present in the config.toml `languages`",
+                        code
+                    );
+                }
+                let dir = dir.trim_matches('/');
+                if dir.is_empty() || dir.contains('/') || dirs.contains(&dir) {
+                    bail!(
+                        "The directory `{}` of the language `{}` in `language_content_dirs` needs to be a distinct directory directly in `content`",
+                        dir,
+                        code
+                    );
+                }
+                dirs.push(dir);
+            }
+            config.language_content_dirs = config
+                .language_content_dirs
+                .iter()
+                .map(|(code, dir)| (code.clone(), dir.trim_matches('/').to_string()))
+                .collect();
+        }
+
+        if let Some(ref timezone) = config.timezone {
+            if timezone.parse::<Tz>().is_err() {
+                bail!("Unknown timezone `{}` in config.toml `timezone`", timezone);
+            }
+        }
+
         if !config.ignored_content.is_empty() {
             // Convert the file glob strings into a compiled glob set matcher. We want to do this once,
             // at program initialization, rather than for every page, for example. We arrange for the
             // globset matcher to always exist (even though it has to be an inside an Option at the
             // moment because of the TOML serializer); if the glob set is empty the `is_match` function
             // of the globber always returns false.
+            // Like in a `.gitignore`, patterns starting with `!` re-include what an earlier
+            // pattern ignored so we keep track of which ones are negated.
             let mut glob_set_builder = GlobSetBuilder::new();
             for pat in &config.ignored_content {
-                let glob = match Glob::new(pat) {
+                let negated = pat.starts_with('!');
+                let glob = match Glob::new(pat.trim_start_matches('!')) {
                     Ok(g) => g,
                     Err(e) => bail!("Invalid ignored_content glob pattern: {}, error = {}", pat, e),
                 };
                 glob_set_builder.add(glob);
+                config.ignored_content_negated.push(negated);
             }
             config.ignored_content_globset =
                 Some(glob_set_builder.build().expect("Bad ignored_content in config file."));
         }
 
+        for extra_feed in config.extra_feeds.iter_mut() {
+            extra_feed.path = extra_feed.path.trim_matches('/').to_string();
+            if extra_feed.path.is_empty() {
+                bail!("An extra feed needs a non-empty `path`");
+            }
+            if extra_feed.taxonomy.is_some() != extra_feed.term.is_some() {
+                bail!(
+                    "The extra feed at `{}` needs both `taxonomy` and `term` to filter by taxonomy",
+                    extra_feed.path
+                );
+            }
+            if let Some(ref pat) = extra_feed.glob {
+                match Glob::new(pat) {
+                    Ok(g) => extra_feed.glob_matcher = Some(g.compile_matcher()),
+                    Err(e) => bail!(
+                        "Invalid glob pattern for the extra feed at `{}`: {}, error = {}",
+                        extra_feed.path,
+                        pat,
+                        e
+                    ),
+                }
+            }
+        }
+
+        if config.sitemap.max_entries == 0 {
+            bail!("`sitemap.max_entries` needs to be greater than 0");
+        }
+
+        if let Some(priority) = config.sitemap.priority {
+            if !(0.0..=1.0).contains(&priority) {
+                bail!("`sitemap.priority` needs to be between 0.0 and 1.0");
+            }
+        }
+
+        if config.search.opensearch {
+            match config.search.opensearch_url {
+                Some(ref url) if url.contains("{searchTerms}") => (),
+                _ => bail!(
+                    "`search.opensearch` needs a `search.opensearch_url` containing `{{searchTerms}}`"
+                ),
+            }
+        }
+
+        if config.build.threads == Some(0) {
+            bail!("`build.threads` needs to be greater than 0");
+        }
+
+        let imageproc_dir = config.imageproc.output_dir();
+        if imageproc_dir.is_empty() || imageproc_dir.split('/').any(|c| c.is_empty() || c == "..") {
+            bail!("`imageproc.output_dir` needs to be a non-empty path without `..` in it");
+        }
+        if config.imageproc.max_dimension == Some(0) {
+            bail!("`imageproc.max_dimension` needs to be greater than 0");
+        }
+
         for taxonomy in config.taxonomies.iter_mut() {
             if taxonomy.lang.is_empty() {
                 taxonomy.lang = config.default_language.clone();
@@ -174,7 +393,11 @@ impl Config {
             path,
             &format!("No `{:?}` file found. Are you in the right directory?", file_name),
         )?;
-        Config::parse(&content)
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        match resolve_includes(&content, base_dir)? {
+            Some(merged) => Config::parse(&merged),
+            None => Config::parse(&content),
+        }
     }
 
     /// Temporary, while we have the settings in 2 places
@@ -231,27 +454,79 @@ impl Config {
         Ok(())
     }
 
+    /// Returns the base url to use for the given language: its own `base_url` if it has one,
+    /// the site one otherwise
+    pub fn base_url_for_lang(&self, lang: &str) -> &str {
+        self.languages
+            .iter()
+            .find(|l| l.code == lang)
+            .and_then(|l| l.base_url.as_deref())
+            .unwrap_or(&self.base_url)
+    }
+
+    /// Returns the given front matter date in RFC 3339, using the configured `timezone` for the
+    /// dates without an offset.
+    /// Dates that already have an offset, or all dates if no timezone is set, are returned as is.
+    pub fn localize_date(&self, date: &str) -> String {
+        let tz = match self.timezone.as_ref().and_then(|t| t.parse::<Tz>().ok()) {
+            Some(tz) => tz,
+            None => return date.to_string(),
+        };
+        if DateTime::parse_from_rfc3339(date).is_ok() {
+            return date.to_string();
+        }
+
+        let naive = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f")
+            .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d").map(|d| d.and_hms(0, 0, 0)));
+        match naive.ok().and_then(|n| tz.from_local_datetime(&n).earliest()) {
+            Some(dt) => dt.to_rfc3339(),
+            None => date.to_string(),
+        }
+    }
+
+    /// Whether the given path is excluded by `ignored_content`.
+    /// Patterns are matched against the whole path and the last matching pattern wins:
+    /// a `!` pattern re-includes files ignored by an earlier one, like in a `.gitignore`.
+    pub fn is_ignored_content(&self, path: &Path) -> bool {
+        match self.ignored_content_globset {
+            Some(ref gs) => match gs.matches(path).into_iter().max() {
+                Some(index) => !self.ignored_content_negated[index],
+                None => false,
+            },
+            None => false,
+        }
+    }
+
     /// Makes a url, taking into account that the base url might have a trailing slash
     pub fn make_permalink(&self, path: &str) -> String {
-        let trailing_bit =
-            if path.ends_with('/') || path.ends_with(&self.feed_filename) || path.is_empty() {
-                ""
-            } else {
-                "/"
-            };
+        self.make_permalink_for_lang(path, &self.default_language)
+    }
+
+    /// Same as `make_permalink` but uses the base url of the given language
+    pub fn make_permalink_for_lang(&self, path: &str, lang: &str) -> String {
+        let base_url = self.base_url_for_lang(lang);
+        let trailing_bit = if path.ends_with('/')
+            || path.ends_with(&self.feed_filename)
+            || path.is_empty()
+            || (!self.pretty_urls && path.ends_with(".html"))
+        {
+            ""
+        } else {
+            "/"
+        };
 
         // Index section with a base url that has a trailing slash
-        if self.base_url.ends_with('/') && path == "/" {
-            self.base_url.clone()
+        if base_url.ends_with('/') && path == "/" {
+            base_url.to_string()
         } else if path == "/" {
             // index section with a base url that doesn't have a trailing slash
-            format!("{}/", self.base_url)
-        } else if self.base_url.ends_with('/') && path.starts_with('/') {
-            format!("{}{}{}", self.base_url, &path[1..], trailing_bit)
-        } else if self.base_url.ends_with('/') || path.starts_with('/') {
-            format!("{}{}{}", self.base_url, path, trailing_bit)
+            format!("{}/", base_url)
+        } else if base_url.ends_with('/') && path.starts_with('/') {
+            format!("{}{}{}", base_url, &path[1..], trailing_bit)
+        } else if base_url.ends_with('/') || path.starts_with('/') {
+            format!("{}{}{}", base_url, path, trailing_bit)
         } else {
-            format!("{}/{}{}", self.base_url, path, trailing_bit)
+            format!("{}/{}{}", base_url, path, trailing_bit)
         }
     }
 
@@ -285,6 +560,26 @@ impl Config {
         self.languages.iter().map(|l| l.code.as_ref()).collect()
     }
 
+    /// Is the content of each language in its own directory, see `language_content_dirs`?
+    pub fn uses_language_content_dirs(&self) -> bool {
+        !self.language_content_dirs.is_empty()
+    }
+
+    /// The language whose content is in the given directory of `content`, if any
+    pub fn language_of_content_dir(&self, dir: &str) -> Option<&str> {
+        self.language_content_dirs.iter().find(|(_, d)| *d == dir).map(|(code, _)| code.as_str())
+    }
+
+    /// The filename of the sections in the given language: `_index.md` for the default language
+    /// or with `language_content_dirs`, `_index.{lang}.md` otherwise
+    pub fn section_filename(&self, lang: &str) -> String {
+        if self.uses_language_content_dirs() || lang == self.default_language {
+            "_index.md".to_string()
+        } else {
+            format!("_index.{}.md", lang)
+        }
+    }
+
     pub fn is_in_build_mode(&self) -> bool {
         self.mode == Mode::Build
     }
@@ -361,30 +656,114 @@ impl Default for Config {
             title: None,
             description: None,
             theme: None,
+            theme_expected_hash: None,
             highlight_code: false,
             highlight_theme: "base16-ocean-dark".to_string(),
             default_language: "en".to_string(),
             languages: Vec::new(),
+            language_content_dirs: HashMap::new(),
+            translation_fallback: false,
+            timezone: None,
             generate_feed: false,
             feed_limit: None,
             feed_filename: "atom.xml".to_string(),
+            feed: feed::Feed::default(),
+            extra_feeds: Vec::new(),
+            sitemap: sitemap::Sitemap::default(),
+            imageproc: imageproc::ImageProc::default(),
+            build: build::Build::default(),
             hard_link_static: false,
+            dedupe_assets: false,
             taxonomies: Vec::new(),
+            term_slug_collisions: taxonomies::TermSlugCollisions::default(),
             compile_sass: false,
             minify_html: false,
+            normalize_line_endings: None,
+            pretty_urls: true,
             mode: Mode::Build,
+            aliases_as_redirects_file: false,
+            paginate_page_one: PaginatePageOne::default(),
+            extra_head: None,
+            extra_body_end: None,
+            generate_headers: false,
+            pages_in_404: false,
+            auto_index_section: true,
             build_search_index: false,
             ignored_content: Vec::new(),
             ignored_content_globset: None,
+            ignored_content_negated: Vec::new(),
             translations: HashMap::new(),
             extra_syntaxes: Vec::new(),
+            extra_template_dirs: Vec::new(),
             output_dir: "public".to_string(),
+            preserve_on_clean: Vec::new(),
             link_checker: link_checker::LinkChecker::default(),
             slugify: slugify::Slugify::default(),
             search: search::Search::default(),
             markdown: markup::Markdown::default(),
+            serve: serve::Serve::default(),
+            schema: HashMap::new(),
             extra: HashMap::new(),
+            data: HashMap::new(),
+        }
+    }
+}
+
+/// Merges `other` into `base`, recursing into tables present in both
+fn merge_toml(base: &mut Toml, other: Toml) {
+    match (base, other) {
+        (Toml::Table(base), Toml::Table(other)) => {
+            for (key, value) in other {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, other) => *base = other,
+    }
+}
+
+/// Merges the files listed in the `include` array of a config file, relative to `base_dir`.
+/// Later files override earlier ones and the config file itself overrides all of them.
+/// Returns `None` if the config file doesn't include anything.
+fn resolve_includes(content: &str, base_dir: &Path) -> Result<Option<String>> {
+    let mut main: Toml = match toml::from_str(content) {
+        Ok(c) => c,
+        Err(e) => bail!(e),
+    };
+    let includes = match main.as_table_mut().and_then(|t| t.remove("include")) {
+        Some(Toml::Array(includes)) => includes,
+        Some(_) => bail!("`include` in the config needs to be an array of paths"),
+        None => return Ok(None),
+    };
+
+    let mut merged = Toml::Table(toml::map::Map::new());
+    for include in includes {
+        let include = match include.as_str() {
+            Some(i) => i.to_string(),
+            None => bail!("`include` in the config needs to be an array of paths"),
+        };
+        let path = base_dir.join(&include);
+        let included: Toml = match toml::from_str(&read_file_with_error(
+            &path,
+            &format!("Config file `{}` included in the config not found", include),
+        )?) {
+            Ok(c) => c,
+            Err(e) => bail!("Failed to parse `{}` included in the config: {}", include, e),
+        };
+        if included.get("include").is_some() {
+            bail!("`{}` is included in the config and can't include other files itself", include);
         }
+        merge_toml(&mut merged, included);
+    }
+    merge_toml(&mut merged, main);
+
+    match toml::to_string(&merged) {
+        Ok(s) => Ok(Some(s)),
+        Err(e) => bail!(e),
     }
 }
 
@@ -476,6 +855,23 @@ hello = "world"
         assert_eq!(config.make_permalink("/tags/rust"), "http://127.0.0.1:1111/tags/rust/");
     }
 
+    #[test]
+    fn can_make_url_with_language_base_url() {
+        let mut config = Config::default();
+        config.base_url = "http://vincent.is/".to_string();
+        config.languages.push(languages::Language {
+            code: "fr".to_string(),
+            base_url: Some("http://vincent.fr".to_string()),
+            ..languages::Language::default()
+        });
+        assert_eq!(
+            config.make_permalink_for_lang("/fr/hello", "fr"),
+            "http://vincent.fr/fr/hello/"
+        );
+        assert_eq!(config.make_permalink_for_lang("/hello", "en"), "http://vincent.is/hello/");
+        assert_eq!(config.make_permalink("/hello"), "http://vincent.is/hello/");
+    }
+
     // https://github.com/Keats/gutenberg/issues/486
     #[test]
     fn doesnt_add_trailing_slash_to_feed() {
@@ -610,6 +1006,21 @@ ignored_content = ["*.{graphml,iso}", "*.py?"]
         assert!(!g.is_match("foo.py"));
     }
 
+    #[test]
+    fn can_re_include_ignored_content_with_negated_patterns() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+ignored_content = ["*/drafts/**", "!*/drafts/keep.md"]
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.is_ignored_content(Path::new("/site/content/drafts/wip.md")));
+        assert!(config.is_ignored_content(Path::new("/site/content/drafts/nested/wip.md")));
+        assert!(!config.is_ignored_content(Path::new("/site/content/drafts/keep.md")));
+        assert!(!config.is_ignored_content(Path::new("/site/content/posts/wip.md")));
+    }
+
     #[test]
     fn link_checker_skip_anchor_prefixes() {
         let config_str = r#"
@@ -650,6 +1061,128 @@ skip_prefixes = [
         );
     }
 
+    #[test]
+    fn can_enable_default_language_feed_in_subdir() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[feed]
+default_language_in_subdir = true
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert!(config.feed.default_language_in_subdir);
+        assert_eq!(config.feed.min_items, 1);
+    }
+
+    #[test]
+    fn can_localize_dates_with_timezone() {
+        let mut config = Config::default();
+        assert_eq!(config.localize_date("2021-01-05"), "2021-01-05");
+
+        config.timezone = Some("Europe/Paris".to_string());
+        assert_eq!(config.localize_date("2021-01-05"), "2021-01-05T00:00:00+01:00");
+        assert_eq!(config.localize_date("2021-07-05T10:30:00"), "2021-07-05T10:30:00+02:00");
+        // Dates with an offset are left alone
+        assert_eq!(config.localize_date("2021-07-05T10:30:00Z"), "2021-07-05T10:30:00Z");
+        assert_eq!(config.localize_date("2021-07-05T10:30:00-05:00"), "2021-07-05T10:30:00-05:00");
+    }
+
+    #[test]
+    fn errors_on_unknown_timezone() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+timezone = "Europe/Nowhere"
+        "#;
+
+        let config = Config::parse(config_str);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn can_set_link_checker_headers() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[link_checker]
+user_agent = "my-checker"
+
+[link_checker.headers."api.example.com"]
+Authorization = "Bearer abc"
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert_eq!(config.link_checker.user_agent, Some("my-checker".to_string()));
+        assert_eq!(
+            config.link_checker.headers["api.example.com"]["Authorization"],
+            "Bearer abc".to_string()
+        );
+    }
+
+    #[test]
+    fn can_make_permalink_without_pretty_urls() {
+        let mut config = Config::default();
+        config.base_url = "http://vincent.is/".to_string();
+        config.pretty_urls = false;
+        assert_eq!(config.make_permalink("/about.html"), "http://vincent.is/about.html");
+        assert_eq!(config.make_permalink("/posts/"), "http://vincent.is/posts/");
+    }
+
+    #[test]
+    fn can_parse_extra_feeds() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[[extra_feeds]]
+path = "/rust/"
+taxonomy = "tags"
+term = "rust"
+glob = "posts/**"
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        let extra_feed = &config.extra_feeds[0];
+        assert_eq!(extra_feed.path, "rust");
+        let mut taxonomies = HashMap::new();
+        taxonomies.insert("tags".to_string(), vec!["rust".to_string()]);
+        assert!(extra_feed.matches("posts/hello.md", &taxonomies));
+        assert!(!extra_feed.matches("pages/hello.md", &taxonomies));
+        assert!(!extra_feed.matches("posts/hello.md", &HashMap::new()));
+    }
+
+    #[test]
+    fn errors_on_extra_feed_with_taxonomy_but_no_term() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[[extra_feeds]]
+path = "rust"
+taxonomy = "tags"
+        "#;
+
+        let config = Config::parse(config_str);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn can_set_livereload_url() {
+        let config_str = r#"
+title = "My site"
+base_url = "example.com"
+
+[serve]
+livereload_url = "http://192.168.1.10:1111"
+        "#;
+
+        let config = Config::parse(config_str).unwrap();
+        assert_eq!(config.serve.livereload_url, Some("http://192.168.1.10:1111".to_string()));
+    }
+
     #[test]
     fn slugify_strategies() {
         let config_str = r#"
@@ -724,4 +1257,152 @@ output_dir = "docs"
         let config = Config::parse(config).unwrap();
         assert_eq!(config.output_dir, "docs".to_string());
     }
+
+    #[test]
+    fn can_use_language_content_dirs() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+languages = [{ code = "fr" }]
+
+[language_content_dirs]
+en = "en/"
+fr = "francais"
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        assert!(config.uses_language_content_dirs());
+        assert_eq!(config.language_of_content_dir("en"), Some("en"));
+        assert_eq!(config.language_of_content_dir("francais"), Some("fr"));
+        assert_eq!(config.language_of_content_dir("fr"), None);
+        assert_eq!(config.section_filename("fr"), "_index.md");
+    }
+
+    #[test]
+    fn errors_on_language_without_content_dir() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+languages = [{ code = "fr" }]
+
+[language_content_dirs]
+fr = "fr"
+        "#;
+
+        let config = Config::parse(config);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn errors_on_sitemap_priority_out_of_range() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[sitemap]
+priority = 1.2
+        "#;
+
+        let config = Config::parse(config);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn can_set_imageproc_output_dir() {
+        let config = Config::parse(
+            "base_url = \"https://example.com\"\n[imageproc]\noutput_dir = \"/img/derived/\"",
+        )
+        .unwrap();
+        assert_eq!(config.imageproc.output_dir(), "img/derived");
+        assert_eq!(Config::default().imageproc.output_dir(), "processed_images");
+
+        for dir in &["/", "../img", "img//derived"] {
+            let config = format!(
+                "base_url = \"https://example.com\"\n[imageproc]\noutput_dir = \"{}\"",
+                dir
+            );
+            assert!(Config::parse(&config).is_err());
+        }
+    }
+
+    #[test]
+    fn can_include_other_config_files() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = tmp_dir.path();
+        std::fs::write(
+            dir.join("languages.toml"),
+            "default_language = \"fr\"\n[extra]\nauthor = \"Included\"\nlicense = \"MIT\"",
+        )
+        .unwrap();
+        std::fs::write(dir.join("extra.toml"), "[extra]\nlicense = \"CC-BY\"\nyear = 2020")
+            .unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            r#"
+base_url = "https://example.com"
+include = ["languages.toml", "extra.toml"]
+
+[extra]
+author = "Main"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(dir.join("config.toml")).unwrap();
+        assert_eq!(config.default_language, "fr");
+        // The config file overrides the included files and later files override earlier ones
+        assert_eq!(config.extra["author"].as_str(), Some("Main"));
+        assert_eq!(config.extra["license"].as_str(), Some("CC-BY"));
+        assert_eq!(config.extra["year"].as_integer(), Some(2020));
+
+        std::fs::write(
+            dir.join("config.toml"),
+            "base_url = \"https://example.com\"\ninclude = [\"nope.toml\"]",
+        )
+        .unwrap();
+        assert!(Config::from_file(dir.join("config.toml")).is_err());
+    }
+
+    #[test]
+    fn errors_on_opensearch_without_search_terms() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[search]
+opensearch = true
+        "#;
+        assert!(Config::parse(config).is_err());
+
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[search]
+opensearch = true
+opensearch_url = "https://replace-this-with-your-url.com/search"
+        "#;
+        assert!(Config::parse(config).is_err());
+    }
+
+    #[test]
+    fn errors_on_zero_build_threads() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+
+[build]
+threads = 0
+        "#;
+
+        assert!(Config::parse(config).is_err());
+    }
+
+    #[test]
+    fn can_normalize_line_endings() {
+        let config = r#"
+base_url = "https://replace-this-with-your-url.com"
+normalize_line_endings = "crlf"
+        "#;
+
+        let config = Config::parse(config).unwrap();
+        let line_ending = config.normalize_line_endings.unwrap();
+        assert_eq!(line_ending, LineEnding::Crlf);
+        assert_eq!(line_ending.normalize("a\nb\r\nc"), "a\r\nb\r\nc");
+        assert_eq!(LineEnding::Lf.normalize("a\nb\r\nc"), "a\nb\nc");
+    }
 }
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+/// The types a front matter field can be required to have
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    /// A TOML date/datetime or a string in one of the formats accepted for `date`
+    Date,
+    Array,
+    Table,
+}
+
+impl FieldType {
+    /// Whether the given front matter value is of that type
+    pub fn matches(self, value: &Json) -> bool {
+        match (self, value) {
+            (FieldType::String, Json::String(_)) => true,
+            (FieldType::Integer, Json::Number(n)) => n.is_i64() || n.is_u64(),
+            (FieldType::Float, Json::Number(_)) => true,
+            (FieldType::Boolean, Json::Bool(_)) => true,
+            (FieldType::Date, Json::String(s)) => {
+                DateTime::parse_from_rfc3339(s).is_ok()
+                    || NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").is_ok()
+                    || NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()
+            }
+            (FieldType::Array, Json::Array(_)) => true,
+            (FieldType::Table, Json::Object(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            FieldType::String => "string",
+            FieldType::Integer => "integer",
+            FieldType::Float => "float",
+            FieldType::Boolean => "boolean",
+            FieldType::Date => "date",
+            FieldType::Array => "array",
+            FieldType::Table => "table",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What the front matter of the pages of a section must contain
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schema {
+    /// Fields every page must set. Fields not known by Zola are looked up in `extra`.
+    pub required: Vec<String>,
+    /// The expected type of some fields, only checked when they are present
+    pub types: HashMap<String, FieldType>,
+}
+
+impl Default for Schema {
+    fn default() -> Schema {
+        Schema { required: Vec::new(), types: HashMap::new() }
+    }
+}
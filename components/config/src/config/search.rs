@@ -13,6 +13,15 @@ pub struct Search {
     /// Includes the description in the search index. When the site becomes too large, you can switch
     /// to that instead. `false` by default
     pub include_description: bool,
+    /// Also writes a gzipped copy of each search index, `search_index.{lang}.js.gz`, for servers
+    /// that can serve precompressed files. `false` by default
+    pub gzip_index: bool,
+    /// Renders an `opensearch.xml` description document so browsers can search the site from
+    /// their address bar. `false` by default
+    pub opensearch: bool,
+    /// The URL of the search results, with `{searchTerms}` where the query goes.
+    /// Required if `opensearch` is set
+    pub opensearch_url: Option<String>,
 }
 
 impl Default for Search {
@@ -22,6 +31,9 @@ impl Default for Search {
             include_content: true,
             include_description: false,
             truncate_content_length: None,
+            gzip_index: false,
+            opensearch: false,
+            opensearch_url: None,
         }
     }
 }
@@ -0,0 +1,15 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Serve {
+    /// Where the browser should load `livereload.js` from, eg `http://192.168.1.10:1111`.
+    /// The websocket connects to the same host. Defaults to the origin of the page being served.
+    pub livereload_url: Option<String>,
+}
+
+impl Default for Serve {
+    fn default() -> Serve {
+        Serve { livereload_url: None }
+    }
+}
@@ -0,0 +1,25 @@
+use serde_derive::{Deserialize, Serialize};
+
+use utils::sitemap::ChangeFreq;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sitemap {
+    /// List the images colocated with a page as image sitemap extensions of its entry.
+    /// `false` by default.
+    pub include_images: bool,
+    /// How many entries a sitemap can have before being split in several files.
+    /// 30000 by default.
+    pub max_entries: usize,
+    /// The `<priority>` of every entry, between 0.0 and 1.0, unless a page overrides it.
+    /// Not set by default.
+    pub priority: Option<f64>,
+    /// The `<changefreq>` of every entry unless a page overrides it. Not set by default.
+    pub changefreq: Option<ChangeFreq>,
+}
+
+impl Default for Sitemap {
+    fn default() -> Sitemap {
+        Sitemap { include_images: false, max_entries: 30000, priority: None, changefreq: None }
+    }
+}
@@ -1,5 +1,38 @@
 use serde_derive::{Deserialize, Serialize};
 
+/// How to order the terms of a taxonomy
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxonomySortBy {
+    /// Alphabetically, the default
+    Name,
+    /// By number of pages, the most used terms first
+    Count,
+}
+
+impl Default for TaxonomySortBy {
+    fn default() -> Self {
+        TaxonomySortBy::Name
+    }
+}
+
+/// What to do when different terms of a taxonomy have the same slug, eg `Rust` and `rust`,
+/// in which case they are merged in a single term
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TermSlugCollisions {
+    /// Show a warning, the default
+    Warn,
+    /// Fail the build
+    Error,
+}
+
+impl Default for TermSlugCollisions {
+    fn default() -> Self {
+        TermSlugCollisions::Warn
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Taxonomy {
@@ -14,6 +47,8 @@ pub struct Taxonomy {
     /// The language for that taxonomy, only used in multilingual sites.
     /// Defaults to the config `default_language` if not set
     pub lang: String,
+    /// How to order the terms, defaults to alphabetically
+    pub sort_by: TaxonomySortBy,
 }
 
 impl Taxonomy {
@@ -2,7 +2,13 @@ mod config;
 pub mod highlighting;
 mod theme;
 pub use crate::config::{
-    languages::Language, link_checker::LinkChecker, slugify::Slugify, taxonomies::Taxonomy, Config,
+    feed::{ExtraFeed, SortTiesBy, TaxonomyTerm, TermsMatch},
+    languages::Language,
+    link_checker::{LinkChecker, LinkCheckerLevel},
+    schema::{FieldType, Schema},
+    slugify::Slugify,
+    taxonomies::{Taxonomy, TaxonomySortBy, TermSlugCollisions},
+    Config, LineEnding, PaginatePageOne,
 };
 
 use std::path::Path;
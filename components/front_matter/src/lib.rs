@@ -11,7 +11,7 @@ mod page;
 mod section;
 
 pub use page::PageFrontMatter;
-pub use section::SectionFrontMatter;
+pub use section::{Cascade, SectionFrontMatter};
 
 lazy_static! {
     static ref TOML_RE: Regex =
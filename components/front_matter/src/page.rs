@@ -6,8 +6,9 @@ use tera::{Map, Value};
 
 use errors::{bail, Result};
 use utils::de::{fix_toml_dates, from_toml_datetime};
+use utils::sitemap::ChangeFreq;
 
-use crate::RawFrontMatter;
+use crate::{Cascade, RawFrontMatter};
 
 /// The front matter of every page
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -38,6 +39,12 @@ pub struct PageFrontMatter {
     /// otherwise is set after parsing front matter and sections
     /// Can't be an empty string if present
     pub path: Option<String>,
+    /// The name of the file the page is written to instead of `index.html`, eg `amp.html`
+    /// Can't be empty or contain a `/` if present
+    pub output_filename: Option<String>,
+    /// Whether to also render an AMP version of the page with the `amp.html` template
+    #[serde(skip_serializing)]
+    pub amp: bool,
     pub taxonomies: HashMap<String, Vec<String>>,
     /// Integer to use to order content. Highest is at the bottom, lowest first
     pub weight: Option<usize>,
@@ -48,10 +55,23 @@ pub struct PageFrontMatter {
     /// Specify a template different from `page.html` to use for that page
     #[serde(skip_serializing)]
     pub template: Option<String>,
-    /// Whether the page is included in the search index
-    /// Defaults to `true` but is only used if search if explicitly enabled in the config.
+    /// Overrides `markdown.toc_max_depth` of the config for that page
+    #[serde(skip_serializing)]
+    pub toc_max_depth: Option<u32>,
+    /// Overrides `sitemap.priority` of the config for that page, between 0.0 and 1.0
+    #[serde(skip_serializing)]
+    pub sitemap_priority: Option<f64>,
+    /// Overrides `sitemap.changefreq` of the config for that page
+    #[serde(skip_serializing)]
+    pub sitemap_changefreq: Option<ChangeFreq>,
+    /// Whether the page is included in the search index, `true` if neither the page nor the
+    /// `cascade` of a parent section set it. Only used if search if explicitly enabled in the config.
+    #[serde(skip_serializing)]
+    pub in_search_index: Option<bool>,
+    /// Explicit translations of the page, as language code -> `@/` path of the translated page.
+    /// Overrides the translations found from the filenames when set.
     #[serde(skip_serializing)]
-    pub in_search_index: bool,
+    pub translations: HashMap<String, String>,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -86,6 +106,18 @@ impl PageFrontMatter {
             }
         }
 
+        if let Some(ref filename) = f.output_filename {
+            if filename == "" || filename.contains('/') {
+                bail!("`output_filename` can't be empty or contain a `/` if present")
+            }
+        }
+
+        if let Some(priority) = f.sitemap_priority {
+            if !(0.0..=1.0).contains(&priority) {
+                bail!("`sitemap_priority` needs to be between 0.0 and 1.0")
+            }
+        }
+
         f.extra = match fix_toml_dates(f.extra) {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in page extra"),
@@ -112,6 +144,19 @@ impl PageFrontMatter {
     pub fn weight(&self) -> usize {
         self.weight.unwrap()
     }
+
+    /// Fills the values the page didn't set with the ones from the `cascade` of a section
+    pub fn apply_cascade(&mut self, cascade: &Cascade) {
+        if self.template.is_none() {
+            self.template = cascade.template.clone();
+        }
+        if self.in_search_index.is_none() {
+            self.in_search_index = cascade.in_search_index;
+        }
+        for (key, value) in &cascade.extra {
+            self.extra.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
 }
 
 impl Default for PageFrontMatter {
@@ -126,11 +171,17 @@ impl Default for PageFrontMatter {
             draft: false,
             slug: None,
             path: None,
+            output_filename: None,
+            amp: false,
             taxonomies: HashMap::new(),
             weight: None,
             aliases: Vec::new(),
-            in_search_index: true,
+            in_search_index: None,
+            translations: HashMap::new(),
             template: None,
+            toc_max_depth: None,
+            sitemap_priority: None,
+            sitemap_changefreq: None,
             extra: Map::new(),
         }
     }
@@ -142,6 +193,7 @@ mod tests {
     use super::RawFrontMatter;
     use tera::to_value;
     use test_case::test_case;
+    use utils::sitemap::ChangeFreq;
 
     #[test_case(&RawFrontMatter::Toml(r#"  "#); "toml")]
     #[test_case(&RawFrontMatter::Toml(r#"  "#); "yaml")]
@@ -206,6 +258,35 @@ path: ""
 
     #[test_case(&RawFrontMatter::Toml(r#"
 title = "Hello"
+sitemap_priority = 0.8
+sitemap_changefreq = "weekly"
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+title: Hello
+sitemap_priority: 0.8
+sitemap_changefreq: weekly
+"#); "yaml")]
+    fn can_parse_sitemap_overrides(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content).unwrap();
+        assert_eq!(res.sitemap_priority, Some(0.8));
+        assert_eq!(res.sitemap_changefreq, Some(ChangeFreq::Weekly));
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+title = "Hello"
+sitemap_priority = 1.5
+"#); "toml")]
+    #[test_case(&RawFrontMatter::Yaml(r#"
+title: Hello
+sitemap_changefreq: sometimes
+"#); "yaml")]
+    fn errors_on_invalid_sitemap_overrides(content: &RawFrontMatter) {
+        let res = PageFrontMatter::parse(content);
+        assert!(res.is_err());
+    }
+
+    #[test_case(&RawFrontMatter::Toml(r#"
+title = "Hello"
 description = "hey there"
 date = 2016-10-10
 "#); "toml")]
@@ -9,6 +9,17 @@ use crate::RawFrontMatter;
 
 static DEFAULT_PAGINATE_PATH: &str = "page";
 
+/// Front matter values given by a section to its pages and the pages of its subsections
+/// when they don't set them themselves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cascade {
+    pub template: Option<String>,
+    pub in_search_index: Option<bool>,
+    /// Merged with the `extra` of the pages, the keys set by a page winning
+    pub extra: Map<String, Value>,
+}
+
 /// The front matter of every section
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
@@ -38,14 +49,25 @@ pub struct SectionFrontMatter {
     /// Path to be used by pagination: the page number will be appended after it. Defaults to `page`.
     #[serde(skip_serializing)]
     pub paginate_path: String,
+    /// Other page sizes to render an additional pagination for, under `{paginate_path}{size}`,
+    /// eg `page50/1/` for a size of 50. Defaults to none.
+    #[serde(skip_serializing)]
+    pub paginate_by_variants: Vec<usize>,
     /// Whether to insert a link for each header like the ones you can see in this site if you hover one
     /// The default template can be overridden by creating a `anchor-link.html` in the `templates` directory
     pub insert_anchor_links: InsertAnchor,
+    /// Overrides `markdown.toc_max_depth` of the config for that section
+    #[serde(skip_serializing)]
+    pub toc_max_depth: Option<u32>,
     /// Whether to render that section or not. Defaults to `true`.
     /// Useful when the section is only there to organize things but is not meant
     /// to be used directly, like a posts section in a personal site
     #[serde(skip_serializing)]
     pub render: bool,
+    /// Whether to write a minimal listing of the pages and subsections of the section at its URL
+    /// when it isn't rendered, with the `auto_list.html` template. Defaults to `false`.
+    #[serde(skip_serializing)]
+    pub auto_list: bool,
     /// Whether to redirect when landing on that section. Defaults to `None`.
     /// Useful for the same reason as `render` but when you don't want a 404 when
     /// landing on the root section page
@@ -60,9 +82,17 @@ pub struct SectionFrontMatter {
     /// sections for each year under a posts section.
     #[serde(skip_serializing)]
     pub transparent: bool,
+    /// Whether the section is listed in the `subsections` of its parent section.
+    /// Defaults to `true`, the section is rendered either way.
+    #[serde(skip_serializing)]
+    pub in_section_list: bool,
     /// Optional template for all pages in this section (including the pages of children section)
     #[serde(skip_serializing)]
     pub page_template: Option<String>,
+    /// Defaults for the front matter of the pages of the section and of its subsections.
+    /// The closest section wins if several of them set the same value.
+    #[serde(skip_serializing)]
+    pub cascade: Cascade,
     /// All aliases for that page. Zola will create HTML templates that will
     /// redirect to this
     #[serde(skip_serializing)]
@@ -70,6 +100,14 @@ pub struct SectionFrontMatter {
     /// Whether to generate a feed for the current section
     #[serde(skip_serializing)]
     pub generate_feed: bool,
+    /// Whether to render the content of all the pages of the section and of its subsections
+    /// in the section itself, like a book. Defaults to `false`.
+    #[serde(skip_serializing)]
+    pub single_page: bool,
+    /// Whether the pages of the section get their own page. Defaults to `true`.
+    /// Mostly useful with `single_page` when the pages are only meant to be read there.
+    #[serde(skip_serializing)]
+    pub render_pages: bool,
     /// Any extra parameter present in the front matter
     pub extra: Map<String, Value>,
 }
@@ -82,6 +120,10 @@ impl SectionFrontMatter {
             Value::Object(o) => o,
             _ => unreachable!("Got something other than a table in section extra"),
         };
+        f.cascade.extra = match fix_toml_dates(f.cascade.extra) {
+            Value::Object(o) => o,
+            _ => unreachable!("Got something other than a table in section cascade extra"),
+        };
 
         Ok(f)
     }
@@ -106,14 +148,21 @@ impl Default for SectionFrontMatter {
             paginate_by: None,
             paginate_reversed: false,
             paginate_path: DEFAULT_PAGINATE_PATH.to_string(),
+            paginate_by_variants: Vec::new(),
             render: true,
+            auto_list: false,
             redirect_to: None,
             insert_anchor_links: InsertAnchor::None,
+            toc_max_depth: None,
             in_search_index: true,
             transparent: false,
+            in_section_list: true,
             page_template: None,
+            cascade: Cascade::default(),
             aliases: Vec::new(),
             generate_feed: false,
+            single_page: false,
+            render_pages: true,
             extra: Map::new(),
             draft: false,
         }
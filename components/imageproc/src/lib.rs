@@ -1,6 +1,6 @@
 use std::collections::hash_map::DefaultHasher;
 use std::collections::hash_map::Entry as HEntry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
@@ -11,14 +11,18 @@ use lazy_static::lazy_static;
 use rayon::prelude::*;
 use regex::Regex;
 
-use errors::{Error, Result};
+use errors::{bail, Error, Result};
 use utils::fs as ufs;
 
-static RESIZED_SUBDIR: &str = "processed_images";
+/// The largest side of the placeholders generated by `Processor::lqip`
+const LQIP_SIZE: u32 = 16;
+const LQIP_QUALITY: u8 = 50;
 
 lazy_static! {
+    /// Matches the hash and collision id at the end of the processed images filenames, with or
+    /// without the readable prefix
     pub static ref RESIZED_FILENAME: Regex =
-        Regex::new(r#"([0-9a-f]{16})([0-9a-f]{2})[.](jpg|png)"#).unwrap();
+        Regex::new(r#"([0-9a-f]{16})([0-9a-f]{2})[.](jpg|png)$"#).unwrap();
 }
 
 /// Describes the precise kind of a resize operation
@@ -104,6 +108,45 @@ impl ResizeOp {
             Fill(_, h) => Some(h),
         }
     }
+
+    /// The requested size, eg `800x600`, `800x` when only the width is set or `x600` when only
+    /// the height is
+    pub fn dimensions(&self) -> String {
+        let side = |s: Option<u32>| s.map_or(String::new(), |s| s.to_string());
+        format!("{}x{}", side(self.width()), side(self.height()))
+    }
+
+    /// Scales the operation down so it doesn't ask for more than `max_w` by `max_h`,
+    /// keeping the aspect ratio of the operations that have an exact output size
+    pub fn clamp(self, max_w: u32, max_h: u32) -> ResizeOp {
+        use ResizeOp::*;
+
+        let scale_down = |w: u32, h: u32| {
+            let factor = (max_w as f64 / w as f64).min(max_h as f64 / h as f64);
+            if factor >= 1.0 {
+                (w, h)
+            } else {
+                (
+                    ((w as f64 * factor).round() as u32).max(1),
+                    ((h as f64 * factor).round() as u32).max(1),
+                )
+            }
+        };
+
+        match self {
+            Scale(w, h) => {
+                let (w, h) = scale_down(w, h);
+                Scale(w, h)
+            }
+            FitWidth(w) => FitWidth(w.min(max_w)),
+            FitHeight(h) => FitHeight(h.min(max_h)),
+            Fit(w, h) => Fit(w.min(max_w), h.min(max_h)),
+            Fill(w, h) => {
+                let (w, h) = scale_down(w, h);
+                Fill(w, h)
+            }
+        }
+    }
 }
 
 impl From<ResizeOp> for u8 {
@@ -201,7 +244,7 @@ impl Hash for Format {
 }
 
 /// Holds all data needed to perform a resize operation
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImageOp {
     source: String,
     op: ResizeOp,
@@ -239,12 +282,13 @@ impl ImageOp {
         Ok(Self::new(source, op, format))
     }
 
-    fn perform(&self, content_path: &Path, target_path: &Path) -> Result<()> {
+    /// Returns whether the image was processed, it isn't if the target is already up to date
+    fn perform(&self, content_path: &Path, target_path: &Path, force: bool) -> Result<bool> {
         use ResizeOp::*;
 
         let src_path = content_path.join(&self.source);
-        if !ufs::file_stale(&src_path, target_path) {
-            return Ok(());
+        if !force && !ufs::file_stale(&src_path, target_path) {
+            return Ok(false);
         }
 
         let mut img = image::open(&src_path)?;
@@ -305,16 +349,18 @@ impl ImageOp {
             }
         }
 
-        Ok(())
+        Ok(true)
     }
 }
 
 /// A strcture into which image operations can be enqueued and then performed.
-/// All output is written in a subdirectory in `static_path`,
+/// All output is written in the `output_dir` subdirectory of `static_path`,
 /// taking care of file stale status based on timestamps and possible hash collisions.
 #[derive(Debug)]
 pub struct Processor {
     content_path: PathBuf,
+    /// The directory of the processed images relative to `static_path` and the base URL
+    output_dir: String,
     resized_path: PathBuf,
     resized_url: String,
     /// A map of a ImageOps by their stored hash.
@@ -323,39 +369,187 @@ pub struct Processor {
     img_ops: HashMap<u64, ImageOp>,
     /// Hash collisions go here:
     img_ops_collisions: Vec<ImageOp>,
+    /// Whether to process images even if an up-to-date output already exists
+    force: bool,
+    /// The largest width or height an operation can ask for
+    max_dimension: Option<u32>,
+    /// Whether operations above `max_dimension` are clamped to it rather than rejected
+    clamp_to_max_dimension: bool,
+    /// Whether operations can ask for a bigger size than the source image
+    allow_upscale: bool,
+    /// Whether the filenames start with the name of the source and the requested size
+    readable_filenames: bool,
+    /// How many images were actually processed by the last `do_process`
+    num_processed: usize,
+    /// Whether the operations inserted now come from rendering the templates
+    building: bool,
+    /// The filenames of the images requested by the templates during the last build, which are
+    /// kept when clearing the operations as the templates are only rendered again by the next build
+    build_filenames: HashSet<String>,
+    /// The warnings of `limit` not taken by `take_warnings` yet
+    warnings: Vec<String>,
 }
 
 impl Processor {
-    pub fn new(content_path: PathBuf, static_path: &Path, base_url: &str) -> Processor {
+    /// `output_dir` is the `/`-separated directory the images are written to, relative to
+    /// `static_path`, and served from, relative to `base_url`
+    pub fn new(
+        content_path: PathBuf,
+        static_path: &Path,
+        base_url: &str,
+        output_dir: &str,
+    ) -> Processor {
+        let output_dir = output_dir.trim_matches('/').to_string();
         Processor {
             content_path,
-            resized_path: static_path.join(RESIZED_SUBDIR),
-            resized_url: Self::resized_url(base_url),
+            resized_path: output_dir.split('/').fold(static_path.to_path_buf(), |p, c| p.join(c)),
+            resized_url: Self::resized_url(base_url, &output_dir),
+            output_dir,
             img_ops: HashMap::new(),
             img_ops_collisions: Vec::new(),
+            force: false,
+            max_dimension: None,
+            clamp_to_max_dimension: false,
+            allow_upscale: true,
+            readable_filenames: false,
+            num_processed: 0,
+            building: false,
+            build_filenames: HashSet::new(),
+            warnings: Vec::new(),
         }
     }
 
-    fn resized_url(base_url: &str) -> String {
+    fn resized_url(base_url: &str, output_dir: &str) -> String {
         if base_url.ends_with('/') {
-            format!("{}{}", base_url, RESIZED_SUBDIR)
+            format!("{}{}", base_url, output_dir)
         } else {
-            format!("{}/{}", base_url, RESIZED_SUBDIR)
+            format!("{}/{}", base_url, output_dir)
         }
     }
 
     pub fn set_base_url(&mut self, base_url: &str) {
-        self.resized_url = Self::resized_url(base_url);
+        self.resized_url = Self::resized_url(base_url, &self.output_dir);
+    }
+
+    pub fn set_force(&mut self, force: bool) {
+        self.force = force;
+    }
+
+    /// Sets the size limits applied by `limit`
+    pub fn set_limits(
+        &mut self,
+        max_dimension: Option<u32>,
+        clamp_to_max_dimension: bool,
+        allow_upscale: bool,
+    ) {
+        self.max_dimension = max_dimension;
+        self.clamp_to_max_dimension = clamp_to_max_dimension;
+        self.allow_upscale = allow_upscale;
+    }
+
+    /// Prefixes the filenames of the processed images with the name of their source and the
+    /// requested size, eg `photo_800x600_{hash}.jpg` rather than `{hash}.jpg`
+    pub fn set_readable_filenames(&mut self, readable_filenames: bool) {
+        self.readable_filenames = readable_filenames;
+    }
+
+    /// Applies the size limits to an operation: an operation asking for more than the
+    /// max dimension is an error, unless it should be clamped, and one asking for more than
+    /// the source is scaled down to it if upscaling isn't allowed
+    pub fn limit(&mut self, img_op: ImageOp) -> Result<ImageOp> {
+        let mut op = img_op.op;
+        if let Some(max) = self.max_dimension {
+            let too_big =
+                op.width().map_or(false, |w| w > max) || op.height().map_or(false, |h| h > max);
+            if too_big {
+                if !self.clamp_to_max_dimension {
+                    bail!(
+                        "Resizing {} to {:?} goes over the `max_dimension` of {}px",
+                        img_op.source,
+                        op,
+                        max
+                    );
+                }
+                op = op.clamp(max, max);
+                self.warnings.push(format!(
+                    "Resizing {} to {:?} instead of {:?} to stay within the `max_dimension` of {}px",
+                    img_op.source, op, img_op.op, max
+                ));
+            }
+        }
+
+        if !self.allow_upscale {
+            let (img_w, img_h) = image::image_dimensions(self.content_path.join(&img_op.source))
+                .map_err(|e| {
+                    Error::chain(format!("Failed to process image: {}", img_op.source), e)
+                })?;
+            op = op.clamp(img_w, img_h);
+        }
+
+        if op == img_op.op {
+            Ok(img_op)
+        } else {
+            Ok(ImageOp::new(img_op.source, op, img_op.format))
+        }
     }
 
     pub fn source_exists(&self, source: &str) -> bool {
         self.content_path.join(source).exists()
     }
 
+    /// Forgets all the operations, eg before rendering the whole site again so the images
+    /// that aren't used anymore get pruned
+    pub fn clear_ops(&mut self) {
+        self.img_ops.clear();
+        self.img_ops_collisions.clear();
+        self.building = false;
+    }
+
+    /// To call before rendering the templates: the operations inserted from then on replace the
+    /// ones of the previous build in the images that `prune` keeps after `clear_ops`
+    pub fn start_build(&mut self) {
+        self.building = true;
+        self.build_filenames.clear();
+    }
+
+    /// The warnings recorded since the last call, eg operations clamped to `max_dimension`
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
     pub fn num_img_ops(&self) -> usize {
         self.img_ops.len() + self.img_ops_collisions.len()
     }
 
+    /// How many images were processed by the last `do_process`, the ones whose output
+    /// was already up to date being skipped
+    pub fn num_processed(&self) -> usize {
+        self.num_processed
+    }
+
+    /// Generates a tiny blurred copy of an image, a low quality image placeholder (LQIP),
+    /// as a data URI meant to be inlined while the actual image loads.
+    /// Returns `None` if the image is already no bigger than a placeholder.
+    pub fn lqip(&self, source: &str) -> Result<Option<String>> {
+        let format = Format::from_args(source, "auto", LQIP_QUALITY)?;
+        let img = image::open(self.content_path.join(source))
+            .map_err(|e| Error::chain(format!("Failed to process image: {}", source), e))?;
+        let (img_w, img_h) = img.dimensions();
+        if img_w <= LQIP_SIZE && img_h <= LQIP_SIZE {
+            return Ok(None);
+        }
+
+        let placeholder = img.thumbnail(LQIP_SIZE, LQIP_SIZE).blur(1.0);
+        let (output_format, mime) = match format {
+            Format::Png => (ImageOutputFormat::Png, "image/png"),
+            Format::Jpeg(q) => (ImageOutputFormat::Jpeg(q), "image/jpeg"),
+        };
+        let mut buffer = Vec::new();
+        placeholder.write_to(&mut buffer, output_format)?;
+
+        Ok(Some(format!("data:{};base64,{}", mime, base64::encode(&buffer))))
+    }
+
     fn insert_with_collisions(&mut self, mut img_op: ImageOp) -> u32 {
         match self.img_ops.entry(img_op.hash) {
             HEntry::Occupied(entry) => {
@@ -402,21 +596,34 @@ impl Processor {
         collision_id
     }
 
-    fn op_filename(hash: u64, collision_id: u32, format: Format) -> String {
+    fn op_filename(&self, img_op: &ImageOp, collision_id: u32) -> String {
         // Please keep this in sync with RESIZED_FILENAME
         assert!(collision_id < 256, "Unexpectedly large number of collisions: {}", collision_id);
-        format!("{:016x}{:02x}.{}", hash, collision_id, format.extension())
-    }
+        let filename =
+            format!("{:016x}{:02x}.{}", img_op.hash, collision_id, img_op.format.extension());
+        if !self.readable_filenames {
+            return filename;
+        }
 
-    fn op_url(&self, hash: u64, collision_id: u32, format: Format) -> String {
-        format!("{}/{}", &self.resized_url, Self::op_filename(hash, collision_id, format))
+        // The hash is still there so images with the same name in different directories
+        // don't overwrite each other
+        let stem: String = Path::new(&img_op.source)
+            .file_stem()
+            .map(|s| s.to_string_lossy())
+            .unwrap_or_default()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+            .collect();
+        format!("{}_{}_{}", stem, img_op.op.dimensions(), filename)
     }
 
     pub fn insert(&mut self, img_op: ImageOp) -> String {
-        let hash = img_op.hash;
-        let format = img_op.format;
-        let collision_id = self.insert_with_collisions(img_op);
-        self.op_url(hash, collision_id, format)
+        let collision_id = self.insert_with_collisions(img_op.clone());
+        let filename = self.op_filename(&img_op, collision_id);
+        if self.building {
+            self.build_filenames.insert(filename.clone());
+        }
+        format!("{}/{}", &self.resized_url, filename)
     }
 
     pub fn prune(&self) -> Result<()> {
@@ -436,7 +643,13 @@ impl Processor {
                     let collision_id =
                         u32::from_str_radix(capts.get(2).unwrap().as_str(), 16).unwrap();
 
-                    if collision_id > 0 || !self.img_ops.contains_key(&hash) {
+                    // Also removes the images named with the other filename scheme
+                    let is_current = self
+                        .img_ops
+                        .get(&hash)
+                        .map_or(false, |op| self.op_filename(op, op.collision_id) == filename)
+                        || self.build_filenames.contains(filename.as_ref());
+                    if collision_id > 0 || !is_current {
                         fs::remove_file(&entry_path)?;
                     }
                 }
@@ -450,14 +663,17 @@ impl Processor {
             ufs::ensure_directory_exists(&self.resized_path)?;
         }
 
-        self.img_ops
+        let processed = self
+            .img_ops
             .par_iter()
-            .map(|(hash, op)| {
-                let target =
-                    self.resized_path.join(Self::op_filename(*hash, op.collision_id, op.format));
-                op.perform(&self.content_path, &target)
+            .map(|(_, op)| {
+                let target = self.resized_path.join(self.op_filename(op, op.collision_id));
+                op.perform(&self.content_path, &target, self.force)
                     .map_err(|e| Error::chain(format!("Failed to process image: {}", op.source), e))
             })
-            .collect::<Result<()>>()
+            .collect::<Result<Vec<bool>>>()?;
+        self.num_processed = processed.into_iter().filter(|p| *p).count();
+
+        Ok(())
     }
 }
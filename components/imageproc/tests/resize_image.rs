@@ -0,0 +1,155 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use imageproc::{ImageOp, Processor, ResizeOp};
+use tempfile::tempdir;
+
+fn test_image_path() -> PathBuf {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    path.push("content");
+    path.push("posts");
+    path.push("with-assets");
+    path.push("zola.png");
+    path
+}
+
+#[test]
+fn can_force_reprocessing_of_fresh_images() {
+    let tmp_dir = tempdir().expect("create temp dir");
+    let content_path = tmp_dir.path().join("content");
+    let static_path = tmp_dir.path().join("static");
+    fs::create_dir_all(&content_path).unwrap();
+    fs::copy(test_image_path(), content_path.join("zola.png")).unwrap();
+
+    let mut processor =
+        Processor::new(content_path, &static_path, "https://example.com", "processed_images");
+    let op = ImageOp::from_args("zola.png".to_string(), "scale", Some(10), Some(10), "auto", 75)
+        .unwrap();
+    let url = processor.insert(op);
+    let filename = url.rsplit('/').next().unwrap();
+    let target = static_path.join("processed_images").join(filename);
+
+    processor.do_process().unwrap();
+    assert!(target.exists());
+
+    // The output is newer than the source so it is considered up to date and not touched
+    fs::write(&target, "not an image").unwrap();
+    processor.do_process().unwrap();
+    assert_eq!(fs::read(&target).unwrap(), b"not an image");
+
+    processor.set_force(true);
+    processor.do_process().unwrap();
+    assert_ne!(fs::read(&target).unwrap(), b"not an image");
+}
+
+#[test]
+fn can_write_processed_images_in_custom_directory() {
+    let tmp_dir = tempdir().expect("create temp dir");
+    let content_path = tmp_dir.path().join("content");
+    let static_path = tmp_dir.path().join("static");
+    fs::create_dir_all(&content_path).unwrap();
+    fs::copy(test_image_path(), content_path.join("zola.png")).unwrap();
+
+    let mut processor =
+        Processor::new(content_path, &static_path, "https://example.com/", "/img/derived/");
+    let op = ImageOp::from_args("zola.png".to_string(), "scale", Some(10), Some(10), "auto", 75)
+        .unwrap();
+    let url = processor.insert(op);
+    assert!(url.starts_with("https://example.com/img/derived/"));
+
+    processor.set_base_url("http://127.0.0.1:1111");
+    let op = ImageOp::from_args("zola.png".to_string(), "scale", Some(10), Some(10), "auto", 75)
+        .unwrap();
+    assert!(processor.insert(op).starts_with("http://127.0.0.1:1111/img/derived/"));
+
+    let filename = url.rsplit('/').next().unwrap();
+    processor.do_process().unwrap();
+    assert!(static_path.join("img").join("derived").join(filename).exists());
+    assert!(!static_path.join("processed_images").exists());
+}
+
+#[test]
+fn can_limit_the_size_of_processed_images() {
+    let tmp_dir = tempdir().expect("create temp dir");
+    let content_path = tmp_dir.path().join("content");
+    let static_path = tmp_dir.path().join("static");
+    fs::create_dir_all(&content_path).unwrap();
+    fs::copy(test_image_path(), content_path.join("zola.png")).unwrap();
+    let (img_w, img_h) = image::image_dimensions(content_path.join("zola.png")).unwrap();
+
+    let mut processor =
+        Processor::new(content_path, &static_path, "https://example.com", "processed_images");
+    let op = || {
+        ImageOp::from_args("zola.png".to_string(), "fill", Some(4000), Some(2000), "auto", 75)
+            .unwrap()
+    };
+
+    // No limits by default
+    assert_eq!(processor.limit(op()).unwrap(), op());
+
+    processor.set_limits(Some(1000), false, true);
+    assert!(processor.limit(op()).is_err());
+
+    processor.set_limits(Some(1000), true, true);
+    let expected =
+        ImageOp::from_args("zola.png".to_string(), "fill", Some(1000), Some(500), "auto", 75)
+            .unwrap();
+    assert_eq!(processor.limit(op()).unwrap(), expected);
+
+    processor.set_limits(None, false, false);
+    let op =
+        ImageOp::from_args("zola.png".to_string(), "fit_width", Some(img_w * 2), None, "auto", 75)
+            .unwrap();
+    let expected =
+        ImageOp::from_args("zola.png".to_string(), "fit_width", Some(img_w), None, "auto", 75)
+            .unwrap();
+    assert_eq!(processor.limit(op).unwrap(), expected);
+    assert_eq!(
+        ResizeOp::Scale(img_w * 2, img_h * 2).clamp(img_w, img_h),
+        ResizeOp::Scale(img_w, img_h)
+    );
+}
+
+#[test]
+fn can_use_readable_filenames() {
+    let tmp_dir = tempdir().expect("create temp dir");
+    let content_path = tmp_dir.path().join("content");
+    let static_path = tmp_dir.path().join("static");
+    fs::create_dir_all(content_path.join("other")).unwrap();
+    fs::copy(test_image_path(), content_path.join("zola.png")).unwrap();
+    fs::copy(test_image_path(), content_path.join("other").join("zola.png")).unwrap();
+
+    let mut processor =
+        Processor::new(content_path, &static_path, "https://example.com", "processed_images");
+    let op = || {
+        ImageOp::from_args("zola.png".to_string(), "fill", Some(80), Some(60), "auto", 75).unwrap()
+    };
+    let hashed_url = processor.insert(op());
+    let hashed_filename = hashed_url.rsplit('/').next().unwrap().to_string();
+
+    processor.set_readable_filenames(true);
+    let url = processor.insert(op());
+    let filename = url.rsplit('/').next().unwrap();
+    assert!(filename.starts_with("zola_80x60_"));
+    // Still ends with the hash, deterministically
+    assert!(filename.ends_with(&hashed_filename));
+    assert_eq!(processor.insert(op()), url);
+
+    let fit_width =
+        ImageOp::from_args("zola.png".to_string(), "fit_width", Some(40), None, "auto", 75)
+            .unwrap();
+    assert!(processor.insert(fit_width).rsplit('/').next().unwrap().starts_with("zola_40x_"));
+
+    // Images with the same name elsewhere get their own file
+    let other =
+        ImageOp::from_args("other/zola.png".to_string(), "fill", Some(80), Some(60), "auto", 75)
+            .unwrap();
+    let other_url = processor.insert(other);
+    assert!(other_url.rsplit('/').next().unwrap().starts_with("zola_80x60_"));
+    assert_ne!(other_url, url);
+
+    processor.do_process().unwrap();
+    assert!(static_path.join("processed_images").join(filename).exists());
+}
@@ -115,7 +115,13 @@ impl FileInfo {
     /// Look for a language in the filename.
     /// If a language has been found, update the name of the file in this struct to
     /// remove it and return the language code
+    /// With `language_content_dirs`, the language is found from the first directory instead,
+    /// which is removed from the components
     pub fn find_language(&mut self, config: &Config) -> Result<String> {
+        if config.uses_language_content_dirs() {
+            return self.find_language_from_dir(config);
+        }
+
         // No languages? Nothing to do
         if !config.is_multilingual() {
             return Ok(config.default_language.clone());
@@ -146,6 +152,32 @@ impl FileInfo {
 
         Ok(lang)
     }
+
+    fn find_language_from_dir(&mut self, config: &Config) -> Result<String> {
+        let lang = match self.components.first().and_then(|d| config.language_of_content_dir(d)) {
+            Some(lang) => lang.to_string(),
+            None => bail!(
+                "File {:?} is not in one of the directories of `language_content_dirs`",
+                self.path
+            ),
+        };
+
+        // `parent` is always the `content` directory followed by the components so we can
+        // find the directory of the language from it
+        let content_path = self
+            .parent
+            .ancestors()
+            .nth(self.components.len())
+            .expect("Get content directory of file")
+            .to_path_buf();
+        let lang_path = content_path.join(self.components.remove(0));
+        // Files at the same place in each directory are translations of each other
+        if let Ok(canonical) = self.canonical.strip_prefix(&lang_path) {
+            self.canonical = content_path.join(canonical);
+        }
+
+        Ok(lang)
+    }
 }
 
 #[cfg(test)]
@@ -184,7 +216,12 @@ mod tests {
     #[test]
     fn can_find_valid_language_in_page() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let mut file = FileInfo::new_page(
             &Path::new("/home/vincent/code/site/content/posts/tutorials/python.fr.md"),
             &PathBuf::new(),
@@ -197,7 +234,12 @@ mod tests {
     #[test]
     fn can_find_valid_language_with_default_locale() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let mut file = FileInfo::new_page(
             &Path::new("/home/vincent/code/site/content/posts/tutorials/python.en.md"),
             &PathBuf::new(),
@@ -210,7 +252,12 @@ mod tests {
     #[test]
     fn can_find_valid_language_in_page_with_assets() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let mut file = FileInfo::new_page(
             &Path::new("/home/vincent/code/site/content/posts/tutorials/python/index.fr.md"),
             &PathBuf::new(),
@@ -236,7 +283,12 @@ mod tests {
     #[test]
     fn errors_on_unknown_language_in_page_with_i18n_on() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("it"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("it"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let mut file = FileInfo::new_page(
             &Path::new("/home/vincent/code/site/content/posts/tutorials/python.fr.md"),
             &PathBuf::new(),
@@ -248,7 +300,12 @@ mod tests {
     #[test]
     fn can_find_valid_language_in_section() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let mut file = FileInfo::new_section(
             &Path::new("/home/vincent/code/site/content/posts/tutorials/_index.fr.md"),
             &PathBuf::new(),
@@ -258,6 +315,42 @@ mod tests {
         assert_eq!(res.unwrap(), "fr");
     }
 
+    #[test]
+    fn can_find_language_from_content_dir() {
+        let mut config = Config::default();
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
+        config.language_content_dirs.insert("en".to_string(), "en".to_string());
+        config.language_content_dirs.insert("fr".to_string(), "fr".to_string());
+        let mut file = FileInfo::new_page(
+            &Path::new("/home/vincent/code/site/content/fr/posts/python.md"),
+            &PathBuf::new(),
+        );
+        let res = file.find_language(&config);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), "fr");
+        assert_eq!(file.components, ["posts".to_string()]);
+        assert_eq!(file.canonical, Path::new("/home/vincent/code/site/content/posts/python"));
+        assert_eq!(file.parent, Path::new("/home/vincent/code/site/content/fr/posts"));
+
+        let mut file = FileInfo::new_section(
+            &Path::new("/home/vincent/code/site/content/en/_index.md"),
+            &PathBuf::new(),
+        );
+        assert_eq!(file.find_language(&config).unwrap(), "en");
+        assert!(file.components.is_empty());
+
+        let mut file = FileInfo::new_page(
+            &Path::new("/home/vincent/code/site/content/posts/python.md"),
+            &PathBuf::new(),
+        );
+        assert!(file.find_language(&config).is_err());
+    }
+
     /// Regression test for https://github.com/getzola/zola/issues/854
     #[test]
     fn correct_canonical_for_index() {
@@ -275,7 +368,12 @@ mod tests {
     #[test]
     fn correct_canonical_after_find_language() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let mut file = FileInfo::new_page(
             &Path::new("/home/vincent/code/site/content/posts/tutorials/python/index.fr.md"),
             &PathBuf::new(),
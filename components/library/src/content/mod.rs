@@ -4,7 +4,7 @@ mod section;
 mod ser;
 
 pub use self::file_info::FileInfo;
-pub use self::page::Page;
+pub use self::page::{Page, RelativeDate};
 pub use self::section::Section;
 pub use self::ser::{SerializingPage, SerializingSection};
 
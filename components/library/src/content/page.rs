@@ -2,14 +2,16 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use chrono::{Local, NaiveDateTime};
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde_derive::Serialize;
 use slotmap::DefaultKey;
-use tera::{Context as TeraContext, Tera};
+use tera::{Context as TeraContext, Tera, Value};
 
 use crate::library::Library;
-use config::Config;
-use errors::{Error, Result};
+use config::{Config, Schema};
+use errors::{bail, Error, Result};
 use front_matter::{split_page_content, InsertAnchor, PageFrontMatter};
 use rendering::{render_content, Heading, RenderContext};
 use utils::fs::{find_related_assets, read_file};
@@ -29,6 +31,64 @@ lazy_static! {
     ).unwrap();
 }
 
+/// How far the date of a page is from the time it was loaded, for "3 days ago" displays
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RelativeDate {
+    /// The number of `unit`s between the two
+    pub value: i64,
+    /// One of `second`, `minute`, `hour`, `day`, `month` or `year`
+    pub unit: &'static str,
+    /// Whether the page is dated in the future
+    pub future: bool,
+}
+
+impl RelativeDate {
+    pub fn between(date: NaiveDateTime, now: NaiveDateTime) -> RelativeDate {
+        let seconds = now.signed_duration_since(date).num_seconds();
+        let future = seconds < 0;
+        let seconds = seconds.abs();
+        let days = seconds / 86400;
+        let (value, unit) = if seconds < 60 {
+            (seconds, "second")
+        } else if seconds < 3600 {
+            (seconds / 60, "minute")
+        } else if days < 1 {
+            (seconds / 3600, "hour")
+        } else if days < 30 {
+            (days, "day")
+        } else if days < 365 {
+            (days / 30, "month")
+        } else {
+            (days / 365, "year")
+        };
+
+        RelativeDate { value, unit, future }
+    }
+
+    /// Formats it as `3 days ago` or `in 1 day`.
+    /// Each language can translate it with the `relative_date_past` and `relative_date_future`
+    /// keys, `{}` being replaced by the duration, and the `relative_date_{unit}` and
+    /// `relative_date_{unit}s` keys for the units.
+    pub fn to_text(&self, config: &Config, lang: &str) -> String {
+        let translate = |key: &str, default: &str| {
+            config.get_translation(lang, key).unwrap_or_else(|_| default.to_string())
+        };
+        let unit = if self.value == 1 { self.unit.to_string() } else { format!("{}s", self.unit) };
+        let duration = format!(
+            "{} {}",
+            self.value,
+            translate(format!("relative_date_{}", unit).as_str(), &unit)
+        );
+        let pattern = if self.future {
+            translate("relative_date_future", "in {}")
+        } else {
+            translate("relative_date_past", "{} ago")
+        };
+
+        pattern.replace("{}", &duration)
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Page {
     /// All info about the actual file
@@ -54,6 +114,10 @@ pub struct Page {
     pub components: Vec<String>,
     /// The full URL for that page
     pub permalink: String,
+    /// The URL path of the AMP version of the page, if `amp` is set in the front matter
+    pub amp_path: Option<String>,
+    /// The full URL of the AMP version of the page, if `amp` is set in the front matter
+    pub amp_permalink: Option<String>,
     /// The summary for the article, defaults to None
     /// When <!-- more --> is found in the text, will take the content up to that part
     /// as summary
@@ -66,6 +130,10 @@ pub struct Page {
     pub lighter: Option<DefaultKey>,
     /// The heavier page, for pages sorted by weight
     pub heavier: Option<DefaultKey>,
+    /// The page before this one in its section, whatever the section is sorted by
+    pub prev_in_section: Option<DefaultKey>,
+    /// The page after this one in its section, whatever the section is sorted by
+    pub next_in_section: Option<DefaultKey>,
     /// Toc made from the headings of the markdown file
     pub toc: Vec<Heading>,
     /// How many words in the raw content
@@ -85,6 +153,13 @@ pub struct Page {
     pub internal_links_with_anchors: Vec<(String, String)>,
     /// Contains the external links that need to be checked
     pub external_links: Vec<String>,
+    /// The date of the page relative to when it was loaded, eg `3 days ago`
+    pub relative_date: Option<String>,
+    /// The components of `relative_date`, for templates formatting it themselves
+    pub relative_date_components: Option<RelativeDate>,
+    /// Whether this page is the content of the default language standing in for a missing
+    /// translation, see `translation_fallback`
+    pub is_fallback: bool,
 }
 
 impl Page {
@@ -135,6 +210,12 @@ impl Page {
             }
         }
 
+        if let Some(date) = page.meta.datetime {
+            let relative_date = RelativeDate::between(date, Local::now().naive_local());
+            page.relative_date = Some(relative_date.to_text(config, &page.lang));
+            page.relative_date_components = Some(relative_date);
+        }
+
         page.slug = {
             if let Some(ref slug) = page.meta.slug {
                 slugify_paths(slug, config.slugify.paths)
@@ -190,11 +271,63 @@ impl Page {
             .map(|p| p.to_string())
             .filter(|p| !p.is_empty())
             .collect::<Vec<_>>();
-        page.permalink = config.make_permalink(&page.path);
+        if page.meta.amp {
+            let amp_path = format!("{}amp/", page.path);
+            page.amp_permalink = Some(config.make_permalink_for_lang(&amp_path, &page.lang));
+            page.amp_path = Some(amp_path);
+        }
+        if let Some(ref filename) = page.meta.output_filename {
+            page.permalink =
+                format!("{}{}", config.make_permalink_for_lang(&page.path, &page.lang), filename);
+            page.path = format!("{}{}", page.path, filename);
+        } else {
+            if !config.pretty_urls && page.path != "/" {
+                page.path = format!("{}.html", page.path.trim_end_matches('/'));
+            }
+            page.permalink = config.make_permalink_for_lang(&page.path, &page.lang);
+        }
 
         Ok(page)
     }
 
+    /// A copy of this page in the given language, at the URL its translation would have, for
+    /// languages it isn't translated to when `translation_fallback` is set.
+    /// It is at the path of the `{name}.{lang}.md` file that doesn't exist so it is still
+    /// possible to link to it.
+    pub fn translation_fallback(&self, lang: &str, config: &Config) -> Page {
+        let mut page = self.clone();
+        let filename = format!("{}.{}.md", self.file.name, lang);
+        page.file.path = self.file.path.with_file_name(&filename);
+        page.file.relative = match self.file.relative.rfind('/') {
+            Some(i) => format!("{}/{}", &self.file.relative[..i], filename),
+            None => filename.clone(),
+        };
+        page.file.filename = filename;
+        page.lang = lang.to_string();
+        page.is_fallback = true;
+        page.translations = vec![];
+
+        page.path = format!("/{}{}", lang, self.path);
+        page.components =
+            std::iter::once(lang.to_string()).chain(self.components.iter().cloned()).collect();
+        page.permalink = match page.meta.output_filename {
+            Some(ref filename) => {
+                let dir = page.path.trim_end_matches(filename.as_str());
+                format!("{}{}", config.make_permalink_for_lang(dir, lang), filename)
+            }
+            None => config.make_permalink_for_lang(&page.path, lang),
+        };
+        if let Some(ref amp_path) = self.amp_path {
+            let amp_path = format!("/{}{}", lang, amp_path);
+            page.amp_permalink = Some(config.make_permalink_for_lang(&amp_path, lang));
+            page.amp_path = Some(amp_path);
+        }
+        page.relative_date =
+            self.relative_date_components.as_ref().map(|date| date.to_text(config, lang));
+
+        page
+    }
+
     /// Read and parse a .md file into a Page struct
     pub fn from_file<P: AsRef<Path>>(
         path: P,
@@ -209,24 +342,15 @@ impl Page {
             let parent_dir = path.parent().unwrap();
             let assets = find_related_assets(parent_dir);
 
-            if let Some(ref globset) = config.ignored_content_globset {
-                // `find_related_assets` only scans the immediate directory (it is not recursive) so our
-                // filtering only needs to work against the file_name component, not the full suffix. If
-                // `find_related_assets` was changed to also return files in subdirectories, we could
-                // use `PathBuf.strip_prefix` to remove the parent directory and then glob-filter
-                // against the remaining path. Note that the current behaviour effectively means that
-                // the `ignored_content` setting in the config file is limited to single-file glob
-                // patterns (no "**" patterns).
-                page.assets = assets
-                    .into_iter()
-                    .filter(|path| match path.file_name() {
-                        None => false,
-                        Some(file) => !globset.is_match(file),
-                    })
-                    .collect();
-            } else {
-                page.assets = assets;
-            }
+            // `find_related_assets` only scans the immediate directory (it is not recursive) so our
+            // filtering only needs to work against the file_name component, not the full suffix.
+            page.assets = assets
+                .into_iter()
+                .filter(|path| match path.file_name() {
+                    None => false,
+                    Some(file) => !config.is_ignored_content(Path::new(file)),
+                })
+                .collect();
 
             page.serialized_assets = page.serialize_assets(&base_path);
         } else {
@@ -247,6 +371,9 @@ impl Page {
     ) -> Result<()> {
         let mut context =
             RenderContext::new(tera, config, &self.permalink, permalinks, anchor_insert);
+        if self.meta.toc_max_depth.is_some() {
+            context.toc_max_depth = self.meta.toc_max_depth;
+        }
 
         context.tera_context.insert("page", &SerializingPage::from_page_basic(self, None));
 
@@ -263,25 +390,82 @@ impl Page {
         Ok(())
     }
 
-    /// Renders the page using the default layout, unless specified in front-matter
-    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
-        let tpl_name = match self.meta.template {
+    /// The template set in the front matter, `page.html` otherwise
+    pub fn get_template_name(&self) -> &str {
+        match self.meta.template {
             Some(ref l) => l,
             None => "page.html",
-        };
+        }
+    }
+
+    /// Renders the page using the default layout, unless specified in front-matter
+    pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
+        self.render_html_with_template(self.get_template_name(), tera, config, library)
+    }
 
+    /// Renders the page with the given template instead of its own
+    pub fn render_html_with_template(
+        &self,
+        tpl_name: &str,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+    ) -> Result<String> {
         let mut context = TeraContext::new();
         context.insert("config", config);
+        context.insert("data", &config.data);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
         context.insert("page", &self.to_serialized(library));
         context.insert("lang", &self.lang);
+        context.insert("translation_map", &self.translation_map(library));
 
-        render_template(&tpl_name, tera, context, &config.theme).map_err(|e| {
+        render_template(tpl_name, tera, context, &config.theme).map_err(|e| {
             Error::chain(format!("Failed to render page '{}'", self.file.path.display()), e)
         })
     }
 
+    /// The permalink of the page in each language it is available in, itself included
+    pub fn translation_map<'a>(&'a self, library: &'a Library) -> HashMap<&'a str, &'a str> {
+        let mut map = HashMap::new();
+        map.insert(self.lang.as_str(), self.permalink.as_str());
+        for key in library.translations.get(&self.file.canonical).into_iter().flatten() {
+            let other = library.get_page_by_key(*key);
+            map.entry(other.lang.as_str()).or_insert(other.permalink.as_str());
+        }
+        map
+    }
+
+    /// Renders the AMP version of the page with the `amp.html` template.
+    /// `current_url` is the AMP permalink and `canonical_url` the permalink of the page.
+    pub fn render_amp_html(
+        &self,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+    ) -> Result<String> {
+        let amp_permalink = match self.amp_permalink {
+            Some(ref p) => p,
+            None => bail!("Page '{}' doesn't have an AMP version", self.file.path.display()),
+        };
+
+        let mut context = TeraContext::new();
+        context.insert("config", config);
+        context.insert("data", &config.data);
+        context.insert("current_url", amp_permalink);
+        context.insert("current_path", &self.amp_path);
+        context.insert("canonical_url", &self.permalink);
+        context.insert("page", &self.to_serialized(library));
+        context.insert("lang", &self.lang);
+
+        render_template("amp.html", tera, context, &config.theme).map_err(|e| {
+            Error::chain(
+                format!("Failed to render the AMP version of page '{}'", self.file.path.display()),
+                e,
+            )
+        })
+    }
+
     /// Creates a vectors of asset URLs.
     fn serialize_assets(&self, base_path: &PathBuf) -> Vec<String> {
         self.assets
@@ -308,6 +492,46 @@ impl Page {
         has_anchor(&self.toc, anchor)
     }
 
+    /// Checks the front matter against the schema of its section, returning a message for
+    /// every field that is missing or doesn't have the expected type
+    pub fn check_schema(&self, schema: &Schema) -> Vec<String> {
+        let mut errors = Vec::new();
+        for field in &schema.required {
+            if self.front_matter_value(field).is_none() {
+                errors.push(format!("missing required field `{}`", field));
+            }
+        }
+
+        let mut typed_fields: Vec<_> = schema.types.iter().collect();
+        typed_fields.sort_by(|a, b| a.0.cmp(b.0));
+        for (field, field_type) in typed_fields {
+            if let Some(value) = self.front_matter_value(field) {
+                if !field_type.matches(&value) {
+                    errors.push(format!("field `{}` should be of type `{}`", field, field_type));
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Gets a front matter field by name, looking into `extra` for the ones Zola doesn't know
+    fn front_matter_value(&self, field: &str) -> Option<Value> {
+        let meta = &self.meta;
+        let string = |s: &Option<String>| s.as_ref().map(|s| Value::String(s.clone()));
+        match field {
+            "title" => string(&meta.title),
+            "description" => string(&meta.description),
+            "date" => string(&meta.date),
+            "updated" => string(&meta.updated),
+            "slug" => string(&meta.slug),
+            "path" => string(&meta.path),
+            "weight" => meta.weight.map(Value::from),
+            "template" => string(&meta.template),
+            _ => meta.extra.get(field.trim_start_matches("extra.")).cloned(),
+        }
+    }
+
     pub fn to_serialized<'a>(&'a self, library: &'a Library) -> SerializingPage<'a> {
         SerializingPage::from_page(self, library)
     }
@@ -324,12 +548,14 @@ mod tests {
     use std::io::Write;
     use std::path::{Path, PathBuf};
 
+    use chrono::{Duration, Local};
     use globset::{Glob, GlobSetBuilder};
     use tempfile::tempdir;
     use tera::Tera;
 
     use super::Page;
-    use config::{Config, Language};
+    use crate::content::ser::SerializingPage;
+    use config::{Config, FieldType, Language, Schema};
     use front_matter::InsertAnchor;
     use utils::slugs::SlugifyStrategy;
 
@@ -359,6 +585,34 @@ Hello world"#;
         assert_eq!(page.content, "<p>Hello world</p>\n".to_string());
     }
 
+    #[test]
+    fn can_get_relative_date() {
+        let yesterday = Local::now().naive_local() - Duration::days(1);
+        let content =
+            format!("+++\ndate = {}\n+++\nHello world", yesterday.format("%Y-%m-%dT%H:%M:%S"));
+        let res = Page::parse(Path::new("post.md"), &content, &Config::default(), &PathBuf::new());
+        assert!(res.is_ok());
+        let page = res.unwrap();
+
+        let serialized = tera::to_value(SerializingPage::from_page_basic(&page, None)).unwrap();
+        assert_eq!(serialized["relative_date"], "1 day ago");
+        assert_eq!(serialized["relative_date_components"]["value"], 1);
+        assert_eq!(serialized["relative_date_components"]["unit"], "day");
+        assert_eq!(serialized["relative_date_components"]["future"], false);
+    }
+
+    #[test]
+    fn can_get_relative_date_in_the_future() {
+        let later = Local::now().naive_local() + Duration::days(3) + Duration::hours(1);
+        let content =
+            format!("+++\ndate = {}\n+++\nHello world", later.format("%Y-%m-%dT%H:%M:%S"));
+        let res = Page::parse(Path::new("post.md"), &content, &Config::default(), &PathBuf::new());
+        assert!(res.is_ok());
+        let page = res.unwrap();
+
+        assert_eq!(page.relative_date.unwrap(), "in 3 days");
+    }
+
     #[test]
     fn test_can_make_url_from_sections_and_slug() {
         let content = r#"
@@ -377,6 +631,69 @@ Hello world"#;
         assert_eq!(page.permalink, "http://hello.com/posts/intro/hello-world/");
     }
 
+    #[test]
+    fn can_make_flat_url_without_pretty_urls() {
+        let content = r#"
++++
++++
+Hello world"#;
+        let mut config = Config::default();
+        config.pretty_urls = false;
+        let res = Page::parse(
+            Path::new("content/posts/intro/start.md"),
+            content,
+            &config,
+            &PathBuf::new(),
+        );
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.path, "/posts/intro/start.html");
+        assert_eq!(page.components, vec!["posts", "intro", "start"]);
+        assert_eq!(page.permalink, config.make_permalink("/posts/intro/start.html"));
+    }
+
+    #[test]
+    fn can_use_custom_output_filename() {
+        let content = r#"
++++
+output_filename = "feed.html"
++++
+Hello world"#;
+        let config = Config::default();
+        let res =
+            Page::parse(Path::new("content/posts/start.md"), content, &config, &PathBuf::new());
+        assert!(res.is_ok());
+        let page = res.unwrap();
+        assert_eq!(page.path, "/posts/start/feed.html");
+        assert_eq!(page.components, vec!["posts", "start"]);
+        assert_eq!(page.permalink, format!("{}feed.html", config.make_permalink("/posts/start/")));
+    }
+
+    #[test]
+    fn can_make_amp_path() {
+        let content = r#"
++++
+amp = true
++++
+Hello world"#;
+        let config = Config::default();
+        let page =
+            Page::parse(Path::new("content/posts/start.md"), content, &config, &PathBuf::new())
+                .unwrap();
+        assert_eq!(page.path, "/posts/start/");
+        assert_eq!(page.amp_path, Some("/posts/start/amp/".to_string()));
+        assert_eq!(page.amp_permalink, Some(config.make_permalink("/posts/start/amp/")));
+
+        let page = Page::parse(
+            Path::new("content/posts/start.md"),
+            "+++\n+++\nHello",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        assert_eq!(page.amp_path, None);
+    }
+
     #[test]
     fn can_make_url_from_slug_only() {
         let content = r#"
@@ -722,7 +1039,12 @@ Hello world
     #[test]
     fn can_specify_language_in_filename() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let content = r#"
 +++
 +++
@@ -739,7 +1061,12 @@ Bonjour le monde"#
     #[test]
     fn can_specify_language_in_filename_with_date() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let content = r#"
 +++
 +++
@@ -755,10 +1082,55 @@ Bonjour le monde"#
         assert_eq!(page.permalink, "http://a-website.com/fr/hello/");
     }
 
+    #[test]
+    fn can_make_translation_fallback() {
+        let mut config = Config::default();
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
+        let content = r#"
++++
++++
+Hello world"#
+            .to_string();
+        let page =
+            Page::parse(Path::new("content/posts/hello.md"), &content, &config, &PathBuf::new())
+                .unwrap();
+        let fallback = page.translation_fallback("fr", &config);
+        assert!(fallback.is_fallback);
+        assert_eq!(fallback.lang, "fr");
+        assert_eq!(fallback.raw_content, page.raw_content);
+        assert_eq!(fallback.file.path, Path::new("content/posts/hello.fr.md"));
+        assert_eq!(fallback.file.relative, "posts/hello.fr.md");
+        assert_eq!(fallback.file.canonical, page.file.canonical);
+        assert_eq!(fallback.path, "/fr/posts/hello/");
+        assert_eq!(fallback.permalink, "http://a-website.com/fr/posts/hello/");
+        assert_eq!(fallback.components, vec!["fr", "posts", "hello"]);
+
+        // Permalinks are made like the ones of the other pages
+        config.base_url = "http://a-website.com/".to_string();
+        config.pretty_urls = false;
+        let page =
+            Page::parse(Path::new("content/posts/hello.md"), &content, &config, &PathBuf::new())
+                .unwrap();
+        let fallback = page.translation_fallback("fr", &config);
+        assert_eq!(fallback.path, "/fr/posts/hello.html");
+        assert_eq!(fallback.permalink, "http://a-website.com/fr/posts/hello.html");
+        assert_eq!(fallback.components, vec!["fr", "posts", "hello"]);
+    }
+
     #[test]
     fn i18n_frontmatter_path_overrides_default_permalink() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let content = r#"
 +++
 path = "bonjour"
@@ -772,4 +1144,36 @@ Bonjour le monde"#
         assert_eq!(page.slug, "hello");
         assert_eq!(page.permalink, "http://a-website.com/bonjour/");
     }
+
+    #[test]
+    fn can_check_front_matter_against_schema() {
+        let content = r#"
++++
+title = "Hello"
+date = 2018-10-08
+
+[extra]
+rating = "great"
+tags = ["a", "b"]
++++
+Hello world"#;
+        let page = Page::parse(Path::new("post.md"), content, &Config::default(), &PathBuf::new())
+            .unwrap();
+
+        let mut schema = Schema::default();
+        schema.required = vec!["title".to_string(), "date".to_string(), "extra.tags".to_string()];
+        schema.types.insert("date".to_string(), FieldType::Date);
+        schema.types.insert("tags".to_string(), FieldType::Array);
+        assert!(page.check_schema(&schema).is_empty());
+
+        schema.required.push("description".to_string());
+        schema.types.insert("rating".to_string(), FieldType::Integer);
+        assert_eq!(
+            page.check_schema(&schema),
+            vec![
+                "missing required field `description`".to_string(),
+                "field `rating` should be of type `integer`".to_string()
+            ]
+        );
+    }
 }
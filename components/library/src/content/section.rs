@@ -108,7 +108,7 @@ impl Section {
             .map(|p| p.to_string())
             .filter(|p| !p.is_empty())
             .collect::<Vec<_>>();
-        section.permalink = config.make_permalink(&section.path);
+        section.permalink = config.make_permalink_for_lang(&section.path, &section.lang);
         Ok(section)
     }
 
@@ -125,24 +125,15 @@ impl Section {
         let parent_dir = path.parent().unwrap();
         let assets = find_related_assets(parent_dir);
 
-        if let Some(ref globset) = config.ignored_content_globset {
-            // `find_related_assets` only scans the immediate directory (it is not recursive) so our
-            // filtering only needs to work against the file_name component, not the full suffix. If
-            // `find_related_assets` was changed to also return files in subdirectories, we could
-            // use `PathBuf.strip_prefix` to remove the parent directory and then glob-filter
-            // against the remaining path. Note that the current behaviour effectively means that
-            // the `ignored_content` setting in the config file is limited to single-file glob
-            // patterns (no "**" patterns).
-            section.assets = assets
-                .into_iter()
-                .filter(|path| match path.file_name() {
-                    None => false,
-                    Some(file) => !globset.is_match(file),
-                })
-                .collect();
-        } else {
-            section.assets = assets;
-        }
+        // `find_related_assets` only scans the immediate directory (it is not recursive) so our
+        // filtering only needs to work against the file_name component, not the full suffix.
+        section.assets = assets
+            .into_iter()
+            .filter(|path| match path.file_name() {
+                None => false,
+                Some(file) => !config.is_ignored_content(Path::new(file)),
+            })
+            .collect();
 
         section.serialized_assets = section.serialize_assets();
 
@@ -176,6 +167,9 @@ impl Section {
             permalinks,
             self.meta.insert_anchor_links,
         );
+        if self.meta.toc_max_depth.is_some() {
+            context.toc_max_depth = self.meta.toc_max_depth;
+        }
 
         context.tera_context.insert("section", &SerializingSection::from_section_basic(self, None));
 
@@ -192,20 +186,46 @@ impl Section {
 
     /// Renders the page using the default layout, unless specified in front-matter
     pub fn render_html(&self, tera: &Tera, config: &Config, library: &Library) -> Result<String> {
-        let tpl_name = self.get_template_name();
+        self.render_html_with_template(self.get_template_name(), tera, config, library)
+    }
 
+    /// Renders the section with the given template instead of its own
+    pub fn render_html_with_template(
+        &self,
+        tpl_name: &str,
+        tera: &Tera,
+        config: &Config,
+        library: &Library,
+    ) -> Result<String> {
         let mut context = TeraContext::new();
         context.insert("config", config);
+        context.insert("data", &config.data);
         context.insert("current_url", &self.permalink);
         context.insert("current_path", &self.path);
         context.insert("section", &self.to_serialized(library));
         context.insert("lang", &self.lang);
+        if self.meta.single_page {
+            let mut content = String::new();
+            self.push_single_page_content(library, &mut content);
+            context.insert("single_page_content", &content);
+        }
 
         render_template(tpl_name, tera, context, &config.theme).map_err(|e| {
             Error::chain(format!("Failed to render section '{}'", self.file.path.display()), e)
         })
     }
 
+    /// Appends the rendered content of all the pages of that section, in order, followed by
+    /// the ones of its subsections
+    fn push_single_page_content(&self, library: &Library, content: &mut String) {
+        for key in &self.pages {
+            content.push_str(&library.get_page_by_key(*key).content);
+        }
+        for key in &self.subsections {
+            library.get_section_by_key(*key).push_single_page_content(library, content);
+        }
+    }
+
     /// Is this the index section?
     pub fn is_index(&self) -> bool {
         self.file.components.is_empty()
@@ -312,7 +332,12 @@ mod tests {
     #[test]
     fn can_specify_language_in_filename() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let content = r#"
 +++
 +++
@@ -334,7 +359,12 @@ Bonjour le monde"#
     #[test]
     fn can_make_links_to_translated_sections_without_double_trailing_slash() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let content = r#"
 +++
 +++
@@ -351,7 +381,12 @@ Bonjour le monde"#
     #[test]
     fn can_make_links_to_translated_subsections_with_trailing_slash() {
         let mut config = Config::default();
-        config.languages.push(Language { code: String::from("fr"), feed: false, search: false });
+        config.languages.push(Language {
+            code: String::from("fr"),
+            feed: false,
+            search: false,
+            base_url: None,
+        });
         let content = r#"
 +++
 +++
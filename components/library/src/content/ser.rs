@@ -6,7 +6,7 @@ use std::path::Path;
 use serde_derive::Serialize;
 use tera::{Map, Value};
 
-use crate::content::{Page, Section};
+use crate::content::{Page, RelativeDate, Section};
 use crate::library::Library;
 use rendering::Heading;
 
@@ -68,6 +68,7 @@ pub struct SerializingPage<'a> {
     relative_path: &'a str,
     content: &'a str,
     permalink: &'a str,
+    amp_permalink: &'a Option<String>,
     slug: &'a str,
     ancestors: Vec<String>,
     title: &'a Option<String>,
@@ -77,6 +78,8 @@ pub struct SerializingPage<'a> {
     year: Option<i32>,
     month: Option<u32>,
     day: Option<u32>,
+    relative_date: &'a Option<String>,
+    relative_date_components: &'a Option<RelativeDate>,
     taxonomies: &'a HashMap<String, Vec<String>>,
     extra: &'a Map<String, Value>,
     path: &'a str,
@@ -88,10 +91,13 @@ pub struct SerializingPage<'a> {
     assets: &'a [String],
     draft: bool,
     lang: &'a str,
+    is_fallback: bool,
     lighter: Option<Box<SerializingPage<'a>>>,
     heavier: Option<Box<SerializingPage<'a>>>,
     earlier: Option<Box<SerializingPage<'a>>>,
     later: Option<Box<SerializingPage<'a>>>,
+    prev_in_section: Option<Box<SerializingPage<'a>>>,
+    next_in_section: Option<Box<SerializingPage<'a>>>,
     translations: Vec<TranslatedContent<'a>>,
 }
 
@@ -119,6 +125,12 @@ impl<'a> SerializingPage<'a> {
         let later = page
             .later
             .map(|k| Box::new(Self::from_page_basic(pages.get(k).unwrap(), Some(library))));
+        let prev_in_section = page
+            .prev_in_section
+            .map(|k| Box::new(Self::from_page_basic(pages.get(k).unwrap(), Some(library))));
+        let next_in_section = page
+            .next_in_section
+            .map(|k| Box::new(Self::from_page_basic(pages.get(k).unwrap(), Some(library))));
         let ancestors = page
             .ancestors
             .iter()
@@ -132,6 +144,7 @@ impl<'a> SerializingPage<'a> {
             ancestors,
             content: &page.content,
             permalink: &page.permalink,
+            amp_permalink: &page.amp_permalink,
             slug: &page.slug,
             title: &page.meta.title,
             description: &page.meta.description,
@@ -141,6 +154,8 @@ impl<'a> SerializingPage<'a> {
             year,
             month,
             day,
+            relative_date: &page.relative_date,
+            relative_date_components: &page.relative_date_components,
             taxonomies: &page.meta.taxonomies,
             path: &page.path,
             components: &page.components,
@@ -151,10 +166,13 @@ impl<'a> SerializingPage<'a> {
             assets: &page.serialized_assets,
             draft: page.is_draft(),
             lang: &page.lang,
+            is_fallback: page.is_fallback,
             lighter,
             heavier,
             earlier,
             later,
+            prev_in_section,
+            next_in_section,
             translations,
         }
     }
@@ -194,6 +212,7 @@ impl<'a> SerializingPage<'a> {
             ancestors,
             content: &page.content,
             permalink: &page.permalink,
+            amp_permalink: &page.amp_permalink,
             slug: &page.slug,
             title: &page.meta.title,
             description: &page.meta.description,
@@ -203,6 +222,8 @@ impl<'a> SerializingPage<'a> {
             year,
             month,
             day,
+            relative_date: &page.relative_date,
+            relative_date_components: &page.relative_date_components,
             taxonomies: &page.meta.taxonomies,
             path: &page.path,
             components: &page.components,
@@ -213,10 +234,13 @@ impl<'a> SerializingPage<'a> {
             assets: &page.serialized_assets,
             draft: page.is_draft(),
             lang: &page.lang,
+            is_fallback: page.is_fallback,
             lighter: None,
             heavier: None,
             earlier: None,
             later: None,
+            prev_in_section: None,
+            next_in_section: None,
             translations,
         }
     }
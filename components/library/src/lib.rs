@@ -7,7 +7,7 @@ mod taxonomies;
 pub use slotmap::{DenseSlotMap, Key};
 
 pub use crate::library::Library;
-pub use content::{Page, Section, SerializingPage, SerializingSection};
+pub use content::{Page, RelativeDate, Section, SerializingPage, SerializingSection};
 pub use pagination::Paginator;
 pub use sorting::sort_actual_pages_by_date;
-pub use taxonomies::{find_taxonomies, Taxonomy, TaxonomyItem};
+pub use taxonomies::{find_taxonomies, find_term_slug_collisions, Taxonomy, TaxonomyItem};
@@ -130,11 +130,70 @@ impl Library {
         self.sections.values().collect::<Vec<_>>()
     }
 
+    /// Groups the pages having `translations` in their front matter with the pages they point to.
+    /// That group replaces the ones found from the filenames: pages that were automatically
+    /// found as translations of it but aren't part of it end up on their own.
+    fn link_explicit_translations(&mut self, content_path: &Path) {
+        let mut groups = vec![];
+        for (key, page) in &self.pages {
+            if page.meta.translations.is_empty() {
+                continue;
+            }
+            let mut group = set![key];
+            for target in page.meta.translations.values() {
+                let path = content_path.join(target.trim_start_matches("@/"));
+                if let Some(target_key) = self.paths_to_pages.get(&path) {
+                    group.insert(*target_key);
+                }
+            }
+            groups.push((page.file.canonical.clone(), group));
+        }
+
+        for (canonical, group) in groups {
+            if let Some(previous) = self.translations.get(&canonical).cloned() {
+                for key in previous.difference(&group) {
+                    let page = &mut self.pages[*key];
+                    page.file.canonical = page.file.path.clone();
+                    self.translations.insert(page.file.canonical.clone(), set![*key]);
+                }
+            }
+            for key in &group {
+                let page = &mut self.pages[*key];
+                if page.file.canonical != canonical {
+                    if let Some(previous) = self.translations.get_mut(&page.file.canonical) {
+                        previous.remove(key);
+                    }
+                    page.file.canonical = canonical.clone();
+                }
+            }
+            self.translations.insert(canonical, group);
+        }
+    }
+
     /// Find out the direct subsections of each subsection if there are some
     /// as well as the pages for each section
     pub fn populate_sections(&mut self, config: &Config) {
-        let root_path =
-            self.sections.values().find(|s| s.is_index()).map(|s| s.file.parent.clone()).unwrap();
+        // With `language_content_dirs`, each language has its index section in its own directory
+        let roots: HashMap<String, PathBuf> = self
+            .sections
+            .values()
+            .filter(|s| s.is_index())
+            .map(|s| (s.lang.clone(), s.file.parent.clone()))
+            .collect();
+        // The content directory (or the one of the default language with `language_content_dirs`),
+        // found from any content file if there is no index section
+        let root_path = roots
+            .get(&config.default_language)
+            .or_else(|| roots.values().next())
+            .cloned()
+            .or_else(|| {
+                let sections =
+                    self.sections.values().map(|s| (&s.file.parent, s.file.components.len()));
+                let pages = self.pages.values().map(|p| (&p.file.parent, p.file.components.len()));
+                sections.chain(pages).next().and_then(|(parent, depth)| {
+                    parent.ancestors().nth(depth).map(|p| p.to_path_buf())
+                })
+            });
         // We are going to get both the ancestors and grandparents for each section in one go
         let mut ancestors: HashMap<PathBuf, Vec<_>> = HashMap::new();
         let mut subsections: HashMap<PathBuf, Vec<_>> = HashMap::new();
@@ -158,10 +217,21 @@ impl Library {
                 continue;
             }
 
-            let mut path = root_path.clone();
-            let root_key = self.paths_to_sections[&root_path.join(&section.file.filename)];
-            // Index section is the first ancestor of every single section
-            let mut parents = vec![root_key];
+            // The parent of a section is always in `content/{components}`
+            let mut path = roots.get(&section.lang).cloned().unwrap_or_else(|| {
+                section
+                    .file
+                    .parent
+                    .ancestors()
+                    .nth(section.file.components.len())
+                    .expect("Get content directory of section")
+                    .to_path_buf()
+            });
+            // Index section is the first ancestor of every single section, if there is one
+            let mut parents = vec![];
+            if let Some(root_key) = self.paths_to_sections.get(&path.join(&section.file.filename)) {
+                parents.push(*root_key);
+            }
             for component in &section.file.components {
                 path = path.join(component);
                 // Skip itself
@@ -188,11 +258,7 @@ impl Library {
         }
 
         for (key, page) in &mut self.pages {
-            let parent_filename = if page.lang != config.default_language {
-                format!("_index.{}.md", page.lang)
-            } else {
-                "_index.md".to_string()
-            };
+            let parent_filename = config.section_filename(&page.lang);
             let mut parent_section_path = page.file.parent.join(&parent_filename);
             while let Some(section_key) = self.paths_to_sections.get(&parent_section_path) {
                 let parent_is_transparent;
@@ -207,17 +273,20 @@ impl Library {
                 // Don't forget to push the actual parent
                 page.ancestors.push(*section_key);
 
-                // Find the page template if one of a parent has page_template set
-                // Stops after the first one found, keep in mind page.ancestors
+                // Find the page template in the closest parent setting one, with either
+                // `page_template` or `cascade.template`. Keep in mind page.ancestors
                 // is [index, ..., parent] so we need to reverse it first
                 if page.meta.template.is_none() {
-                    for ancestor in page.ancestors.iter().rev() {
+                    page.meta.template = page.ancestors.iter().rev().find_map(|ancestor| {
                         let s = self.sections.get(*ancestor).unwrap();
-                        if s.meta.page_template.is_some() {
-                            page.meta.template = s.meta.page_template.clone();
-                            break;
-                        }
-                    }
+                        s.meta.page_template.clone().or_else(|| s.meta.cascade.template.clone())
+                    });
+                }
+
+                // The closest section wins since values already set are kept
+                for ancestor in page.ancestors.iter().rev() {
+                    let s = self.sections.get(*ancestor).unwrap();
+                    page.meta.apply_cascade(&s.meta.cascade);
                 }
 
                 if !parent_is_transparent {
@@ -242,17 +311,29 @@ impl Library {
             };
         }
 
+        if let (true, Some(root_path)) = (self.is_multilingual, root_path) {
+            let content_path = if config.uses_language_content_dirs() {
+                root_path.parent().unwrap_or(&root_path).to_path_buf()
+            } else {
+                root_path
+            };
+            self.link_explicit_translations(&content_path);
+        }
+
         self.sort_sections_pages();
 
         let sections = self.paths_to_sections.clone();
         let mut sections_weight = HashMap::new();
+        let mut sections_listed = HashMap::new();
         for (key, section) in &self.sections {
             sections_weight.insert(key, section.meta.weight);
+            sections_listed.insert(key, section.meta.in_section_list);
         }
 
         for section in self.sections.values_mut() {
             if let Some(ref children) = subsections.get(&section.file.path) {
-                let mut children: Vec<_> = children.iter().map(|p| sections[p]).collect();
+                let mut children: Vec<_> =
+                    children.iter().map(|p| sections[p]).filter(|k| sections_listed[k]).collect();
                 children.sort_by(|a, b| sections_weight[a].cmp(&sections_weight[b]));
                 section.subsections = children;
             }
@@ -307,6 +388,8 @@ impl Library {
 
             for (k2, val1, val2) in with_siblings {
                 if let Some(page) = self.pages.get_mut(k2) {
+                    page.prev_in_section = val1;
+                    page.next_in_section = val2;
                     match sort_by {
                         SortBy::Date => {
                             page.earlier = val2;
@@ -441,6 +524,7 @@ impl Library {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::content::FileInfo;
 
     #[test]
     fn can_find_no_collisions() {
@@ -505,4 +589,53 @@ mod tests {
         assert!(collisions[0].1.contains(&page.file.relative));
         assert!(collisions[0].1.contains(&page2.file.relative));
     }
+
+    #[test]
+    fn can_find_pages_around_in_section_sorted_by_weight() {
+        let mut library = Library::new(10, 10, false);
+        let mut keys = Vec::new();
+        // Inserted in an order that doesn't match their weight on purpose
+        for (slug, weight) in &[("b", 2), ("c", 3), ("a", 1)] {
+            let mut page = Page::default();
+            page.permalink = format!("https://example.com/{}/", slug);
+            page.meta.weight = Some(*weight);
+            keys.push(library.insert_page(page));
+        }
+        let mut section = Section::default();
+        section.meta.sort_by = SortBy::Weight;
+        section.pages = keys.clone();
+        library.insert_section(section);
+
+        library.sort_sections_pages();
+        let (b, c, a) = (keys[0], keys[1], keys[2]);
+        let page_a = library.get_page_by_key(a);
+        assert_eq!(page_a.prev_in_section, None);
+        assert_eq!(page_a.next_in_section, Some(b));
+        let page_b = library.get_page_by_key(b);
+        assert_eq!(page_b.prev_in_section, Some(a));
+        assert_eq!(page_b.next_in_section, Some(c));
+        let page_c = library.get_page_by_key(c);
+        assert_eq!(page_c.prev_in_section, Some(b));
+        assert_eq!(page_c.next_in_section, None);
+    }
+
+    #[test]
+    fn can_populate_sections_without_index_section() {
+        let config = Config::default();
+        let mut library = Library::new(10, 10, false);
+        let mut section = Section::default();
+        section.lang = config.default_language.clone();
+        section.file =
+            FileInfo::new_section(Path::new("/site/content/api/_index.md"), &PathBuf::new());
+        library.insert_section(section);
+        let mut page = Page::default();
+        page.lang = config.default_language.clone();
+        page.file = FileInfo::new_page(Path::new("/site/content/api/users.md"), &PathBuf::new());
+        let page_key = library.insert_page(page);
+
+        library.populate_sections(&config);
+        let section = library.get_section("/site/content/api/_index.md").unwrap();
+        assert!(section.ancestors.is_empty());
+        assert_eq!(section.pages, vec![page_key]);
+    }
 }
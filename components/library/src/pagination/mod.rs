@@ -44,6 +44,13 @@ impl<'a> Pager<'a> {
     }
 }
 
+/// The number and permalink of a pager, to render a full pagination nav in templates
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct PagerLink<'a> {
+    number: usize,
+    permalink: &'a str,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Paginator<'a> {
     /// All pages in the section/taxonomy
@@ -63,6 +70,9 @@ pub struct Paginator<'a> {
     template: String,
     /// Whether this is the index section, we need it for the template name
     is_index: bool,
+    /// Whether this is one of the `paginate_by_variants` of a section: its first pager is
+    /// under the pagination path rather than being the section itself
+    pub is_variant: bool,
 }
 
 impl<'a> Paginator<'a> {
@@ -82,6 +92,32 @@ impl<'a> Paginator<'a> {
             paginate_path: section.meta.paginate_path.clone(),
             is_index: section.is_index(),
             template: section.get_template_name().to_string(),
+            is_variant: false,
+        };
+
+        paginator.fill_pagers(library);
+        paginator
+    }
+
+    /// Create a paginator for one of the `paginate_by_variants` of a section.
+    /// Its pagers are all under `{paginate_path}{paginate_by}`, including the first one
+    pub fn from_section_variant(
+        section: &'a Section,
+        library: &'a Library,
+        paginate_by: usize,
+    ) -> Paginator<'a> {
+        let mut paginator = Paginator {
+            all_pages: Cow::from(&section.pages[..]),
+            pagers: Vec::with_capacity(section.pages.len() / paginate_by),
+            paginate_by,
+            paginate_reversed: section.meta.paginate_reversed,
+            root: PaginationRoot::Section(section),
+            permalink: section.permalink.clone(),
+            path: section.path.clone(),
+            paginate_path: format!("{}{}", section.meta.paginate_path, paginate_by),
+            is_index: section.is_index(),
+            template: section.get_template_name().to_string(),
+            is_variant: true,
         };
 
         paginator.fill_pagers(library);
@@ -111,6 +147,7 @@ impl<'a> Paginator<'a> {
                 .unwrap_or_else(|| "page".to_string()),
             is_index: false,
             template: format!("{}/single.html", taxonomy.kind.name),
+            is_variant: false,
         };
 
         // taxonomy paginators have no sorting so we won't have to reverse
@@ -145,7 +182,7 @@ impl<'a> Paginator<'a> {
         let mut pagers = vec![];
         for (index, page) in pages.into_iter().enumerate() {
             // First page has no pagination path
-            if index == 0 {
+            if index == 0 && !self.is_variant {
                 pagers.push(Pager::new(1, page, self.permalink.clone(), self.path.clone()));
                 continue;
             }
@@ -169,7 +206,7 @@ impl<'a> Paginator<'a> {
         }
 
         // We always have the index one at least
-        if pagers.is_empty() {
+        if pagers.is_empty() && !self.is_variant {
             pagers.push(Pager::new(1, vec![], self.permalink.clone(), self.path.clone()));
         }
 
@@ -183,25 +220,34 @@ impl<'a> Paginator<'a> {
 
         // Global variables
         paginator.insert("paginate_by", to_value(self.paginate_by).unwrap());
-        paginator.insert("first", to_value(&self.permalink).unwrap());
+        paginator.insert("first", to_value(&self.pagers[0].permalink).unwrap());
         let last_pager = &self.pagers[self.pagers.len() - 1];
         paginator.insert("last", to_value(&last_pager.permalink).unwrap());
 
         // Variables for this specific page
-        if pager_index > 0 {
-            let prev_pager = &self.pagers[pager_index - 1];
-            paginator.insert("previous", to_value(&prev_pager.permalink).unwrap());
+        let previous = if pager_index > 0 {
+            to_value(&self.pagers[pager_index - 1].permalink).unwrap()
         } else {
-            paginator.insert("previous", Value::Null);
-        }
-
-        if pager_index < self.pagers.len() - 1 {
-            let next_pager = &self.pagers[pager_index + 1];
-            paginator.insert("next", to_value(&next_pager.permalink).unwrap());
+            Value::Null
+        };
+        let next = if pager_index < self.pagers.len() - 1 {
+            to_value(&self.pagers[pager_index + 1].permalink).unwrap()
         } else {
-            paginator.insert("next", Value::Null);
-        }
+            Value::Null
+        };
+        // `prev_permalink` and `next_permalink` are aliases, named after the `<link rel>` they
+        // are meant for
+        paginator.insert("prev_permalink", previous.clone());
+        paginator.insert("previous", previous);
+        paginator.insert("next_permalink", next.clone());
+        paginator.insert("next", next);
         paginator.insert("number_pagers", to_value(&self.pagers.len()).unwrap());
+        let pagers = self
+            .pagers
+            .iter()
+            .map(|p| PagerLink { number: p.index, permalink: &p.permalink })
+            .collect::<Vec<_>>();
+        paginator.insert("pagers", to_value(&pagers).unwrap());
         let base_url = if self.paginate_path.is_empty() {
             self.permalink.to_string()
         } else {
@@ -224,6 +270,7 @@ impl<'a> Paginator<'a> {
     ) -> Result<String> {
         let mut context = Context::new();
         context.insert("config", &config);
+        context.insert("data", &config.data);
         match self.root {
             PaginationRoot::Section(s) => {
                 context
@@ -256,7 +303,7 @@ mod tests {
     use config::Taxonomy as TaxonomyConfig;
     use front_matter::SectionFrontMatter;
 
-    use super::Paginator;
+    use super::{PagerLink, Paginator};
 
     fn create_section(is_index: bool, paginate_reversed: bool) -> Section {
         let mut f = SectionFrontMatter::default();
@@ -402,6 +449,57 @@ mod tests {
         assert_eq!(context["total_pages"], to_value(4).unwrap());
     }
 
+    #[test]
+    fn test_can_get_previous_and_next_permalinks_of_middle_pager() {
+        let (section, library) = create_library(false, 5, false);
+        let paginator = Paginator::from_section(&section, &library);
+        assert_eq!(paginator.pagers.len(), 3);
+
+        let context = paginator.build_paginator_context(&paginator.pagers[1]);
+        assert_eq!(context["previous"], to_value("https://vincent.is/posts/").unwrap());
+        assert_eq!(context["next"], to_value("https://vincent.is/posts/page/3/").unwrap());
+        assert_eq!(context["prev_permalink"], context["previous"]);
+        assert_eq!(context["next_permalink"], context["next"]);
+    }
+
+    #[test]
+    fn test_can_list_all_pagers_in_paginator_context() {
+        let (section, library) = create_library(false, 5, false);
+        let paginator = Paginator::from_section(&section, &library);
+        assert_eq!(paginator.pagers.len(), 3);
+
+        let context = paginator.build_paginator_context(&paginator.pagers[1]);
+        assert_eq!(
+            context["pagers"],
+            to_value(vec![
+                PagerLink { number: 1, permalink: "https://vincent.is/posts/" },
+                PagerLink { number: 2, permalink: "https://vincent.is/posts/page/2/" },
+                PagerLink { number: 3, permalink: "https://vincent.is/posts/page/3/" },
+            ])
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_can_create_paginator_variant() {
+        let (section, library) = create_library(false, 3, false);
+        let paginator = Paginator::from_section_variant(&section, &library, 3);
+        assert_eq!(paginator.pagers.len(), 2);
+
+        assert_eq!(paginator.pagers[0].index, 1);
+        assert_eq!(paginator.pagers[0].pages.len(), 3);
+        assert_eq!(paginator.pagers[0].permalink, "https://vincent.is/posts/page3/1/");
+        assert_eq!(paginator.pagers[0].path, "/posts/page3/1/");
+
+        assert_eq!(paginator.pagers[1].index, 2);
+        assert_eq!(paginator.pagers[1].pages.len(), 1);
+        assert_eq!(paginator.pagers[1].permalink, "https://vincent.is/posts/page3/2/");
+
+        let context = paginator.build_paginator_context(&paginator.pagers[0]);
+        assert_eq!(context["first"], to_value("https://vincent.is/posts/page3/1/").unwrap());
+        assert_eq!(context["base_url"], to_value("https://vincent.is/posts/page3/").unwrap());
+    }
+
     #[test]
     fn test_can_create_paginator_for_taxonomy() {
         let (_, library) = create_library(false, 3, false);
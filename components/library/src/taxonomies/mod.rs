@@ -1,11 +1,11 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use serde_derive::Serialize;
 use slotmap::DefaultKey;
 use tera::{Context, Tera};
 
-use config::{Config, Taxonomy as TaxonomyConfig};
+use config::{Config, Taxonomy as TaxonomyConfig, TaxonomySortBy};
 use errors::{bail, Error, Result};
 use utils::templates::render_template;
 
@@ -74,7 +74,10 @@ impl TaxonomyItem {
         let (mut pages, ignored_pages) = sort_pages_by_date(data);
         let item_slug = slugify_paths(name, config.slugify.taxonomies);
         let permalink = if taxonomy.lang != config.default_language {
-            config.make_permalink(&format!("/{}/{}/{}", taxonomy.lang, taxo_slug, item_slug))
+            config.make_permalink_for_lang(
+                &format!("/{}/{}/{}", taxonomy.lang, taxo_slug, item_slug),
+                &taxonomy.lang,
+            )
         } else {
             config.make_permalink(&format!("/{}/{}", taxo_slug, item_slug))
         };
@@ -152,6 +155,10 @@ impl Taxonomy {
                 false
             }
         });
+        if kind.sort_by == TaxonomySortBy::Count {
+            // Stable sort so terms with the same number of pages stay alphabetical
+            sorted_items.sort_by(|a, b| b.pages.len().cmp(&a.pages.len()));
+        }
         Taxonomy { kind, slug, items: sorted_items }
     }
 
@@ -172,12 +179,16 @@ impl Taxonomy {
     ) -> Result<String> {
         let mut context = Context::new();
         context.insert("config", config);
+        context.insert("data", &config.data);
         context.insert("lang", &self.kind.lang);
         context.insert("term", &SerializedTaxonomyItem::from_item(item, library));
         context.insert("taxonomy", &self.kind);
         context.insert(
             "current_url",
-            &config.make_permalink(&format!("{}/{}", self.kind.name, item.slug)),
+            &config.make_permalink_for_lang(
+                &format!("{}/{}", self.kind.name, item.slug),
+                &self.kind.lang,
+            ),
         );
         context.insert("current_path", &format!("/{}/{}/", self.kind.name, item.slug));
 
@@ -195,12 +206,16 @@ impl Taxonomy {
     ) -> Result<String> {
         let mut context = Context::new();
         context.insert("config", config);
+        context.insert("data", &config.data);
         let terms: Vec<SerializedTaxonomyItem> =
             self.items.iter().map(|i| SerializedTaxonomyItem::from_item(i, library)).collect();
         context.insert("terms", &terms);
         context.insert("lang", &self.kind.lang);
         context.insert("taxonomy", &self.kind);
-        context.insert("current_url", &config.make_permalink(&self.kind.name));
+        context.insert(
+            "current_url",
+            &config.make_permalink_for_lang(&self.kind.name, &self.kind.lang),
+        );
         context.insert("current_path", &format!("/{}/", self.kind.name));
 
         render_template(&format!("{}/list.html", self.kind.name), tera, context, &config.theme)
@@ -259,6 +274,40 @@ pub fn find_taxonomies(config: &Config, library: &Library) -> Result<Vec<Taxonom
     Ok(taxonomies)
 }
 
+/// Finds the different term names of a taxonomy that have the same slug, eg `Rust` and `rust`,
+/// and end up merged in a single term.
+/// Returns the taxonomy name, the slug and the term names, sorted for a stable output
+pub fn find_term_slug_collisions(
+    config: &Config,
+    library: &Library,
+) -> Vec<(String, String, Vec<String>)> {
+    // Taxonomies of different languages are different taxonomies even with the same name
+    let mut names_by_slug: BTreeMap<(String, String, String), BTreeSet<&str>> = BTreeMap::new();
+    for page in library.pages_values() {
+        for (name, terms) in &page.meta.taxonomies {
+            let defined = config.taxonomies.iter().any(|t| &t.name == name && t.lang == page.lang);
+            if !defined {
+                continue;
+            }
+            for term in terms {
+                let slug = slugify_paths(term, config.slugify.taxonomies);
+                names_by_slug
+                    .entry((name.clone(), page.lang.clone(), slug))
+                    .or_insert_with(BTreeSet::new)
+                    .insert(term);
+            }
+        }
+    }
+
+    names_by_slug
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|((taxonomy, _, slug), names)| {
+            (taxonomy, slug, names.into_iter().map(|n| n.to_string()).collect())
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,6 +413,31 @@ mod tests {
         assert_eq!(categories.items[1].pages.len(), 1);
     }
 
+    #[test]
+    fn can_sort_taxonomy_terms_by_count() {
+        let mut config = Config::default();
+        let mut library = Library::new(3, 0, false);
+        config.taxonomies = vec![TaxonomyConfig {
+            name: "tags".to_string(),
+            lang: config.default_language.clone(),
+            sort_by: TaxonomySortBy::Count,
+            ..TaxonomyConfig::default()
+        }];
+
+        for tags in &[vec!["rust", "db"], vec!["rust", "js"], vec!["rust", "js"]] {
+            let mut page = Page::default();
+            let mut taxos = HashMap::new();
+            taxos.insert("tags".to_string(), tags.iter().map(|t| t.to_string()).collect());
+            page.meta.taxonomies = taxos;
+            page.lang = config.default_language.clone();
+            library.insert_page(page);
+        }
+
+        let taxonomies = find_taxonomies(&config, &library).unwrap();
+        let names: Vec<_> = taxonomies[0].items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["rust", "js", "db"]);
+    }
+
     #[test]
     fn can_make_slugified_taxonomies() {
         let mut config = Config::default();
@@ -489,7 +563,12 @@ mod tests {
     #[test]
     fn can_make_taxonomies_in_multiple_languages() {
         let mut config = Config::default();
-        config.languages.push(Language { feed: false, code: "fr".to_string(), search: false });
+        config.languages.push(Language {
+            feed: false,
+            code: "fr".to_string(),
+            search: false,
+            base_url: None,
+        });
         let mut library = Library::new(2, 0, true);
 
         config.taxonomies = vec![
@@ -976,4 +1055,34 @@ mod tests {
         // under the safe slugify strategy all terms should be distinct
         assert_eq!(tax.items.len(), 4);
     }
+
+    #[test]
+    fn can_find_terms_with_the_same_slug() {
+        let mut config = Config::default();
+        let mut library = Library::new(2, 0, false);
+        config.taxonomies = vec![TaxonomyConfig {
+            name: "tags".to_string(),
+            lang: config.default_language.clone(),
+            ..TaxonomyConfig::default()
+        }];
+
+        for tags in &[vec!["Rust", "js"], vec!["rust", "db"]] {
+            let mut page = Page::default();
+            let mut taxonomies = HashMap::new();
+            taxonomies.insert("tags".to_string(), tags.iter().map(|t| t.to_string()).collect());
+            page.meta.taxonomies = taxonomies;
+            page.lang = config.default_language.clone();
+            library.insert_page(page);
+        }
+
+        let collisions = find_term_slug_collisions(&config, &library);
+        assert_eq!(
+            collisions,
+            vec![(
+                "tags".to_string(),
+                "rust".to_string(),
+                vec!["Rust".to_string(), "rust".to_string()]
+            )]
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use lazy_static::lazy_static;
-use reqwest::header::{HeaderMap, ACCEPT};
-use reqwest::{blocking::Client, StatusCode};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use reqwest::{blocking::Client, StatusCode, Url};
 
 use config::LinkChecker;
 
@@ -29,6 +29,8 @@ lazy_static! {
     static ref LINKS: Arc<RwLock<HashMap<String, Result>>> = Arc::new(RwLock::new(HashMap::new()));
 }
 
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
 pub fn check_url(url: &str, config: &LinkChecker) -> Result {
     {
         let guard = LINKS.read().unwrap();
@@ -37,12 +39,28 @@ pub fn check_url(url: &str, config: &LinkChecker) -> Result {
         }
     }
 
+    recheck_url(url, config)
+}
+
+/// Same as `check_url` but ignores any previous result for that url
+pub fn recheck_url(url: &str, config: &LinkChecker) -> Result {
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, "text/html".parse().unwrap());
     headers.append(ACCEPT, "*/*".parse().unwrap());
+    if let Some(host_headers) =
+        Url::parse(url).ok().and_then(|u| u.host_str().and_then(|h| config.headers.get(h)))
+    {
+        for (name, value) in host_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header name `{}` in the config: {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid value for header `{}` in the config: {}", name, e))?;
+            headers.insert(name, value);
+        }
+    }
 
     let client = Client::builder()
-        .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+        .user_agent(config.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
         .build()
         .expect("reqwest client build");
 
@@ -129,10 +147,12 @@ fn check_page_for_anchor(url: &str, body: String) -> errors::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::{
-        check_page_for_anchor, check_url, has_anchor, is_valid, message, LinkChecker, LINKS,
+        check_page_for_anchor, check_url, has_anchor, is_valid, message, recheck_url, LinkChecker,
+        DEFAULT_USER_AGENT, LINKS,
     };
     use mockito::mock;
-    use reqwest::StatusCode;
+    use reqwest::{StatusCode, Url};
+    use std::collections::HashMap;
 
     // NOTE: HTTP mock paths below are randomly generated to avoid name
     // collisions. Mocks with the same path can sometimes bleed between tests
@@ -165,6 +185,50 @@ mod tests {
         assert!(LINKS.read().unwrap().get(&url).is_some());
     }
 
+    #[test]
+    fn can_recheck_cached_links() {
+        let url = format!("{}{}", mockito::server_url(), "/c9s2yedq4u");
+        let m = mock("GET", "/c9s2yedq4u").with_status(200).expect(2).create();
+
+        assert!(is_valid(&check_url(&url, &LinkChecker::default())));
+        // Cached: no new request
+        assert!(is_valid(&check_url(&url, &LinkChecker::default())));
+        assert!(is_valid(&recheck_url(&url, &LinkChecker::default())));
+        m.assert();
+    }
+
+    #[test]
+    fn sends_configured_user_agent_and_headers() {
+        let url = format!("{}{}", mockito::server_url(), "/u3hnq8xk2d");
+        let m = mock("GET", "/u3hnq8xk2d")
+            .match_header("user-agent", "my-checker/1.0")
+            .match_header("authorization", "token abc")
+            .with_status(200)
+            .create();
+
+        let mut config = LinkChecker::default();
+        config.user_agent = Some("my-checker/1.0".to_string());
+        let host = Url::parse(&url).unwrap().host_str().unwrap().to_string();
+        let mut headers = HashMap::new();
+        headers.insert("Authorization".to_string(), "token abc".to_string());
+        config.headers.insert(host, headers);
+
+        assert!(is_valid(&recheck_url(&url, &config)));
+        m.assert();
+    }
+
+    #[test]
+    fn sends_default_user_agent() {
+        let url = format!("{}{}", mockito::server_url(), "/p0d7xv4kwa");
+        let m = mock("GET", "/p0d7xv4kwa")
+            .match_header("user-agent", DEFAULT_USER_AGENT)
+            .with_status(200)
+            .create();
+
+        assert!(is_valid(&recheck_url(&url, &LinkChecker::default())));
+        m.assert();
+    }
+
     #[test]
     fn can_follow_301_links() {
         let _m1 = mock("GET", "/c7qrtrv3zz")
@@ -14,6 +14,8 @@ pub struct RenderContext<'a> {
     pub current_page_permalink: &'a str,
     pub permalinks: Cow<'a, HashMap<String, String>>,
     pub insert_anchor: InsertAnchor,
+    /// The deepest heading level included in the table of contents
+    pub toc_max_depth: Option<u32>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -26,12 +28,14 @@ impl<'a> RenderContext<'a> {
     ) -> RenderContext<'a> {
         let mut tera_context = Context::new();
         tera_context.insert("config", config);
+        tera_context.insert("data", &config.data);
         Self {
             tera: Cow::Borrowed(tera),
             tera_context,
             current_page_permalink,
             permalinks: Cow::Borrowed(permalinks),
             insert_anchor,
+            toc_max_depth: config.markdown.toc_max_depth,
             config,
         }
     }
@@ -44,6 +48,7 @@ impl<'a> RenderContext<'a> {
             current_page_permalink: "",
             permalinks: Cow::Owned(HashMap::new()),
             insert_anchor: InsertAnchor::None,
+            toc_max_depth: config.markdown.toc_max_depth,
             config,
         }
     }
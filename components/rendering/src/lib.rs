@@ -6,6 +6,7 @@ mod table_of_contents;
 use errors::Result;
 
 pub use context::RenderContext;
+pub use markdown::find_internal_links;
 use markdown::markdown_to_html;
 pub use shortcode::render_shortcodes;
 pub use table_of_contents::Heading;
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use lazy_static::lazy_static;
 use pulldown_cmark as cmark;
 use regex::Regex;
@@ -16,6 +18,7 @@ use self::cmark::{Event, LinkType, Options, Parser, Tag};
 
 mod codeblock;
 mod fence;
+mod math;
 use self::codeblock::CodeBlock;
 
 const CONTINUE_READING: &str = "<span id=\"continue-reading\"></span>";
@@ -161,6 +164,21 @@ fn get_heading_refs(events: &[Event]) -> Vec<HeadingRef> {
     heading_refs
 }
 
+/// Returns the targets of all the internal links (`@/posts/something.md#hey`) found in the
+/// given markdown, without rendering it
+pub fn find_internal_links(content: &str) -> Vec<String> {
+    let mut opts = Options::empty();
+    opts.insert(Options::ENABLE_TABLES);
+    opts.insert(Options::ENABLE_FOOTNOTES);
+
+    Parser::new_ext(content, opts)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link(_, link, _)) if link.starts_with("@/") => Some(link.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
 pub fn markdown_to_html(content: &str, context: &RenderContext) -> Result<Rendered> {
     lazy_static! {
         static ref EMOJI_REPLACER: gh_emoji::Replacer = gh_emoji::Replacer::new();
@@ -190,8 +208,16 @@ pub fn markdown_to_html(content: &str, context: &RenderContext) -> Result<Render
         opts.insert(Options::ENABLE_SMART_PUNCTUATION);
     }
 
+    // The math is swapped for placeholders before parsing so the LaTeX isn't treated as markdown
+    let (content, maths) = if context.config.markdown.render_math {
+        let (content, maths) = math::extract_math(content);
+        (Cow::Owned(content), maths)
+    } else {
+        (Cow::Borrowed(content), Vec::new())
+    };
+
     {
-        let mut events = Parser::new_ext(content, opts)
+        let mut events = Parser::new_ext(&content, opts)
             .map(|event| {
                 match event {
                     Event::Text(text) => {
@@ -199,12 +225,24 @@ pub fn markdown_to_html(content: &str, context: &RenderContext) -> Result<Render
                         if let Some(ref mut code_block) = highlighter {
                             let html = code_block.highlight(&text);
                             Event::Html(html.into())
-                        } else if context.config.markdown.render_emoji {
-                            let processed_text = EMOJI_REPLACER.replace_all(&text);
-                            Event::Text(processed_text.to_string().into())
                         } else {
-                            // Business as usual
-                            Event::Text(text)
+                            let text = if context.config.markdown.render_emoji {
+                                EMOJI_REPLACER.replace_all(&text).to_string().into()
+                            } else {
+                                text
+                            };
+                            if !maths.is_empty() && math::has_math(&text) {
+                                match math::render_math(&text, &maths) {
+                                    Ok(html) => Event::Html(html.into()),
+                                    Err(e) => {
+                                        error = Some(e);
+                                        Event::Html("".into())
+                                    }
+                                }
+                            } else {
+                                // Business as usual
+                                Event::Text(text)
+                            }
                         }
                     }
                     Event::Start(Tag::CodeBlock(ref kind)) => {
@@ -385,6 +423,7 @@ pub fn markdown_to_html(content: &str, context: &RenderContext) -> Result<Render
                 let mut c = tera::Context::new();
                 c.insert("id", &id);
                 c.insert("level", &heading_ref.level);
+                c.insert("symbol", &context.config.markdown.anchor_symbol);
 
                 let anchor_link = utils::templates::render_template(
                     &ANCHOR_LINK_TEMPLATE,
@@ -416,7 +455,12 @@ pub fn markdown_to_html(content: &str, context: &RenderContext) -> Result<Render
         Ok(Rendered {
             summary_len: if has_summary { html.find(CONTINUE_READING) } else { None },
             body: html,
-            toc: make_table_of_contents(headings),
+            toc: make_table_of_contents(
+                headings
+                    .into_iter()
+                    .filter(|h| context.toc_max_depth.map_or(true, |depth| h.level <= depth))
+                    .collect(),
+            ),
             internal_links_with_anchors,
             external_links,
         })
@@ -0,0 +1,192 @@
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+use lazy_static::lazy_static;
+use pulldown_cmark::escape::escape_html;
+use regex::Regex;
+
+use errors::{Error, Result};
+
+lazy_static! {
+    static ref MATH_PLACEHOLDER_RE: Regex = Regex::new(r"@@ZOLA_MATH_(\d+)@@").unwrap();
+}
+
+/// A math expression found in the markdown
+#[derive(Debug, PartialEq)]
+pub struct Math {
+    pub latex: String,
+    /// `$$...$$` rather than `$...$`
+    pub display: bool,
+}
+
+/// Where the next blank line is, as math and code spans can't go over paragraphs
+fn paragraph_end(content: &str, from: usize) -> usize {
+    content[from..].find("\n\n").map_or(content.len(), |i| from + i)
+}
+
+/// The fence character and length if the line opens or closes a fenced code block
+fn fence_of(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let c = trimmed.chars().next()?;
+    if c != '`' && c != '~' {
+        return None;
+    }
+    let len = trimmed.chars().take_while(|x| *x == c).count();
+    if len >= 3 {
+        Some((c, len))
+    } else {
+        None
+    }
+}
+
+/// Replaces the `$...$` and `$$...$$` expressions of the markdown with placeholders so the markdown
+/// parser doesn't touch the LaTeX, returning the math found in order.
+/// Code blocks, inline code and escaped dollars (`\$`) are left alone.
+pub fn extract_math(content: &str) -> (String, Vec<Math>) {
+    let bytes = content.as_bytes();
+    let mut out = String::with_capacity(content.len());
+    let mut maths = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut at_line_start = true;
+    let mut i = 0;
+
+    let mut push_math = |out: &mut String, latex: &str, display: bool| {
+        out.push_str(&format!("@@ZOLA_MATH_{}@@", maths.len()));
+        maths.push(Math { latex: latex.trim().to_string(), display });
+    };
+
+    while i < bytes.len() {
+        if at_line_start {
+            let line_end = content[i..].find('\n').map_or(content.len(), |e| i + e + 1);
+            let line = &content[i..line_end];
+            match (fence, fence_of(line)) {
+                (Some((c, len)), Some((c2, len2))) if c == c2 && len2 >= len => fence = None,
+                (Some(_), _) => (),
+                (None, Some(f)) => fence = Some(f),
+                (None, None) => at_line_start = false,
+            }
+            if at_line_start {
+                out.push_str(line);
+                i = line_end;
+                continue;
+            }
+        }
+
+        match bytes[i] {
+            b'\n' => {
+                out.push('\n');
+                at_line_start = true;
+                i += 1;
+            }
+            b'\\' if bytes.get(i + 1) == Some(&b'$') => {
+                out.push_str("\\$");
+                i += 2;
+            }
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let end = paragraph_end(content, i);
+                let mut j = i + run;
+                let mut closing = None;
+                while j < end {
+                    if bytes[j] == b'`' {
+                        let other = bytes[j..end].iter().take_while(|b| **b == b'`').count();
+                        if other == run {
+                            closing = Some(j + run);
+                            break;
+                        }
+                        j += other;
+                    } else {
+                        j += 1;
+                    }
+                }
+                let until = closing.unwrap_or(i + run);
+                out.push_str(&content[i..until]);
+                i = until;
+            }
+            b'$' if bytes.get(i + 1) == Some(&b'$') => {
+                let end = paragraph_end(content, i);
+                match content[i + 2..end].find("$$") {
+                    Some(j) if !content[i + 2..i + 2 + j].trim().is_empty() => {
+                        push_math(&mut out, &content[i + 2..i + 2 + j], true);
+                        i += j + 4;
+                    }
+                    _ => {
+                        out.push_str("$$");
+                        i += 2;
+                    }
+                }
+            }
+            b'$' => {
+                // Like Pandoc: no space right after the opening `$` or right before the closing one,
+                // and no digit right after the closing one so amounts like `$5 and $10` stay as-is
+                let closing = if bytes.get(i + 1).map_or(true, |b| b.is_ascii_whitespace()) {
+                    None
+                } else {
+                    let mut j = i + 1;
+                    let mut found = None;
+                    while j < bytes.len() && bytes[j] != b'\n' {
+                        match bytes[j] {
+                            b'\\' => j += 1,
+                            b'$' => {
+                                if !bytes[j - 1].is_ascii_whitespace()
+                                    && !bytes.get(j + 1).map_or(false, |b| b.is_ascii_digit())
+                                {
+                                    found = Some(j);
+                                }
+                                break;
+                            }
+                            _ => (),
+                        }
+                        j += 1;
+                    }
+                    found
+                };
+                match closing {
+                    Some(j) => {
+                        push_math(&mut out, &content[i + 1..j], false);
+                        i = j + 1;
+                    }
+                    None => {
+                        out.push('$');
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                let c = content[i..].chars().next().unwrap();
+                out.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+
+    (out, maths)
+}
+
+/// Escapes the given text and replaces the math placeholders it contains with MathML
+pub fn render_math(text: &str, maths: &[Math]) -> Result<String> {
+    let mut html = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in MATH_PLACEHOLDER_RE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        let math = match caps[1].parse::<usize>().ok().and_then(|idx| maths.get(idx)) {
+            Some(m) => m,
+            None => continue,
+        };
+        escape_html(&mut html, &text[last..whole.start()]).expect("Could not write to buffer");
+        let style = if math.display { DisplayStyle::Block } else { DisplayStyle::Inline };
+        let mathml = latex_to_mathml(&math.latex, style).map_err(|e| {
+            Error::msg(format!("Failed to render the math `{}`: {}", math.latex, e))
+        })?;
+        html.push_str(&mathml);
+        last = whole.end();
+    }
+    escape_html(&mut html, &text[last..]).expect("Could not write to buffer");
+    Ok(html)
+}
+
+/// Whether the text contains a math placeholder
+pub fn has_math(text: &str) -> bool {
+    text.contains("@@ZOLA_MATH_")
+}
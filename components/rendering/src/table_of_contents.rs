@@ -1,7 +1,7 @@
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
 /// Populated while receiving events from the markdown parser
-#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Heading {
     pub level: u32,
     pub id: String,
@@ -448,6 +448,19 @@ fn can_insert_anchor_for_multi_heading() {
     );
 }
 
+#[test]
+fn can_insert_anchor_with_custom_symbol() {
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default();
+    config.markdown.anchor_symbol = "#".to_string();
+    let context = RenderContext::new(&ZOLA_TERA, &config, "", &permalinks_ctx, InsertAnchor::Left);
+    let res = render_content("# Hello", &context).unwrap();
+    assert_eq!(
+        res.body,
+        "<h1 id=\"hello\"><a class=\"zola-anchor\" href=\"#hello\" aria-label=\"Anchor link for: hello\">#</a>Hello</h1>\n"
+    );
+}
+
 // See https://github.com/Keats/gutenberg/issues/42
 #[test]
 fn can_insert_anchor_with_exclamation_mark() {
@@ -518,6 +531,44 @@ fn can_make_toc() {
     assert_eq!(toc[0].children[1].children.len(), 1);
 }
 
+#[test]
+fn can_limit_toc_depth() {
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default();
+    config.markdown.toc_max_depth = Some(2);
+    let context = RenderContext::new(
+        &ZOLA_TERA,
+        &config,
+        "https://mysite.com/something",
+        &permalinks_ctx,
+        InsertAnchor::None,
+    );
+
+    let res = render_content(
+        r#"
+# Heading 1
+
+## Heading 2
+
+### Heading 3
+
+#### Heading 4
+
+## Another Heading 2
+    "#,
+        &context,
+    )
+    .unwrap();
+
+    let toc = res.toc;
+    assert_eq!(toc.len(), 1);
+    assert_eq!(toc[0].children.len(), 2);
+    assert!(toc[0].children.iter().all(|h| h.children.is_empty()));
+    // The headings are still in the content
+    assert!(res.body.contains(r#"<h3 id="heading-3">Heading 3</h3>"#));
+    assert!(res.body.contains(r#"<h4 id="heading-4">Heading 4</h4>"#));
+}
+
 #[test]
 fn can_ignore_tags_in_toc() {
     let permalinks_ctx = HashMap::new();
@@ -1156,3 +1207,41 @@ fn can_use_smart_punctuation() {
     let res = render_content(r#"This -- is "it"..."#, &context).unwrap();
     assert_eq!(res.body, "<p>This – is “it”…</p>\n");
 }
+
+#[test]
+fn can_render_math() {
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default();
+    config.markdown.render_math = true;
+    let context = RenderContext::new(&ZOLA_TERA, &config, "", &permalinks_ctx, InsertAnchor::None);
+    let res = render_content("The square of $x$ is $x^2$.\n\n$$\na_1 + b_1\n$$", &context).unwrap();
+    assert!(res.body.starts_with("<p>The square of <math"));
+    assert!(res.body.contains("<msup>"));
+    assert!(res.body.contains(r#"display="block""#));
+    assert!(!res.body.contains('$'));
+    assert!(!res.body.contains("ZOLA_MATH"));
+}
+
+#[test]
+fn math_is_left_alone_in_code_and_when_escaped() {
+    let permalinks_ctx = HashMap::new();
+    let mut config = Config::default();
+    config.markdown.render_math = true;
+    let context = RenderContext::new(&ZOLA_TERA, &config, "", &permalinks_ctx, InsertAnchor::None);
+    let res =
+        render_content("It costs \\$5, not $5 and $10.\n\n`$x^2$`\n\n```\n$x^2$\n```", &context)
+            .unwrap();
+    assert_eq!(
+        res.body,
+        "<p>It costs $5, not $5 and $10.</p>\n<p><code>$x^2$</code></p>\n<pre><code>$x^2$\n</code></pre>\n"
+    );
+}
+
+#[test]
+fn math_is_not_rendered_when_disabled_in_config() {
+    let permalinks_ctx = HashMap::new();
+    let config = Config::default();
+    let context = RenderContext::new(&ZOLA_TERA, &config, "", &permalinks_ctx, InsertAnchor::None);
+    let res = render_content("$x^2$", &context).unwrap();
+    assert_eq!(res.body, "<p>$x^2$</p>\n");
+}
@@ -0,0 +1,46 @@
+use std::fs::File;
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use relative_path::RelativePathBuf;
+
+use errors::{bail, Error, Result};
+
+/// Errors if we don't know how to write an archive at that path: only gzipped tarballs
+/// are supported, not zip files
+pub fn check_archive_target(target: &Path) -> Result<()> {
+    let name = target.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !name.ends_with(".tar.gz") && !name.ends_with(".tgz") {
+        bail!(
+            "Cannot write an archive at {}: only `.tar.gz` and `.tgz` are supported",
+            target.display()
+        );
+    }
+    Ok(())
+}
+
+/// Writes the given outputs, with their paths relative to the output directory, in a gzipped
+/// tarball at `target`. They are written in the given order and without modification times
+/// so the same outputs always give the same archive.
+pub fn write_archive(outputs: &[(RelativePathBuf, Vec<u8>)], target: &Path) -> Result<()> {
+    check_archive_target(target)?;
+
+    let file = File::create(target)
+        .map_err(|e| Error::chain(format!("Failed to create {}", target.display()), e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (path, content) in outputs {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, path.as_str(), content.as_slice())
+            .map_err(|e| Error::chain(format!("Failed to archive {}", path), e))?;
+    }
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| Error::chain(format!("Failed to write {}", target.display()), e))?;
+
+    Ok(())
+}
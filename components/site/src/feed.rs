@@ -1,14 +1,19 @@
 use std::path::PathBuf;
 
+use chrono::{Duration, Local};
 use rayon::prelude::*;
 use serde_derive::Serialize;
 use tera::Context;
 
 use crate::Site;
+use config::{Config, SortTiesBy};
 use errors::Result;
 use library::{sort_actual_pages_by_date, Page, TaxonomyItem};
 use utils::templates::render_template;
 
+/// Above that many items, an unlimited feed gets a warning as it is likely very big
+const LARGE_FEED_ITEMS: usize = 1000;
+
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct SerializedFeedTaxonomyItem<'a> {
     name: &'a str,
@@ -26,6 +31,16 @@ impl<'a> SerializedFeedTaxonomyItem<'a> {
     }
 }
 
+/// The pages that can go in a feed: the ones with a date, recent enough if `max_age_days` is set
+pub fn feed_pages<'a>(config: &Config, all_pages: Vec<&'a Page>) -> Vec<&'a Page> {
+    let mut pages = all_pages.into_iter().filter(|p| p.meta.date.is_some()).collect::<Vec<_>>();
+    if let Some(days) = config.feed.max_age_days {
+        let oldest = Local::now().naive_local() - Duration::days(i64::from(days));
+        pages.retain(|p| p.meta.datetime.map_or(false, |d| d >= oldest));
+    }
+    pages
+}
+
 pub fn render_feed(
     site: &Site,
     all_pages: Vec<&Page>,
@@ -33,14 +48,41 @@ pub fn render_feed(
     base_path: Option<&PathBuf>,
     additional_context_fn: impl Fn(Context) -> Context,
 ) -> Result<Option<String>> {
-    let mut pages = all_pages.into_iter().filter(|p| p.meta.date.is_some()).collect::<Vec<_>>();
+    let mut pages = feed_pages(&site.config, all_pages);
 
-    // Don't generate a feed if none of the pages has a date
-    if pages.is_empty() {
+    // Don't generate a feed if none of the pages has a date or if there are
+    // not enough of them
+    if pages.is_empty() || pages.len() < site.config.feed.min_items {
         return Ok(None);
     }
 
-    pages.par_sort_unstable_by(sort_actual_pages_by_date);
+    match site.config.feed.sort_ties_by {
+        SortTiesBy::Permalink => pages.par_sort_unstable_by(sort_actual_pages_by_date),
+        SortTiesBy::Title => pages.par_sort_unstable_by(|a, b| {
+            b.meta
+                .datetime
+                .unwrap()
+                .cmp(&a.meta.datetime.unwrap())
+                .then_with(|| a.meta.title.cmp(&b.meta.title))
+                .then_with(|| a.permalink.cmp(&b.permalink))
+        }),
+    }
+
+    // Dates without an offset are in the configured timezone, so we give them the right one
+    // since the feed formats require it
+    let localized_pages: Vec<Page>;
+    if site.config.timezone.is_some() {
+        localized_pages = pages
+            .iter()
+            .map(|p| {
+                let mut page = (*p).clone();
+                page.meta.date = page.meta.date.map(|d| site.config.localize_date(&d));
+                page.meta.updated = page.meta.updated.map(|d| site.config.localize_date(&d));
+                page
+            })
+            .collect();
+        pages = localized_pages.iter().collect();
+    }
 
     let mut context = Context::new();
     context.insert(
@@ -52,23 +94,41 @@ pub fn render_feed(
             .max() // I love lexicographically sorted date strings
             .unwrap(), // Guaranteed because of pages[0].meta.date
     );
+    let feed_filename = &site.config.feed_filename;
+    let feed_url = if let Some(ref base) = base_path {
+        site.config.make_permalink_for_lang(
+            &base.join(feed_filename).to_string_lossy().replace('\\', "/"),
+            lang,
+        )
+    } else {
+        site.config.make_permalink_for_lang(feed_filename, lang)
+    };
+
     let library = site.library.read().unwrap();
     // limit to the last n elements if the limit is set; otherwise use all.
-    let num_entries = site.config.feed_limit.unwrap_or_else(|| pages.len());
+    // A limit of 0 also means all of them.
+    let num_entries = match site.config.feed_limit {
+        Some(limit) if limit > 0 => limit,
+        _ => {
+            if pages.len() > LARGE_FEED_ITEMS {
+                let message = format!(
+                    "The feed at {} has {} items as `feed_limit` isn't set, consider setting it to keep the feed small",
+                    feed_url,
+                    pages.len()
+                );
+                println!("{}", message);
+                site.add_warning(None, message);
+            }
+            pages.len()
+        }
+    };
     let p =
         pages.iter().take(num_entries).map(|x| x.to_serialized_basic(&library)).collect::<Vec<_>>();
 
     context.insert("pages", &p);
     context.insert("config", &site.config);
+    context.insert("data", &site.config.data);
     context.insert("lang", lang);
-
-    let feed_filename = &site.config.feed_filename;
-    let feed_url = if let Some(ref base) = base_path {
-        site.config.make_permalink(&base.join(feed_filename).to_string_lossy().replace('\\', "/"))
-    } else {
-        site.config.make_permalink(feed_filename)
-    };
-
     context.insert("feed_url", &feed_url);
 
     context = additional_context_fn(context);
@@ -0,0 +1,75 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref LINK_RE: Regex = Regex::new(r#"<link\s[^>]*>"#).unwrap();
+    static ref STYLESHEET_RE: Regex = Regex::new(r#"\srel=["']?stylesheet["']?"#).unwrap();
+    // The values can be unquoted, eg when the HTML is minified
+    static ref HREF_RE: Regex =
+        Regex::new(r#"\shref=(?:"([^"]+)"|'([^']+)'|([^\s"'>]+))"#).unwrap();
+    static ref SCRIPT_RE: Regex =
+        Regex::new(r#"<script\s[^>]*?src=(?:"([^"]+)"|'([^']+)'|([^\s"'>]+))"#).unwrap();
+}
+
+/// The attribute value of a match of `HREF_RE` or `SCRIPT_RE`, whether it was quoted or not
+fn attribute_value<'t>(caps: &regex::Captures<'t>) -> &'t str {
+    caps.get(1).or_else(|| caps.get(2)).or_else(|| caps.get(3)).map_or("", |m| m.as_str())
+}
+
+/// Turns an URL found in the HTML into a path on the site, ignoring anything hosted elsewhere
+/// since preloading only makes sense for same-origin resources
+fn to_site_path(url: &str, base_url: &str) -> Option<String> {
+    let base_url = base_url.trim_end_matches('/');
+    if !base_url.is_empty() && url.starts_with(base_url) {
+        let path = &url[base_url.len()..];
+        if path.starts_with('/') {
+            return Some(path.to_string());
+        }
+        return None;
+    }
+
+    if url.starts_with('/') && !url.starts_with("//") {
+        Some(url.to_string())
+    } else {
+        None
+    }
+}
+
+/// Finds the stylesheets and scripts of a rendered page worth preloading.
+/// Returns their path with the `as` value of the preload hint.
+pub fn find_preloads(html: &str, base_url: &str) -> Vec<(String, &'static str)> {
+    let mut preloads: Vec<(String, &'static str)> = Vec::new();
+
+    for link in LINK_RE.find_iter(html) {
+        let tag = link.as_str();
+        if !STYLESHEET_RE.is_match(tag) {
+            continue;
+        }
+        if let Some(path) =
+            HREF_RE.captures(tag).and_then(|c| to_site_path(attribute_value(&c), base_url))
+        {
+            preloads.push((path, "style"));
+        }
+    }
+    for script in SCRIPT_RE.captures_iter(html) {
+        if let Some(path) = to_site_path(attribute_value(&script), base_url) {
+            preloads.push((path, "script"));
+        }
+    }
+
+    preloads.dedup();
+    preloads
+}
+
+/// Renders the preloads of every page in the `_headers` format read by Netlify and Cloudflare
+pub fn render_headers(pages: &[(String, Vec<(String, &'static str)>)]) -> String {
+    let mut out = String::new();
+    for (route, preloads) in pages {
+        out.push_str(route);
+        out.push('\n');
+        for (path, kind) in preloads {
+            out.push_str(&format!("  Link: <{}>; rel=preload; as={}\n", path, kind));
+        }
+    }
+    out
+}
@@ -1,37 +1,67 @@
+pub mod archive;
 pub mod feed;
+pub mod headers;
 pub mod link_checking;
+pub mod render_cache;
 pub mod sass;
 pub mod sitemap;
+pub mod static_files;
 pub mod tpls;
 
-use std::collections::HashMap;
-use std::fs::remove_dir_all;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::{read_dir, remove_dir_all, remove_file, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
 
-use lazy_static::lazy_static;
+use chrono::Utc;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde_derive::Serialize;
+use sha2::{Digest, Sha256};
 use tera::{Context, Tera};
 use walkdir::{DirEntry, WalkDir};
 
-use config::{get_config, Config};
+use crate::render_cache::RenderCache;
+use crate::static_files::StaticResolver;
+use config::{
+    get_config, Config, ExtraFeed, LinkCheckerLevel, PaginatePageOne, TermSlugCollisions,
+};
 use errors::{bail, Error, Result};
 use front_matter::InsertAnchor;
-use library::{find_taxonomies, Library, Page, Paginator, Section, Taxonomy};
-use relative_path::RelativePathBuf;
+use library::{
+    find_taxonomies, find_term_slug_collisions, Library, Page, Paginator, Section, Taxonomy,
+    TaxonomyItem,
+};
+use relative_path::{RelativePath, RelativePathBuf};
 use std::time::Instant;
+use templates::global_fns::SharedTera;
 use templates::render_redirect_template;
 use utils::fs::{
     copy_directory, copy_file_if_needed, create_directory, create_file, ensure_directory_exists,
+    read_file,
 };
 use utils::minify;
 use utils::net::get_available_port;
 use utils::templates::render_template;
 
-lazy_static! {
-    /// The in-memory rendered map content
-    pub static ref SITE_CONTENT: Arc<RwLock<HashMap<RelativePathBuf, String>>> = Arc::new(RwLock::new(HashMap::new()));
-}
+/// The in-memory rendered content, by path relative to the output directory
+pub type SiteContent = Arc<RwLock<HashMap<RelativePathBuf, String>>>;
+
+/// A function transforming the markdown of a page before it is rendered, see
+/// `Site::add_content_preprocessor`
+pub type ContentPreprocessor = fn(&str, &Page) -> String;
+
+/// A function transforming the HTML of a page, or of a section when the page is `None`, once its
+/// template is rendered, see `Site::add_html_postprocessor`
+pub type HtmlPostprocessor = fn(String, Option<&Page>) -> Result<String>;
 
 /// Where are we building the site
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -40,6 +70,99 @@ pub enum BuildMode {
     Disk,
     /// In memory for the content -> `zola serve`
     Memory,
+    /// In memory, returned by `Site::render_to_vec` instead of being written anywhere
+    Collect,
+}
+
+/// Computes the SHA-256 of a theme directory, as an hex string.
+/// The hash is computed over all the files of the theme sorted by their path relative to the
+/// theme directory, with `/` separators: for each of them the path, a NUL byte, the content
+/// of the file and another NUL byte.
+pub fn compute_theme_hash(theme_path: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(theme_path).follow_links(true) {
+        let entry = entry.map_err(|e| {
+            Error::chain(format!("Failed to read the theme at {}", theme_path.display()), e)
+        })?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(theme_path).unwrap();
+            let components: Vec<_> =
+                relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+            files.push((components.join("/"), entry.path().to_path_buf()));
+        }
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for (relative, path) in files {
+        let content = std::fs::read(&path)
+            .map_err(|e| Error::chain(format!("Failed to read {}", path.display()), e))?;
+        hasher.update(relative.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&content);
+        hasher.update(&[0]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// A non-fatal issue found while loading or building the site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildWarning {
+    /// The file the warning is about, if any
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl fmt::Display for BuildWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path {
+            Some(ref path) => write!(f, "{}: {}", path.display(), self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A warning or error of `load` or `build`, written as a JSON line to the diagnostics output
+#[derive(Debug, Serialize)]
+struct Diagnostic<'a> {
+    path: Option<&'a Path>,
+    /// `load` or `build`
+    phase: &'a str,
+    /// `warning` or `error`
+    severity: &'a str,
+    message: &'a str,
+}
+
+/// The message of the error followed by all its causes
+fn error_with_causes(e: &Error) -> String {
+    let mut message = e.to_string();
+    let mut cause = e.source();
+    while let Some(c) = cause {
+        message.push_str(&format!("\nReason: {}", c));
+        cause = c.source();
+    }
+    message
+}
+
+/// The result of the last build in `zola serve`, written to `__zola/status.json`
+#[derive(Debug, Serialize)]
+struct BuildStatus {
+    success: bool,
+    errors: usize,
+    warnings: usize,
+    /// The error message, with its causes, if the build failed
+    error: Option<String>,
+    /// When the build finished, in RFC 3339
+    timestamp: String,
+}
+
+/// A page as listed in the 404 template when `pages_in_404` is set
+#[derive(Debug, Serialize)]
+struct Page404Entry {
+    title: Option<String>,
+    permalink: String,
+    lang: String,
 }
 
 #[derive(Debug)]
@@ -49,6 +172,8 @@ pub struct Site {
     /// The parsed config for the site
     pub config: Config,
     pub tera: Tera,
+    /// A copy of `tera` for the `include_md` function, see `share_tera`
+    pub(crate) shared_tera: SharedTera,
     imageproc: Arc<Mutex<imageproc::Processor>>,
     // the live reload port to be used if there is one
     pub live_reload: Option<u16>,
@@ -63,7 +188,54 @@ pub struct Site {
     pub library: Arc<RwLock<Library>>,
     /// Whether to load draft pages
     include_drafts: bool,
+    /// Whether to ignore all caches (processed images, checked links, unchanged files)
+    force: bool,
+    /// Overrides `config.minify_html` if set, see `set_minify`
+    minify: Option<bool>,
     build_mode: BuildMode,
+    /// Warnings collected during the last load/build
+    warnings: Mutex<Vec<BuildWarning>>,
+    /// How many of `warnings` come from the last load, the others come from the last build
+    load_warnings: usize,
+    /// The term slug collision warnings recorded by the last `populate_taxonomies`, part of
+    /// the load warnings
+    term_slug_warnings: Vec<String>,
+    /// Where the content is rendered in `BuildMode::Memory`.
+    /// Can be replaced to share it with something else, like the `zola serve` web server
+    pub content_map: SiteContent,
+    /// The outputs rendered in `BuildMode::Collect`
+    collected: Mutex<Vec<(RelativePathBuf, Vec<u8>)>>,
+    /// The hash of the content of every asset written so far -> where it was written,
+    /// used by `dedupe_assets`
+    written_assets: Mutex<HashMap<String, PathBuf>>,
+    /// The ETag of every file written by `write_content` during the last build, by path
+    /// relative to the output directory
+    etags: Mutex<HashMap<RelativePathBuf, String>>,
+    /// The hashes computed by the `get_sri_hash` function, cleared at the start of every build
+    pub(crate) sri_hashes: Arc<Mutex<HashMap<String, String>>>,
+    /// The bundles made by the `bundle` function during the current build, by path relative to
+    /// the output directory, written once everything is rendered
+    pub(crate) bundles: Arc<Mutex<HashMap<String, String>>>,
+    /// A hash of the content of the site, computed when loading and available in templates
+    /// with the `build_hash()` function
+    pub build_hash: String,
+    /// When the site was last loaded, in RFC 3339. Available with the `build_time()` function
+    pub build_time: String,
+    /// The thread pool `load` and `build` run in if `build.threads` is set or `set_threads`
+    /// was called, rayon's global pool otherwise
+    thread_pool: Option<Arc<ThreadPool>>,
+    /// Run in order on the markdown of every page before rendering it
+    content_preprocessors: Vec<ContentPreprocessor>,
+    /// Run in order on the HTML of every page and section once rendered
+    html_postprocessors: Vec<HtmlPostprocessor>,
+    /// Whether to reuse the markdown rendered by the previous load, see `use_render_cache`
+    render_cache: bool,
+    /// The directory of the templates overriding the ones of pages and sections, see
+    /// `set_template_variant`
+    template_variant: Option<String>,
+    /// The file the warnings and errors of `load` and `build` are written to as JSON lines,
+    /// see `set_diagnostics_output`
+    diagnostics_output: Option<PathBuf>,
 }
 
 impl Site {
@@ -76,22 +248,45 @@ impl Site {
         config.load_extra_syntaxes(path)?;
 
         if let Some(theme) = config.theme.clone() {
+            let theme_path = path.join("themes").join(&theme);
+            if let Some(ref expected_hash) = config.theme_expected_hash {
+                let hash = compute_theme_hash(&theme_path)?;
+                if &hash != expected_hash {
+                    bail!(
+                        "The theme `{}` doesn't match `theme_expected_hash` in the config: expected {}, got {}",
+                        theme,
+                        expected_hash,
+                        hash
+                    );
+                }
+            }
             // Grab data from the extra section of the theme
-            config.merge_with_theme(&path.join("themes").join(&theme).join("theme.toml"))?;
+            config.merge_with_theme(&theme_path.join("theme.toml"))?;
         }
 
         let tera = tpls::load_tera(path, &config)?;
 
         let content_path = path.join("content");
         let static_path = path.join("static");
-        let imageproc =
-            imageproc::Processor::new(content_path.clone(), &static_path, &config.base_url);
+        let mut imageproc = imageproc::Processor::new(
+            content_path.clone(),
+            &static_path,
+            &config.base_url,
+            config.imageproc.output_dir(),
+        );
+        imageproc.set_limits(
+            config.imageproc.max_dimension,
+            config.imageproc.clamp_to_max_dimension,
+            config.imageproc.allow_upscale,
+        );
+        imageproc.set_readable_filenames(config.imageproc.readable_filenames);
         let output_path = path.join(config.output_dir.clone());
 
-        let site = Site {
+        let mut site = Site {
             base_path: path.to_path_buf(),
             config,
             tera,
+            shared_tera: Arc::new(RwLock::new(Arc::new(Tera::default()))),
             imageproc: Arc::new(Mutex::new(imageproc)),
             live_reload: None,
             output_path,
@@ -100,36 +295,200 @@ impl Site {
             taxonomies: Vec::new(),
             permalinks: HashMap::new(),
             include_drafts: false,
+            force: false,
+            minify: None,
             // We will allocate it properly later on
             library: Arc::new(RwLock::new(Library::new(0, 0, false))),
             build_mode: BuildMode::Disk,
+            warnings: Mutex::new(Vec::new()),
+            load_warnings: 0,
+            term_slug_warnings: Vec::new(),
+            content_map: SiteContent::default(),
+            collected: Mutex::new(Vec::new()),
+            written_assets: Mutex::new(HashMap::new()),
+            etags: Mutex::new(HashMap::new()),
+            sri_hashes: Arc::new(Mutex::new(HashMap::new())),
+            bundles: Arc::new(Mutex::new(HashMap::new())),
+            build_hash: String::new(),
+            build_time: String::new(),
+            thread_pool: None,
+            content_preprocessors: Vec::new(),
+            html_postprocessors: Vec::new(),
+            render_cache: false,
+            template_variant: None,
+            diagnostics_output: None,
         };
+        let threads = site.config.build.threads;
+        site.set_threads(threads)?;
 
         Ok(site)
     }
 
     /// Enable some `zola serve` related options
     pub fn enable_serve_mode(&mut self) {
-        SITE_CONTENT.write().unwrap().clear();
+        self.content_map.write().unwrap().clear();
         self.config.enable_serve_mode();
         self.build_mode = BuildMode::Memory;
     }
 
+    /// Registers a function to transform the markdown of every page before it is rendered,
+    /// eg to expand custom macros. Preprocessors run in the order they were added, each one
+    /// getting the output of the previous one. The `raw_content` of the pages is left as is.
+    pub fn add_content_preprocessor(&mut self, preprocessor: ContentPreprocessor) {
+        self.content_preprocessors.push(preprocessor);
+    }
+
+    /// Registers a function to transform the HTML of every page and section once their template
+    /// is rendered, before it is minified and the live reload script is added, eg to add
+    /// `loading="lazy"` to all images. Postprocessors run in the order they were added, each one
+    /// getting the output of the previous one, and get `None` rather than a page for sections.
+    pub fn add_html_postprocessor(&mut self, postprocessor: HtmlPostprocessor) {
+        self.html_postprocessors.push(postprocessor);
+    }
+
+    /// Runs the HTML postprocessors on the rendered HTML of a page or section
+    fn postprocess_html(&self, html: String, page: Option<&Page>) -> Result<String> {
+        self.html_postprocessors
+            .iter()
+            .try_fold(html, |html, postprocessor| postprocessor(html, page))
+    }
+
+    /// Renders pages and sections with `{variant}/{template}` rather than `{template}` when there
+    /// is one, eg `variant-b/page.html` instead of `page.html`, to compare alternative templates.
+    /// Combined with `set_output_path`, the variant can be built next to the usual output.
+    pub fn set_template_variant(&mut self, variant: Option<String>) {
+        self.template_variant = variant;
+    }
+
+    /// The template of the variant replacing the given one if there is one, the given one otherwise
+    fn resolve_template<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if let Some(ref variant) = self.template_variant {
+            let variant_name = format!("{}/{}", variant, name);
+            if self.tera.templates.contains_key(&variant_name) {
+                return Cow::Owned(variant_name);
+            }
+        }
+        Cow::Borrowed(name)
+    }
+
     /// Set the site to load the drafts.
     /// Needs to be called before loading it
     pub fn include_drafts(&mut self) {
         self.include_drafts = true;
     }
 
+    /// Ignore all caches for a guaranteed clean rebuild: images are always processed,
+    /// external links always checked and the output directory always cleaned.
+    pub fn force_rebuild(&mut self) {
+        self.force = true;
+        let mut imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (force_rebuild)");
+        imageproc.set_force(true);
+    }
+
+    /// Persists the rendered markdown of pages and sections with the links found in them in
+    /// `.zola-cache` and only renders again the ones that changed since the previous load.
+    /// Meant for `zola check`: the output of the templates isn't cached.
+    /// Needs to be called before loading the site
+    pub fn use_render_cache(&mut self) {
+        self.render_cache = true;
+    }
+
+    /// Whether to minify the HTML output, regardless of `minify_html` in the config.
+    /// Useful to debug unminified output in `zola serve` for example
+    pub fn set_minify(&mut self, minify: bool) {
+        self.minify = Some(minify);
+    }
+
+    /// Records a warning so it can be retrieved with `warnings` once the build is done
+    pub fn add_warning(&self, path: Option<&Path>, message: String) {
+        let warning = BuildWarning { path: path.map(|p| p.to_path_buf()), message };
+        self.warnings.lock().expect("Couldn't lock warnings (add_warning)").push(warning);
+    }
+
+    /// Records the warnings of the image operations requested since the last call
+    fn add_image_warnings(&self) {
+        let warnings = self
+            .imageproc
+            .lock()
+            .expect("Couldn't lock imageproc (add_image_warnings)")
+            .take_warnings();
+        for message in warnings {
+            println!("Warning: {}", message);
+            self.add_warning(None, message);
+        }
+    }
+
+    /// Also writes the warnings and errors of `load` and `build` to the given file, one JSON object
+    /// per line with the `path`, `phase`, `severity` and `message` of the diagnostic, for CI
+    /// systems and other tools. The file is truncated when calling this.
+    pub fn set_diagnostics_output<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        create_file(path, "")?;
+        self.diagnostics_output = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Appends the warnings recorded from `first_warning` onwards and the error of the phase,
+    /// if any, to the diagnostics output
+    fn write_diagnostics(&self, phase: &str, first_warning: usize, res: &Result<()>) -> Result<()> {
+        let path = match self.diagnostics_output {
+            Some(ref p) => p,
+            None => return Ok(()),
+        };
+        let warnings = self.warnings();
+        let error = res.as_ref().err().map(error_with_causes);
+        let mut diagnostics: Vec<_> = warnings
+            .iter()
+            .skip(first_warning)
+            .map(|w| Diagnostic {
+                path: w.path.as_deref(),
+                phase,
+                severity: "warning",
+                message: &w.message,
+            })
+            .collect();
+        if let Some(ref message) = error {
+            diagnostics.push(Diagnostic { path: None, phase, severity: "error", message });
+        }
+
+        let mut file = OpenOptions::new().append(true).create(true).open(path).map_err(|e| {
+            Error::chain(format!("Failed to open diagnostics output {:?}", path), e)
+        })?;
+        for diagnostic in diagnostics {
+            let json = serde_json::to_string(&diagnostic).expect("Couldn't serialize a diagnostic");
+            writeln!(file, "{}", json).map_err(|e| {
+                Error::chain(format!("Failed to write to diagnostics output {:?}", path), e)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// All the warnings found during the last `load` and `build`
+    pub fn warnings(&self) -> Vec<BuildWarning> {
+        self.warnings.lock().expect("Couldn't lock warnings (warnings)").clone()
+    }
+
+    /// The strong ETag of every file rendered during the last build, by path relative
+    /// to the output directory. It only depends on the content of the file so it stays
+    /// the same across builds as long as that content doesn't change
+    pub fn etags(&self) -> HashMap<RelativePathBuf, String> {
+        self.etags.lock().unwrap().clone()
+    }
+
     /// The index sections are ALWAYS at those paths
     /// There are one index section for the default language + 1 per language
+    /// or at the root of the directory of each language with `language_content_dirs`
     fn index_section_paths(&self) -> Vec<(PathBuf, Option<String>)> {
-        let mut res = vec![(self.content_path.join("_index.md"), None)];
+        let index_path = |lang: &str| {
+            let root = match self.config.language_content_dirs.get(lang) {
+                Some(dir) => self.content_path.join(dir),
+                None => self.content_path.clone(),
+            };
+            root.join(self.config.section_filename(lang))
+        };
+        let mut res = vec![(index_path(&self.config.default_language), None)];
         for language in &self.config.languages {
-            res.push((
-                self.content_path.join(format!("_index.{}.md", language.code)),
-                Some(language.code.clone()),
-            ));
+            res.push((index_path(&language.code), Some(language.code.clone())));
         }
         res
     }
@@ -149,13 +508,45 @@ impl Site {
     /// Reloads the templates and rebuild the site without re-rendering the Markdown.
     pub fn reload_templates(&mut self) -> Result<()> {
         self.tera.full_reload()?;
+        self.share_tera();
         // TODO: be smarter than that, no need to recompile sass for example
         self.build()
     }
 
+    /// Same as `reload_templates` but only re-renders the taxonomies, for when only
+    /// taxonomy templates changed
+    pub fn reload_taxonomy_templates(&mut self) -> Result<()> {
+        self.tera.full_reload()?;
+        self.share_tera();
+        self.rebuild_taxonomies()
+    }
+
+    /// Finds the taxonomies again and renders them with their feeds, without rendering
+    /// anything else. Needs the site to be loaded.
+    /// This takes `&mut self` as the taxonomies are replaced and the global functions holding
+    /// a copy of them have to be registered again in the Tera instance
+    pub fn rebuild_taxonomies(&mut self) -> Result<()> {
+        self.populate_taxonomies()?;
+        // The taxonomies are cloned in some global functions
+        tpls::register_early_global_fns(self);
+        tpls::register_tera_global_fns(self);
+        self.render_taxonomies()
+    }
+
+    /// Updates the copy of the templates rendering the files included with `include_md`.
+    /// Needs to be called whenever the templates or their functions change
+    pub(crate) fn share_tera(&self) {
+        *self.shared_tera.write().unwrap() = Arc::new(self.tera.clone());
+    }
+
     pub fn set_base_url(&mut self, base_url: String) {
         let mut imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (set_base_url)");
         imageproc.set_base_url(&base_url);
+        // The override applies to every language, otherwise `zola serve` would link to the
+        // real domains of the languages having their own base url
+        for language in self.config.languages.iter_mut() {
+            language.base_url = None;
+        }
         self.config.base_url = base_url;
     }
 
@@ -163,21 +554,56 @@ impl Site {
         self.output_path = path.as_ref().to_path_buf();
     }
 
+    /// Runs `load` and `build` in a dedicated pool of `threads` threads instead of rayon's
+    /// global one, or in the global one again with `None`
+    pub fn set_threads(&mut self, threads: Option<usize>) -> Result<()> {
+        self.thread_pool = match threads {
+            Some(threads) => {
+                let pool = ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(|e| Error::chain("Failed to create the thread pool", e))?;
+                Some(Arc::new(pool))
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
     /// Reads all .md files in the `content` directory and create pages/sections
     /// out of them
     pub fn load(&mut self) -> Result<()> {
+        let res = match self.thread_pool.clone() {
+            Some(pool) => pool.install(|| self.load_site()),
+            None => self.load_site(),
+        };
+        // Warnings are cleared when loading so they all come from this load
+        self.load_warnings = self.warnings().len();
+        self.write_diagnostics("load", 0, &res)?;
+        res
+    }
+
+    fn load_site(&mut self) -> Result<()> {
         let base_path = self.base_path.to_string_lossy().replace("\\", "/");
 
         self.library = Arc::new(RwLock::new(Library::new(0, 0, self.config.is_multilingual())));
+        self.warnings.lock().expect("Couldn't lock warnings (load)").clear();
+        self.load_warnings = 0;
+        self.term_slug_warnings.clear();
+        // Everything is rendered again so only the images still in use are processed
+        self.imageproc.lock().expect("Couldn't lock imageproc (load)").clear_ops();
+        self.config.data = tpls::load_data_directory(&self.base_path.join("data"))?;
         let mut pages_insert_anchors = HashMap::new();
+        let mut schema_errors = Vec::new();
 
         // not the most elegant loop, but this is necessary to use skip_current_dir
         // which we can only decide to use after we've deserialised the section
         // so it's kinda necessecary
         let mut dir_walker = WalkDir::new(format!("{}/{}", base_path, "content/")).into_iter();
         let mut allowed_index_filenames: Vec<_> =
-            self.config.languages.iter().map(|l| format!("_index.{}.md", l.code)).collect();
+            self.config.languages.iter().map(|l| self.config.section_filename(&l.code)).collect();
         allowed_index_filenames.push("_index.md".to_string());
+        allowed_index_filenames.dedup();
 
         loop {
             let entry: DirEntry = match dir_walker.next() {
@@ -192,14 +618,29 @@ impl Site {
             };
 
             // ignore excluded content
-            match &self.config.ignored_content_globset {
-                Some(gs) => {
-                    if gs.is_match(path) {
+            if self.config.is_ignored_content(path) {
+                continue;
+            }
+
+            // With `language_content_dirs`, only the directories of the languages have content
+            if self.config.uses_language_content_dirs() {
+                let relative = path.strip_prefix(&self.content_path).unwrap_or(path);
+                let lang_dir =
+                    relative.components().next().map(|c| c.as_os_str().to_string_lossy());
+                match lang_dir {
+                    // The `content` directory itself, we still need to walk through it
+                    None => continue,
+                    Some(dir) if self.config.language_of_content_dir(&dir).is_none() => {
+                        let message = "Ignored as it isn't in one of the directories of `language_content_dirs`".to_string();
+                        println!("{}: {}", path.display(), message);
+                        self.add_warning(Some(path), message);
+                        if path.is_dir() {
+                            dir_walker.skip_current_dir();
+                        }
                         continue;
                     }
+                    Some(_) => (),
                 }
-
-                None => (),
             }
 
             // we process a section when we encounter the dir
@@ -233,7 +674,9 @@ impl Site {
                             } else {
                                 // https://github.com/getzola/zola/issues/1244
                                 if path_str.starts_with("_index.") {
-                                    println!("Expected a section filename, got `{}`. Allowed values: `{:?}`", path_str, &allowed_index_filenames);
+                                    let message = format!("Expected a section filename, got `{}`. Allowed values: `{:?}`", path_str, &allowed_index_filenames);
+                                    println!("{}", message);
+                                    self.add_warning(Some(f.path()), message);
                                 }
                                 None
                             }
@@ -267,6 +710,11 @@ impl Site {
                 if page.meta.draft && !self.include_drafts {
                     continue;
                 }
+                if let Some(schema) = self.config.schema.get(&page.file.components.join("/")) {
+                    for error in page.check_schema(schema) {
+                        schema_errors.push(format!("{}: {}", page.file.path.display(), error));
+                    }
+                }
                 pages_insert_anchors.insert(
                     page.file.path.clone(),
                     self.find_parent_section_insert_anchor(&page.file.parent.clone(), &page.lang),
@@ -274,7 +722,16 @@ impl Site {
                 self.add_page(page, false)?;
             }
         }
+        if !schema_errors.is_empty() {
+            bail!(
+                "Some pages don't match the front matter schema of their section:\n{}",
+                schema_errors.join("\n")
+            );
+        }
         self.create_default_index_sections()?;
+        if self.config.translation_fallback {
+            self.add_translation_fallbacks()?;
+        }
 
         {
             let library = self.library.read().unwrap();
@@ -282,14 +739,44 @@ impl Site {
             if !collisions.is_empty() {
                 return Err(Error::from_collisions(collisions));
             }
+
+            for page in library.pages().values() {
+                for (lang, target) in &page.meta.translations {
+                    let path = self.content_path.join(target.trim_start_matches("@/"));
+                    match library.get_page(&path) {
+                        Some(p) if &p.lang == lang => (),
+                        Some(p) => bail!(
+                            "Page `{}` has a {} translation `{}` which is in {}",
+                            page.file.path.display(),
+                            lang,
+                            target,
+                            p.lang
+                        ),
+                        None => bail!(
+                            "Page `{}` has a {} translation `{}` which doesn't exist",
+                            page.file.path.display(),
+                            lang,
+                            target
+                        ),
+                    }
+                }
+            }
         }
 
         // taxonomy Tera fns are loaded in `register_early_global_fns`
         // so we do need to populate it first.
         self.populate_taxonomies()?;
+        self.build_hash = self.compute_build_hash();
+        self.build_time = Utc::now().to_rfc3339();
         tpls::register_early_global_fns(self);
         self.populate_sections();
+        self.warn_about_ignored_pages();
+        self.check_feed_template()?;
+        if self.config.link_checker.check_internal_links {
+            link_checking::check_internal_links(&self)?;
+        }
         self.render_markdown()?;
+        self.add_image_warnings();
         tpls::register_tera_global_fns(self);
 
         // Needs to be done after rendering markdown as we only get the anchors at that point
@@ -297,13 +784,68 @@ impl Site {
 
         if self.config.is_in_check_mode() {
             link_checking::check_external_links(&self)?;
+        } else if self.config.is_in_build_mode()
+            && self.config.link_checker.external_level == LinkCheckerLevel::Warn
+        {
+            link_checking::warn_about_external_links(&self)?;
         }
 
         Ok(())
     }
 
+    /// Adds a fallback page, with the content of the default language, for every language a page
+    /// of the default language isn't translated to
+    fn add_translation_fallbacks(&mut self) -> Result<()> {
+        let fallbacks: Vec<_> = {
+            let library = self.library.read().unwrap();
+            let translated: HashSet<_> =
+                library.pages_values().iter().map(|p| (&p.file.canonical, &p.lang)).collect();
+            library
+                .pages_values()
+                .iter()
+                .filter(|p| p.lang == self.config.default_language)
+                .flat_map(|page| {
+                    self.config
+                        .languages
+                        .iter()
+                        .filter(|l| l.code != self.config.default_language)
+                        .filter(|l| !translated.contains(&(&page.file.canonical, &l.code)))
+                        .map(|l| page.translation_fallback(&l.code, &self.config))
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        };
+        for page in fallbacks {
+            self.add_page(page, false)?;
+        }
+        Ok(())
+    }
+
+    /// Hashes the path and raw content of every page and section, so the hash only changes
+    /// when the content does
+    pub fn compute_build_hash(&self) -> String {
+        let library = self.library.read().unwrap();
+        let mut content: Vec<(&PathBuf, &str)> = library
+            .pages_values()
+            .into_iter()
+            .map(|p| (&p.file.path, p.raw_content.as_str()))
+            .chain(
+                library
+                    .sections_values()
+                    .into_iter()
+                    .map(|s| (&s.file.path, s.raw_content.as_str())),
+            )
+            .collect();
+        content.sort();
+
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
     /// Insert a default index section for each language if necessary so we don't need to create
-    /// a _index.md to render the index page at the root of the site
+    /// a _index.md to render the index page at the root of the site, unless `auto_index_section`
+    /// is turned off
     pub fn create_default_index_sections(&mut self) -> Result<()> {
         for (index_path, lang) in self.index_section_paths() {
             if let Some(ref index_section) = self.library.read().unwrap().get_section(&index_path) {
@@ -317,26 +859,31 @@ impl Site {
             }
             let mut library = self.library.write().expect("Get lock for load");
             // Not in else because of borrow checker
-            if !library.contains_section(&index_path) {
+            if self.config.auto_index_section && !library.contains_section(&index_path) {
                 let mut index_section = Section::default();
-                index_section.file.parent = self.content_path.clone();
+                index_section.file.parent = index_path.parent().unwrap().to_path_buf();
                 index_section.file.filename =
                     index_path.file_name().unwrap().to_string_lossy().to_string();
+                index_section.file.name =
+                    index_path.file_stem().unwrap().to_string_lossy().to_string();
+                index_section.file.relative = index_path
+                    .strip_prefix(&self.content_path)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace("\\", "/");
+                index_section.file.path = index_path;
                 if let Some(ref l) = lang {
-                    index_section.file.name = format!("_index.{}", l);
                     index_section.path = format!("{}/", l);
-                    index_section.permalink = self.config.make_permalink(l);
-                    let filename = format!("_index.{}.md", l);
-                    index_section.file.path = self.content_path.join(&filename);
-                    index_section.file.relative = filename;
+                    index_section.permalink = self.config.make_permalink_for_lang(l, l);
                 } else {
-                    index_section.file.name = "_index".to_string();
                     index_section.permalink = self.config.make_permalink("");
-                    index_section.file.path = self.content_path.join("_index.md");
-                    index_section.file.relative = "_index.md".to_string();
                     index_section.path = "/".to_string();
                 }
-                index_section.lang = index_section.file.find_language(&self.config)?;
+                index_section.lang = if self.config.uses_language_content_dirs() {
+                    lang.unwrap_or_else(|| self.config.default_language.clone())
+                } else {
+                    index_section.file.find_language(&self.config)?
+                };
                 library.insert_section(index_section);
             }
         }
@@ -352,6 +899,7 @@ impl Site {
         let permalinks = &self.permalinks;
         let tera = &self.tera;
         let config = &self.config;
+        let preprocessors = &self.content_preprocessors;
 
         // This is needed in the first place because of silly borrow checker
         let mut pages_insert_anchors = HashMap::new();
@@ -362,6 +910,12 @@ impl Site {
             );
         }
 
+        let cache = if self.render_cache {
+            Some(RenderCache::new(self, &pages_insert_anchors))
+        } else {
+            None
+        };
+
         let mut library = self.library.write().expect("Get lock for render_markdown");
         library
             .pages_mut()
@@ -369,8 +923,11 @@ impl Site {
             .collect::<Vec<_>>()
             .par_iter_mut()
             .map(|page| {
+                if cache.as_ref().map_or(false, |c| c.restore_page(page)) {
+                    return Ok(());
+                }
                 let insert_anchor = pages_insert_anchors[&page.file.path];
-                page.render_markdown(permalinks, tera, config, insert_anchor)
+                render_page_markdown(page, preprocessors, permalinks, tera, config, insert_anchor)
             })
             .collect::<Result<()>>()?;
 
@@ -379,9 +936,18 @@ impl Site {
             .values_mut()
             .collect::<Vec<_>>()
             .par_iter_mut()
-            .map(|section| section.render_markdown(permalinks, tera, config))
+            .map(|section| {
+                if cache.as_ref().map_or(false, |c| c.restore_section(section)) {
+                    return Ok(());
+                }
+                section.render_markdown(permalinks, tera, config)
+            })
             .collect::<Result<()>>()?;
 
+        if let Some(cache) = cache {
+            cache.save(&library)?;
+        }
+
         Ok(())
     }
 
@@ -392,7 +958,14 @@ impl Site {
         if render_md {
             let insert_anchor =
                 self.find_parent_section_insert_anchor(&page.file.parent, &page.lang);
-            page.render_markdown(&self.permalinks, &self.tera, &self.config, insert_anchor)?;
+            render_page_markdown(
+                &mut page,
+                &self.content_preprocessors,
+                &self.permalinks,
+                &self.tera,
+                &self.config,
+                insert_anchor,
+            )?;
         }
 
         let mut library = self.library.write().expect("Get lock for add_page");
@@ -446,11 +1019,7 @@ impl Site {
         parent_path: &PathBuf,
         lang: &str,
     ) -> InsertAnchor {
-        let parent = if lang != self.config.default_language {
-            parent_path.join(format!("_index.{}.md", lang))
-        } else {
-            parent_path.join("_index.md")
-        };
+        let parent = parent_path.join(self.config.section_filename(lang));
         match self.library.read().unwrap().get_section(&parent) {
             Some(s) => s.meta.insert_anchor_links,
             None => InsertAnchor::None,
@@ -464,22 +1033,126 @@ impl Site {
         library.populate_sections(&self.config);
     }
 
+    /// Records a warning for each page that couldn't be sorted in its section
+    fn warn_about_ignored_pages(&self) {
+        let library = self.library.read().unwrap();
+        for section in library.sections_values() {
+            for key in &section.ignored_pages {
+                self.add_warning(
+                    Some(&library.get_page_by_key(*key).file.path),
+                    "Page ignored (missing date or weight in a sorted section)".to_string(),
+                );
+            }
+        }
+    }
+
+    /// Feeds can be requested by the config, a language, a taxonomy or a section, independently
+    /// of each other: make sure the feed template can be found as soon as any of them needs it
+    /// instead of erroring halfway through the build
+    fn check_feed_template(&self) -> Result<()> {
+        let needs_feed = self.config.generate_feed
+            || self.config.languages.iter().any(|l| l.feed)
+            || self.config.taxonomies.iter().any(|t| t.feed)
+            || self.config.feed.all_sections
+            || self.library.read().unwrap().sections_values().iter().any(|s| s.meta.generate_feed);
+        if !needs_feed {
+            return Ok(());
+        }
+
+        let feed_filename = &self.config.feed_filename;
+        let mut candidates =
+            vec![feed_filename.to_string(), format!("__zola_builtins/{}", feed_filename)];
+        if let Some(ref theme) = self.config.theme {
+            candidates.push(format!("{}/templates/{}", theme, feed_filename));
+        }
+        if !candidates.iter().any(|name| self.tera.templates.contains_key(name)) {
+            bail!(
+                "A feed was requested but the feed template `{}` could not be found",
+                feed_filename
+            );
+        }
+
+        Ok(())
+    }
+
     /// Find all the tags and categories if it's asked in the config
     pub fn populate_taxonomies(&mut self) -> Result<()> {
         if self.config.taxonomies.is_empty() {
             return Ok(());
         }
 
-        self.taxonomies = find_taxonomies(&self.config, &self.library.read().unwrap())?;
+        let library = self.library.clone();
+        let library = library.read().unwrap();
+        let mut messages = Vec::new();
+        for (taxonomy, slug, names) in find_term_slug_collisions(&self.config, &library) {
+            let message = format!(
+                "The {} terms {} have the same slug `{}` and are merged in a single term",
+                taxonomy,
+                names.iter().map(|n| format!("`{}`", n)).collect::<Vec<_>>().join(", "),
+                slug
+            );
+            match self.config.term_slug_collisions {
+                TermSlugCollisions::Error => bail!(message),
+                TermSlugCollisions::Warn => messages.push(message),
+            }
+        }
+        self.replace_term_slug_warnings(messages);
+        self.taxonomies = find_taxonomies(&self.config, &library)?;
 
         Ok(())
     }
 
+    /// Replaces the term slug collision warnings of the previous `populate_taxonomies` so
+    /// populating the taxonomies again, eg in `rebuild_taxonomies`, doesn't duplicate them.
+    /// They are kept with the load warnings, before the ones of the last build
+    fn replace_term_slug_warnings(&mut self, messages: Vec<String>) {
+        let previous = std::mem::replace(&mut self.term_slug_warnings, messages.clone());
+        let mut warnings =
+            self.warnings.lock().expect("Couldn't lock warnings (replace_term_slug_warnings)");
+        let before = warnings.len();
+        warnings.retain(|w| w.path.is_some() || !previous.contains(&w.message));
+        self.load_warnings -= (before - warnings.len()).min(self.load_warnings);
+
+        for message in messages {
+            println!("Warning: {}", message);
+            let index = self.load_warnings.min(warnings.len());
+            warnings.insert(index, BuildWarning { path: None, message });
+            self.load_warnings += 1;
+        }
+    }
+
+    /// Adds `extra_head`, `extra_body_end` and the livereload script to a rendered HTML page
+    fn inject_html(&self, html: String) -> String {
+        self.inject_livereload(self.inject_extra_html(html))
+    }
+
+    /// The snippets are only added to outputs having a `</head>` and `</body>` respectively
+    fn inject_extra_html(&self, mut html: String) -> String {
+        if let Some(ref head) = self.config.extra_head {
+            if let Some(index) = html.find("</head>") {
+                html.insert_str(index, head);
+            }
+        }
+        if let Some(ref body_end) = self.config.extra_body_end {
+            if let Some(index) = html.rfind("</body>") {
+                html.insert_str(index, body_end);
+            }
+        }
+
+        html
+    }
+
     /// Inject live reload script tag if in live reload mode
     fn inject_livereload(&self, mut html: String) -> String {
         if let Some(port) = self.live_reload {
-            let script =
-                format!(r#"<script src="/livereload.js?port={}&amp;mindelay=10"></script>"#, port,);
+            let host = match self.config.serve.livereload_url {
+                Some(ref url) => url.trim_end_matches('/'),
+                None => "",
+            };
+            let script = format!(
+                r#"<script src="{}/livereload.js?port={}&amp;mindelay=10"></script>"#,
+                host, port,
+            );
             if let Some(index) = html.rfind("</body>") {
                 html.insert_str(index, &script);
             } else {
@@ -508,11 +1181,62 @@ impl Site {
         Ok(())
     }
 
+    /// Where the files of the `static` folders are looked up by `zola serve` since they are not
+    /// copied in `BuildMode::Memory`, the user files taking precedence over the theme ones
+    pub fn static_resolver(&self) -> StaticResolver {
+        let mut roots = vec![self.static_path.clone()];
+        if let Some(ref theme) = self.config.theme {
+            roots.push(self.base_path.join("themes").join(theme).join("static"));
+        }
+        StaticResolver::new(roots)
+    }
+
     pub fn num_img_ops(&self) -> usize {
         let imageproc = self.imageproc.lock().expect("Couldn't lock imageproc (num_img_ops)");
         imageproc.num_img_ops()
     }
 
+    /// How many images were processed by the last build, the up to date ones being skipped
+    pub fn num_processed_images(&self) -> usize {
+        let imageproc =
+            self.imageproc.lock().expect("Couldn't lock imageproc (num_processed_images)");
+        imageproc.num_processed()
+    }
+
+    /// Runs the expensive steps whose outputs are reused by the next builds: the image
+    /// processing, written in `static/processed_images`, and the Sass compilation, cached
+    /// in `.zola-cache`. Nothing else is written. Needs to be called after `load`.
+    pub fn warm_cache(&mut self) -> Result<()> {
+        // The images to process are only known once everything is rendered
+        let build_mode = self.build_mode;
+        self.build_mode = BuildMode::Collect;
+        let res = self.build();
+        self.build_mode = build_mode;
+        self.collected.lock().unwrap().clear();
+        res?;
+        self.process_images()?;
+
+        if let Some(ref theme) = self.config.theme {
+            let theme_path = self.base_path.join("themes").join(theme);
+            if theme_path.join("sass").exists() {
+                sass::warm_sass_cache(&theme_path)?;
+            }
+        }
+        if self.config.compile_sass {
+            sass::warm_sass_cache(&self.base_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the processed images that aren't used anymore since the site was last loaded,
+    /// without processing the others, eg to call periodically during a long `zola serve`.
+    /// The images requested by the templates are only known once the site is built so the ones
+    /// of the last build are kept.
+    pub fn prune_images(&self) -> Result<()> {
+        self.imageproc.lock().expect("Couldn't lock imageproc (prune_images)").prune()
+    }
+
     pub fn process_images(&self) -> Result<()> {
         let mut imageproc =
             self.imageproc.lock().expect("Couldn't lock imageproc (process_images)");
@@ -522,6 +1246,10 @@ impl Site {
 
     /// Deletes the `public` directory if it exists
     pub fn clean(&self) -> Result<()> {
+        if !self.config.preserve_on_clean.is_empty() {
+            return self.clean_selective(&self.config.preserve_on_clean);
+        }
+
         if self.output_path.exists() {
             // Delete current `public` directory so we can start fresh
             remove_dir_all(&self.output_path)
@@ -531,6 +1259,36 @@ impl Site {
         Ok(())
     }
 
+    /// Deletes everything in the output directory except the given top-level entries,
+    /// eg `.git` or `CNAME`
+    pub fn clean_selective(&self, preserve: &[String]) -> Result<()> {
+        if !self.output_path.exists() {
+            return Ok(());
+        }
+
+        let entries = read_dir(&self.output_path)
+            .map_err(|e| Error::chain("Couldn't read output directory", e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| Error::chain("Couldn't read output directory", e))?.path();
+            let name = path.file_name().unwrap().to_string_lossy();
+            if preserve.iter().any(|p| p.trim_matches('/') == name) {
+                continue;
+            }
+            let res = if path.is_dir() { remove_dir_all(&path) } else { remove_file(&path) };
+            res.map_err(|e| Error::chain(format!("Couldn't delete {}", path.display()), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders the site in memory with `render_to_vec` and writes the outputs in a `.tar.gz`
+    /// archive at `target` instead of the output directory. Zip archives are not supported.
+    pub fn build_archive(&mut self, target: &Path) -> Result<()> {
+        archive::check_archive_target(target)?;
+        let outputs = self.render_to_vec()?;
+        archive::write_archive(&outputs, target)
+    }
+
     /// Handles whether to write to disk or to memory
     pub fn write_content(
         &self,
@@ -539,8 +1297,9 @@ impl Site {
         content: String,
         create_dirs: bool,
     ) -> Result<PathBuf> {
-        let write_dirs = self.build_mode == BuildMode::Disk || create_dirs;
-        ensure_directory_exists(&self.output_path)?;
+        let write_dirs =
+            (self.build_mode == BuildMode::Disk || create_dirs) && !self.is_collecting();
+        self.ensure_output_directory()?;
 
         let mut site_path = RelativePathBuf::new();
         let mut current_path = self.output_path.to_path_buf();
@@ -558,7 +1317,8 @@ impl Site {
             create_directory(&current_path)?;
         }
 
-        let final_content = if !filename.ends_with("html") || !self.config.minify_html {
+        let minify = self.minify.unwrap_or(self.config.minify_html);
+        let final_content = if !filename.ends_with("html") || !minify {
             content
         } else {
             match minify::html(content) {
@@ -566,6 +1326,13 @@ impl Site {
                 Err(error) => bail!(error),
             }
         };
+        let final_content = match self.config.normalize_line_endings {
+            Some(line_ending) => line_ending.normalize(&final_content),
+            None => final_content,
+        };
+
+        let etag = format!("\"{:x}\"", Sha256::digest(final_content.as_bytes()));
+        self.etags.lock().unwrap().insert(site_path.join(filename), etag);
 
         match self.build_mode {
             BuildMode::Disk => {
@@ -576,7 +1343,13 @@ impl Site {
                 let site_path =
                     if filename != "index.html" { site_path.join(filename) } else { site_path };
 
-                SITE_CONTENT.write().unwrap().insert(site_path, final_content);
+                self.content_map.write().unwrap().insert(site_path, final_content);
+            }
+            BuildMode::Collect => {
+                self.collected
+                    .lock()
+                    .unwrap()
+                    .push((site_path.join(filename), final_content.into_bytes()));
             }
         }
 
@@ -584,50 +1357,253 @@ impl Site {
     }
 
     fn copy_asset(&self, src: &Path, dest: &PathBuf) -> Result<()> {
-        copy_file_if_needed(src, dest, self.config.hard_link_static)
+        if self.is_collecting() {
+            let relative = dest.strip_prefix(&self.output_path).unwrap_or(dest);
+            return self.collect_file(src, relative);
+        }
+        if !self.config.dedupe_assets || self.build_mode != BuildMode::Disk {
+            return copy_file_if_needed(src, dest, self.config.hard_link_static);
+        }
+
+        let content = std::fs::read(src)
+            .map_err(|e| Error::chain(format!("Failed to read {}", src.display()), e))?;
+        let hash = format!("{:x}", Sha256::digest(&content));
+        // Keep the lock while copying so no other page links to a file not written yet
+        let mut written_assets = self.written_assets.lock().unwrap();
+        match written_assets.get(&hash) {
+            Some(existing) if existing != dest => {
+                if dest.exists() {
+                    remove_file(dest)?;
+                }
+                if let Some(parent_directory) = dest.parent() {
+                    create_directory(parent_directory)?;
+                }
+                std::fs::hard_link(existing, dest).map_err(|e| {
+                    Error::chain(
+                        format!(
+                            "Was not able to link {} to {}",
+                            existing.display(),
+                            dest.display()
+                        ),
+                        e,
+                    )
+                })
+            }
+            Some(_) => Ok(()),
+            None => {
+                copy_file_if_needed(src, dest, self.config.hard_link_static)?;
+                written_assets.insert(hash, dest.clone());
+                Ok(())
+            }
+        }
+    }
+
+    fn is_collecting(&self) -> bool {
+        self.build_mode == BuildMode::Collect
+    }
+
+    /// Nothing is written to disk when collecting the outputs so we don't even create
+    /// the output directory in that case
+    fn ensure_output_directory(&self) -> Result<()> {
+        if self.is_collecting() {
+            return Ok(());
+        }
+        ensure_directory_exists(&self.output_path)
+    }
+
+    fn collect_file(&self, src: &Path, relative: &Path) -> Result<()> {
+        let content = std::fs::read(src)
+            .map_err(|e| Error::chain(format!("Failed to read {}", src.display()), e))?;
+        let relative = RelativePathBuf::from_path(relative)
+            .map_err(|e| Error::chain(format!("Invalid output path {}", relative.display()), e))?;
+        self.collected.lock().unwrap().push((relative, content));
+        Ok(())
+    }
+
+    /// Writes a file that isn't rendered from a template at the root of the output directory,
+    /// or collects it when collecting the outputs
+    fn write_file(&self, filename: &str, content: Vec<u8>) -> Result<()> {
+        if self.is_collecting() {
+            self.collected.lock().unwrap().push((RelativePathBuf::from(filename), content));
+            return Ok(());
+        }
+        let path = self.output_path.join(filename);
+        std::fs::write(&path, content)
+            .map_err(|e| Error::chain(format!("Failed to create file {}", path.display()), e))
+    }
+
+    /// Compiles the Sass files of the site or of a theme in the output directory, or collects
+    /// the CSS when collecting the outputs
+    fn compile_sass(&self, base_path: &Path) -> Result<()> {
+        if !self.is_collecting() {
+            return sass::compile_sass_with_cache(base_path, &self.output_path);
+        }
+        for (css_path, css) in sass::compile_sass_files(base_path)? {
+            let relative = RelativePathBuf::from_path(&css_path).map_err(|e| {
+                Error::chain(format!("Invalid output path {}", css_path.display()), e)
+            })?;
+            self.collected.lock().unwrap().push((relative, css.into_bytes()));
+        }
+        Ok(())
+    }
+
+    /// Renders the whole site in memory and returns every output with its path relative
+    /// to the output directory, sorted by path. Nothing is written to the output directory
+    /// or to the `content_map`, the processed images are written in `static/processed_images`
+    /// like in any build.
+    pub fn render_to_vec(&mut self) -> Result<Vec<(RelativePathBuf, Vec<u8>)>> {
+        // Processed images will be in static so they are collected with it
+        let mut outputs = self.collect_outputs(|site| {
+            site.build()?;
+            site.process_images()?;
+            site.collect_static_directories()
+        })?;
+        // A later output overwrites an earlier one with the same path, like on disk
+        outputs.reverse();
+        let mut seen = HashSet::new();
+        outputs.retain(|(path, _)| seen.insert(path.clone()));
+        outputs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(outputs)
+    }
+
+    /// Runs `build_fn` in `BuildMode::Collect` and returns the outputs it collected in order
+    fn collect_outputs<F>(&mut self, build_fn: F) -> Result<Vec<(RelativePathBuf, Vec<u8>)>>
+    where
+        F: FnOnce(&Site) -> Result<()>,
+    {
+        let previous_mode = self.build_mode;
+        self.build_mode = BuildMode::Collect;
+        self.collected.lock().unwrap().clear();
+        let res = build_fn(self);
+        self.build_mode = previous_mode;
+        let outputs = std::mem::replace(&mut *self.collected.lock().unwrap(), Vec::new());
+        res.map(|_| outputs)
+    }
+
+    fn collect_static_directories(&self) -> Result<()> {
+        let mut static_dirs = vec![];
+        // The user files will overwrite the theme files
+        if let Some(ref theme) = self.config.theme {
+            static_dirs.push(self.base_path.join("themes").join(theme).join("static"));
+        }
+        static_dirs.push(self.static_path.clone());
+
+        for dir in static_dirs.iter().filter(|d| d.exists()) {
+            for entry in WalkDir::new(dir).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+                if entry.file_type().is_file() {
+                    let relative = entry.path().strip_prefix(dir).unwrap();
+                    self.collect_file(entry.path(), relative)?;
+                }
+            }
+        }
+
+        Ok(())
     }
 
     /// Renders a single content page
     pub fn render_page(&self, page: &Page) -> Result<()> {
-        let output = page.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-        let content = self.inject_livereload(output);
-        let components: Vec<&str> = page.path.split('/').collect();
+        let output = page.render_html_with_template(
+            &self.resolve_template(page.get_template_name()),
+            &self.tera,
+            &self.config,
+            &self.library.read().unwrap(),
+        )?;
+        let content = self.inject_html(self.postprocess_html(output, Some(page))?);
+        let mut components: Vec<&str> = page.path.split('/').collect();
+        // The last component is the file itself with `output_filename` or without pretty URLs,
+        // eg `/about.html`
+        let filename =
+            if page.path.ends_with('/') { "index.html" } else { components.pop().unwrap() };
         let current_path =
-            self.write_content(&components, "index.html", content, !page.assets.is_empty())?;
+            self.write_content(&components, filename, content, !page.assets.is_empty())?;
+        // Without pretty URLs, pages of a section are written in the same directory so their
+        // assets go in a directory named after the page instead, eg `about/` for `about.html`
+        let assets_path = if !self.config.pretty_urls && page.meta.output_filename.is_none() {
+            current_path.join(filename.trim_end_matches(".html"))
+        } else {
+            current_path
+        };
 
-        // Copy any asset we found previously into the same directory as the index.html
+        // Copy any asset we found previously into the directory of the page
         for asset in &page.assets {
             let asset_path = asset.as_path();
             self.copy_asset(
                 &asset_path,
-                &current_path
+                &assets_path
                     .join(asset_path.file_name().expect("Couldn't get filename from page asset")),
             )?;
         }
 
+        // And the AMP version of the page, in its own `amp` directory
+        if let Some(ref amp_path) = page.amp_path {
+            let output =
+                page.render_amp_html(&self.tera, &self.config, &self.library.read().unwrap())?;
+            let content = self.inject_html(self.postprocess_html(output, Some(page))?);
+            let components: Vec<&str> = amp_path.split('/').collect();
+            self.write_content(&components, "index.html", content, false)?;
+        }
+
         Ok(())
     }
 
     /// Deletes the `public` directory (only for `zola build`) and builds the site
+    /// In `zola serve`, the outcome of the build is then available at `__zola/status.json`
     pub fn build(&self) -> Result<()> {
+        // The warnings of a previous build are dropped when `build_site` starts
+        let first_warning = self.load_warnings;
+        let res = match self.thread_pool {
+            Some(ref pool) => pool.install(|| self.build_site()),
+            None => self.build_site(),
+        };
+        if self.build_mode == BuildMode::Memory {
+            self.write_build_status(&res)?;
+        }
+        self.write_diagnostics("build", first_warning, &res)?;
+        res
+    }
+
+    fn write_build_status(&self, res: &Result<()>) -> Result<()> {
+        let error = res.as_ref().err().map(error_with_causes);
+        let status = BuildStatus {
+            success: res.is_ok(),
+            errors: if res.is_ok() { 0 } else { 1 },
+            warnings: self.warnings().len(),
+            error,
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        let json = serde_json::to_string(&status).expect("Couldn't serialize the build status");
+        self.write_content(&["__zola"], "status.json", json, false)?;
+        Ok(())
+    }
+
+    fn build_site(&self) -> Result<()> {
         let mut start = Instant::now();
+        self.warnings
+            .lock()
+            .expect("Couldn't lock warnings (build_site)")
+            .truncate(self.load_warnings);
         // Do not clean on `zola serve` otherwise we end up copying assets all the time
-        if self.build_mode == BuildMode::Disk {
+        if self.build_mode == BuildMode::Disk || (self.force && !self.is_collecting()) {
             self.clean()?;
         }
+        self.written_assets.lock().unwrap().clear();
+        self.etags.lock().unwrap().clear();
+        self.sri_hashes.lock().unwrap().clear();
+        self.bundles.lock().unwrap().clear();
+        self.imageproc.lock().expect("Couldn't lock imageproc (build_site)").start_build();
         start = log_time(start, "Cleaned folder");
 
         // Generate/move all assets before rendering any content
         if let Some(ref theme) = self.config.theme {
             let theme_path = self.base_path.join("themes").join(theme);
             if theme_path.join("sass").exists() {
-                sass::compile_sass(&theme_path, &self.output_path)?;
+                self.compile_sass(&theme_path)?;
                 start = log_time(start, "Compiled theme Sass");
             }
         }
 
         if self.config.compile_sass {
-            sass::compile_sass(&self.base_path, &self.output_path)?;
+            self.compile_sass(&self.base_path)?;
             start = log_time(start, "Compiled own Sass");
         }
 
@@ -659,6 +1635,14 @@ impl Site {
             } else {
                 library.pages_values()
             };
+            if is_multilingual && self.config.feed.default_language_in_subdir {
+                self.render_feed(
+                    pages.clone(),
+                    Some(&PathBuf::from(self.config.default_language.clone())),
+                    &self.config.default_language,
+                    |c| c,
+                )?;
+            }
             self.render_feed(pages, None, &self.config.default_language, |c| c)?;
             start = log_time(start, "Generated feed in default language");
         }
@@ -673,58 +1657,158 @@ impl Site {
             start = log_time(start, "Generated feed in other language");
         }
 
+        for extra_feed in &self.config.extra_feeds {
+            self.render_feed(
+                self.extra_feed_pages(&library, extra_feed)?,
+                Some(&PathBuf::from(&extra_feed.path)),
+                &self.config.default_language,
+                |c| c,
+            )?;
+        }
+        if !self.config.extra_feeds.is_empty() {
+            start = log_time(start, "Generated extra feeds");
+        }
+
         self.render_404()?;
         start = log_time(start, "Rendered 404");
         self.render_robots()?;
         start = log_time(start, "Rendered robots.txt");
+        if self.config.search.opensearch {
+            self.render_opensearch()?;
+            start = log_time(start, "Rendered opensearch.xml");
+        }
+        self.render_humans()?;
+        start = log_time(start, "Rendered humans.txt");
         self.render_taxonomies()?;
         start = log_time(start, "Rendered taxonomies");
+        self.write_bundles()?;
+        start = log_time(start, "Wrote bundles");
+        self.add_image_warnings();
+        // The static files are collected by `render_to_vec` once everything is built
+        if self.is_collecting() {
+            return Ok(());
+        }
         // We process images at the end as we might have picked up images to process from markdown
         // or from templates
         self.process_images()?;
         start = log_time(start, "Processed images");
-        // Processed images will be in static so the last step is to copy it
-        self.copy_static_directories()?;
-        log_time(start, "Copied static dir");
+        // Processed images will be in static so the last step is to copy it, unless we are
+        // serving: the static files are then served from where they are
+        if self.build_mode != BuildMode::Memory {
+            self.copy_static_directories()?;
+            start = log_time(start, "Copied static dir");
+        }
+        // Needs to be the very last step as it reads the rendered HTML files
+        if self.config.generate_headers && self.build_mode == BuildMode::Disk {
+            self.render_headers()?;
+            log_time(start, "Rendered _headers");
+        }
 
         Ok(())
     }
 
+    /// Renders only the sections and pages whose path starts with `prefix`, eg `/docs/api/`,
+    /// as well as the taxonomy terms those pages belong to.
+    /// The whole site is still loaded so links to pages outside of the subtree have the right
+    /// permalinks. Unlike `build`, the output directory is not cleaned.
+    pub fn build_subtree(&self, prefix: &str) -> Result<()> {
+        self.ensure_output_directory()?;
+        let library = self.library.read().unwrap();
+
+        library
+            .sections_values()
+            .into_par_iter()
+            .filter(|s| s.path.starts_with(prefix))
+            .map(|s| self.render_section(s, false))
+            .collect::<Result<()>>()?;
+
+        library
+            .pages_values()
+            .into_par_iter()
+            .filter(|p| p.path.starts_with(prefix))
+            .map(|p| self.render_page(p))
+            .collect::<Result<()>>()?;
+
+        for taxonomy in &self.taxonomies {
+            let components = self.taxonomy_components(taxonomy);
+            taxonomy
+                .items
+                .par_iter()
+                .filter(|item| {
+                    item.pages.iter().any(|k| library.get_page_by_key(*k).path.starts_with(prefix))
+                })
+                .map(|item| self.render_taxonomy_term(taxonomy, item, &components, &library))
+                .collect::<Result<()>>()?;
+        }
+
+        self.process_images()?;
+        self.copy_static_directories()
+    }
+
+    /// Renders only the draft pages, and the sections leading to them, in `output_dir` so they can
+    /// be reviewed before being published. The site needs to be loaded with `include_drafts`.
+    /// The usual output directory is left untouched.
+    pub fn build_drafts_preview<P: AsRef<Path>>(&mut self, output_dir: P) -> Result<()> {
+        if !self.include_drafts {
+            bail!("The site needs to be loaded with the drafts to build a preview of them");
+        }
+        let output_path =
+            std::mem::replace(&mut self.output_path, output_dir.as_ref().to_path_buf());
+        let res = self.render_drafts();
+        self.output_path = output_path;
+        res
+    }
+
+    fn render_drafts(&self) -> Result<()> {
+        self.ensure_output_directory()?;
+        let library = self.library.read().unwrap();
+        let drafts: Vec<_> = library.pages_values().into_iter().filter(|p| p.meta.draft).collect();
+
+        let mut sections: Vec<_> = drafts.iter().flat_map(|p| p.ancestors.clone()).collect();
+        sections.sort();
+        sections.dedup();
+        sections
+            .into_par_iter()
+            .map(|k| self.render_section(library.get_section_by_key(k), false))
+            .collect::<Result<()>>()?;
+
+        // Only the drafts and their colocated assets: the static files and processed images are
+        // the ones of the usual build
+        drafts.into_par_iter().map(|p| self.render_page(p)).collect::<Result<()>>()
+    }
+
     pub fn build_search_index(&self) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
-        // TODO: add those to the SITE_CONTENT map
+        self.ensure_output_directory()?;
+        // TODO: add those to the content map
 
         // index first
-        create_file(
-            &self.output_path.join(&format!("search_index.{}.js", self.config.default_language)),
-            &format!(
-                "window.searchIndex = {};",
-                search::build_index(
-                    &self.config.default_language,
-                    &self.library.read().unwrap(),
-                    &self.config
-                )?
-            ),
-        )?;
+        self.write_search_index(&self.config.default_language)?;
 
         for language in &self.config.languages {
             if language.code != self.config.default_language && language.search {
-                create_file(
-                    &self.output_path.join(&format!("search_index.{}.js", &language.code)),
-                    &format!(
-                        "window.searchIndex = {};",
-                        search::build_index(
-                            &language.code,
-                            &self.library.read().unwrap(),
-                            &self.config
-                        )?
-                    ),
-                )?;
+                self.write_search_index(&language.code)?;
             }
         }
 
         // then elasticlunr.min.js
-        create_file(&self.output_path.join("elasticlunr.min.js"), search::ELASTICLUNR_JS)?;
+        self.write_file("elasticlunr.min.js", search::ELASTICLUNR_JS.as_bytes().to_vec())?;
+
+        Ok(())
+    }
+
+    /// Writes `search_index.{lang}.js` and its gzipped copy if `search.gzip_index` is set
+    fn write_search_index(&self, lang: &str) -> Result<()> {
+        let content = format!(
+            "window.searchIndex = {};",
+            search::build_index(lang, &self.library.read().unwrap(), &self.config)?
+        );
+        self.write_file(&format!("search_index.{}.js", lang), content.as_bytes().to_vec())?;
+
+        if self.config.search.gzip_index {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(content.as_bytes())?;
+            self.write_file(&format!("search_index.{}.js.gz", lang), encoder.finish()?)?;
+        }
 
         Ok(())
     }
@@ -748,45 +1832,118 @@ impl Site {
     }
 
     /// Renders all the aliases for each page/section: a magic HTML template that redirects to
-    /// the canonical one
+    /// the canonical one, or a line of the `_redirects` file with `aliases_as_redirects_file`
     pub fn render_aliases(&self) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
         let library = self.library.read().unwrap();
-        for (_, page) in library.pages() {
-            for alias in &page.meta.aliases {
-                self.render_alias(&alias, &page.permalink)?;
+        let pages_aliases = library
+            .pages_values()
+            .into_iter()
+            .flat_map(|p| p.meta.aliases.iter().map(move |a| (a, &p.permalink)));
+        let sections_aliases = library
+            .sections_values()
+            .into_iter()
+            .flat_map(|s| s.meta.aliases.iter().map(move |a| (a, &s.permalink)));
+
+        let mut redirects = Vec::new();
+        for (alias, permalink) in pages_aliases.chain(sections_aliases) {
+            if self.config.aliases_as_redirects_file {
+                redirects.push(format!("/{} {} 301", alias.trim_start_matches('/'), permalink));
+            } else {
+                self.render_alias(&alias, &permalink)?;
             }
         }
-        for (_, section) in library.sections() {
-            for alias in &section.meta.aliases {
-                self.render_alias(&alias, &section.permalink)?;
-            }
+
+        if !redirects.is_empty() {
+            redirects.sort();
+            self.write_content(&[], "_redirects", redirects.join("\n") + "\n", false)?;
         }
         Ok(())
     }
 
     /// Renders 404.html
     pub fn render_404(&self) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
         let mut context = Context::new();
         context.insert("config", &self.config);
+        context.insert("data", &self.config.data);
         context.insert("lang", &self.config.default_language);
+        if self.config.pages_in_404 {
+            context.insert("pages", &self.pages_for_404());
+        }
         let output = render_template("404.html", &self.tera, context, &self.config.theme)?;
-        let content = self.inject_livereload(output);
+        let content = self.inject_html(output);
         self.write_content(&[], "404.html", content, false)?;
         Ok(())
     }
 
+    /// The title, permalink and language of every page, sorted by permalink
+    fn pages_for_404(&self) -> Vec<Page404Entry> {
+        let library = self.library.read().unwrap();
+        let mut pages: Vec<_> = library
+            .pages_values()
+            .into_iter()
+            .map(|p| Page404Entry {
+                title: p.meta.title.clone(),
+                permalink: p.permalink.clone(),
+                lang: p.lang.clone(),
+            })
+            .collect();
+        pages.sort_by(|a, b| a.permalink.cmp(&b.permalink));
+        pages
+    }
+
     /// Renders robots.txt
     pub fn render_robots(&self) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
         let mut context = Context::new();
         context.insert("config", &self.config);
+        context.insert("data", &self.config.data);
         let content = render_template("robots.txt", &self.tera, context, &self.config.theme)?;
         self.write_content(&[], "robots.txt", content, false)?;
         Ok(())
     }
 
+    /// Writes the CSS and JS bundles made by the `bundle` function while rendering
+    fn write_bundles(&self) -> Result<()> {
+        let bundles = std::mem::take(&mut *self.bundles.lock().unwrap());
+        for (path, content) in bundles {
+            let (dir, filename) =
+                path.split_at(path.rfind('/').expect("Bundles are in a directory"));
+            self.write_content(&[dir], &filename[1..], content, false)?;
+        }
+        Ok(())
+    }
+
+    /// Renders the `opensearch.xml` description document
+    pub fn render_opensearch(&self) -> Result<()> {
+        self.ensure_output_directory()?;
+        let mut context = Context::new();
+        context.insert("config", &self.config);
+        context.insert("data", &self.config.data);
+        let content = render_template("opensearch.xml", &self.tera, context, &self.config.theme)?;
+        self.write_content(&[], "opensearch.xml", content, false)?;
+        Ok(())
+    }
+
+    /// Renders `humans.txt` if the site or its theme has a template for it
+    pub fn render_humans(&self) -> Result<()> {
+        let in_theme = self.config.theme.as_ref().map_or(false, |theme| {
+            self.tera.templates.contains_key(&format!("{}/templates/humans.txt", theme))
+        });
+        if !self.tera.templates.contains_key("humans.txt") && !in_theme {
+            return Ok(());
+        }
+
+        self.ensure_output_directory()?;
+        let mut context = Context::new();
+        context.insert("config", &self.config);
+        context.insert("data", &self.config.data);
+        let content = render_template("humans.txt", &self.tera, context, &self.config.theme)?;
+        self.write_content(&[], "humans.txt", content, false)?;
+        Ok(())
+    }
+
     /// Renders all taxonomies
     pub fn render_taxonomies(&self) -> Result<()> {
         for taxonomy in &self.taxonomies {
@@ -796,93 +1953,137 @@ impl Site {
         Ok(())
     }
 
+    /// The output path components of a taxonomy, eg `["fr", "tags"]`
+    fn taxonomy_components<'a>(&self, taxonomy: &'a Taxonomy) -> Vec<&'a str> {
+        let mut components = Vec::new();
+        if taxonomy.kind.lang != self.config.default_language {
+            components.push(taxonomy.kind.lang.as_ref());
+        }
+        components.push(taxonomy.slug.as_ref());
+        components
+    }
+
     fn render_taxonomy(&self, taxonomy: &Taxonomy) -> Result<()> {
         if taxonomy.items.is_empty() {
             return Ok(());
         }
 
-        ensure_directory_exists(&self.output_path)?;
-
-        let mut components = Vec::new();
-        if taxonomy.kind.lang != self.config.default_language {
-            components.push(taxonomy.kind.lang.as_ref());
-        }
+        self.ensure_output_directory()?;
 
-        components.push(taxonomy.slug.as_ref());
+        let components = self.taxonomy_components(taxonomy);
 
         let list_output =
             taxonomy.render_all_terms(&self.tera, &self.config, &self.library.read().unwrap())?;
-        let content = self.inject_livereload(list_output);
+        let content = self.inject_html(list_output);
         self.write_content(&components, "index.html", content, false)?;
 
         let library = self.library.read().unwrap();
         taxonomy
             .items
             .par_iter()
-            .map(|item| {
-                let mut comp = components.clone();
-                comp.push(&item.slug);
-
-                if taxonomy.kind.is_paginated() {
-                    self.render_paginated(
-                        comp.clone(),
-                        &Paginator::from_taxonomy(&taxonomy, item, &library),
-                    )?;
-                } else {
-                    let single_output =
-                        taxonomy.render_term(item, &self.tera, &self.config, &library)?;
-                    let content = self.inject_livereload(single_output);
-                    self.write_content(&comp, "index.html", content, false)?;
-                }
+            .map(|item| self.render_taxonomy_term(taxonomy, item, &components, &library))
+            .collect::<Result<()>>()
+    }
 
-                if taxonomy.kind.feed {
-                    self.render_feed(
-                        item.pages.iter().map(|p| library.get_page_by_key(*p)).collect(),
-                        Some(&PathBuf::from(format!("{}/{}", taxonomy.slug, item.slug))),
-                        if self.config.is_multilingual() && !taxonomy.kind.lang.is_empty() {
-                            &taxonomy.kind.lang
-                        } else {
-                            &self.config.default_language
-                        },
-                        |mut context: Context| {
-                            context.insert("taxonomy", &taxonomy.kind);
-                            context
-                                .insert("term", &feed::SerializedFeedTaxonomyItem::from_item(item));
-                            context
-                        },
-                    )
+    /// Renders a single term of a taxonomy, with its feed if the taxonomy has one
+    fn render_taxonomy_term(
+        &self,
+        taxonomy: &Taxonomy,
+        item: &TaxonomyItem,
+        components: &[&str],
+        library: &Library,
+    ) -> Result<()> {
+        let mut comp = components.to_vec();
+        comp.push(&item.slug);
+
+        if taxonomy.kind.is_paginated() {
+            self.render_paginated(
+                comp.clone(),
+                &Paginator::from_taxonomy(&taxonomy, item, library),
+            )?;
+        } else {
+            let single_output = taxonomy.render_term(item, &self.tera, &self.config, library)?;
+            let content = self.inject_html(single_output);
+            self.write_content(&comp, "index.html", content, false)?;
+        }
+
+        if taxonomy.kind.feed {
+            self.render_feed(
+                item.pages.iter().map(|p| library.get_page_by_key(*p)).collect(),
+                Some(&PathBuf::from(format!("{}/{}", taxonomy.slug, item.slug))),
+                if self.config.is_multilingual() && !taxonomy.kind.lang.is_empty() {
+                    &taxonomy.kind.lang
                 } else {
-                    Ok(())
-                }
-            })
-            .collect::<Result<()>>()
+                    &self.config.default_language
+                },
+                |mut context: Context| {
+                    context.insert("taxonomy", &taxonomy.kind);
+                    context.insert("term", &feed::SerializedFeedTaxonomyItem::from_item(item));
+                    context
+                },
+            )
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes a `_headers` file with preload hints for the stylesheets and scripts used by
+    /// each HTML file of the output directory
+    pub fn render_headers(&self) -> Result<()> {
+        let mut pages = Vec::new();
+        let walker =
+            WalkDir::new(&self.output_path).sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        for entry in walker.into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !path.is_file() || path.extension().map_or(true, |ext| ext != "html") {
+                continue;
+            }
+            let preloads = headers::find_preloads(&read_file(path)?, &self.config.base_url);
+            if preloads.is_empty() {
+                continue;
+            }
+            let relative =
+                path.strip_prefix(&self.output_path).unwrap().to_string_lossy().replace('\\', "/");
+            let route = if relative == "index.html" {
+                "/".to_string()
+            } else if relative.ends_with("/index.html") {
+                format!("/{}", relative.trim_end_matches("index.html"))
+            } else {
+                format!("/{}", relative)
+            };
+            pages.push((route, preloads));
+        }
+
+        create_file(&self.output_path.join("_headers"), &headers::render_headers(&pages))
     }
 
     /// What it says on the tin
     pub fn render_sitemap(&self) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
 
         let library = self.library.read().unwrap();
         let all_sitemap_entries =
             { sitemap::find_entries(&library, &self.taxonomies[..], &self.config) };
-        let sitemap_limit = 30000;
+        let sitemap_limit = self.config.sitemap.max_entries;
 
         if all_sitemap_entries.len() < sitemap_limit {
             // Create single sitemap
             let mut context = Context::new();
             context.insert("entries", &all_sitemap_entries);
+            context.insert("config", &self.config);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
             self.write_content(&[], "sitemap.xml", sitemap, false)?;
             return Ok(());
         }
 
-        // Create multiple sitemaps (max 30000 urls each)
+        // Create multiple sitemaps (max `sitemap.max_entries` urls each)
         let mut sitemap_index = Vec::new();
         for (i, chunk) in
             all_sitemap_entries.iter().collect::<Vec<_>>().chunks(sitemap_limit).enumerate()
         {
             let mut context = Context::new();
             context.insert("entries", &chunk);
+            context.insert("config", &self.config);
             let sitemap = render_template("sitemap.xml", &self.tera, context, &self.config.theme)?;
             let file_name = format!("sitemap{}.xml", i + 1);
             self.write_content(&[], &file_name, sitemap, false)?;
@@ -905,6 +2106,71 @@ impl Site {
         Ok(())
     }
 
+    /// The pages matching the filters of an extra feed
+    fn extra_feed_pages<'a>(
+        &self,
+        library: &'a Library,
+        extra_feed: &ExtraFeed,
+    ) -> Result<Vec<&'a Page>> {
+        let section_pages = match extra_feed.section {
+            Some(ref section) => match library.get_section(&self.content_path.join(section)) {
+                Some(s) => Some(&s.pages),
+                None => bail!(
+                    "The extra feed at `{}` uses the section `{}` which doesn't exist",
+                    extra_feed.path,
+                    section
+                ),
+            },
+            None => None,
+        };
+
+        Ok(library
+            .pages()
+            .iter()
+            .filter(|(key, _)| section_pages.map_or(true, |keys| keys.contains(key)))
+            .filter(|(_, p)| extra_feed.matches(&p.file.relative, &p.meta.taxonomies))
+            .map(|(_, p)| p)
+            .collect())
+    }
+
+    /// Every permalink the site produces: its HTML files, including the pagers and aliases, and
+    /// its feeds. They are found by rendering the site without writing anything so they are
+    /// exactly the ones a build would write. Needs to be called after `load`.
+    pub fn all_permalinks(&mut self) -> Result<Vec<String>> {
+        let outputs = self.collect_outputs(|site| site.build())?;
+        let mut permalinks: Vec<_> =
+            outputs.iter().filter_map(|(path, _)| self.output_permalink(path)).collect();
+        permalinks.sort();
+        Ok(permalinks)
+    }
+
+    /// The permalink of an output file if it is a HTML file or a feed, `404.html` excepted
+    fn output_permalink(&self, path: &RelativePath) -> Option<String> {
+        let filename = path.file_name()?;
+        if !(filename.ends_with(".html") || filename == self.config.feed_filename)
+            || path.as_str() == "404.html"
+        {
+            return None;
+        }
+
+        // The files of the other languages are in a directory named after them but might use
+        // another base URL
+        let lang = path
+            .as_str()
+            .split('/')
+            .next()
+            .filter(|c| self.config.languages.iter().any(|l| l.code == *c))
+            .unwrap_or(&self.config.default_language);
+        let dir = path.parent().map_or("", |p| p.as_str());
+        let dir = if dir.is_empty() { "/".to_string() } else { format!("/{}/", dir) };
+        let permalink = self.config.make_permalink_for_lang(&dir, lang);
+        if filename == "index.html" {
+            Some(permalink)
+        } else {
+            Some(format!("{}{}", permalink, filename))
+        }
+    }
+
     /// Renders a feed for the given path and at the given path
     /// If both arguments are `None`, it will render only the feed for the whole
     /// site at the root folder.
@@ -915,7 +2181,7 @@ impl Site {
         lang: &str,
         additional_context_fn: impl Fn(Context) -> Context,
     ) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
 
         let feed = match feed::render_feed(self, all_pages, lang, base_path, additional_context_fn)?
         {
@@ -942,12 +2208,14 @@ impl Site {
         Ok(())
     }
 
-    /// Renders a single section
+    /// Renders a single section: its feed, assets and pages are handled regardless of `render`,
+    /// which only controls whether the HTML of the section itself is written
     pub fn render_section(&self, section: &Section, render_pages: bool) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
         let mut output_path = self.output_path.clone();
         let mut components: Vec<&str> = Vec::new();
-        let create_directories = self.build_mode == BuildMode::Disk || !section.assets.is_empty();
+        let create_directories = (self.build_mode == BuildMode::Disk || !section.assets.is_empty())
+            && !self.is_collecting();
 
         if section.lang != self.config.default_language {
             components.push(&section.lang);
@@ -967,7 +2235,9 @@ impl Site {
             }
         }
 
-        if section.meta.generate_feed {
+        let generate_feed =
+            section.meta.generate_feed || (self.config.feed.all_sections && !section.is_index());
+        if generate_feed {
             let library = &self.library.read().unwrap();
             let pages = section.pages.iter().map(|k| library.get_page_by_key(*k)).collect();
             self.render_feed(
@@ -992,7 +2262,7 @@ impl Site {
             )?;
         }
 
-        if render_pages {
+        if render_pages && section.meta.render_pages {
             section
                 .pages
                 .par_iter()
@@ -1000,10 +2270,30 @@ impl Site {
                 .collect::<Result<()>>()?;
         }
 
-        if !section.meta.render {
-            return Ok(());
+        if section.meta.render {
+            self.render_section_html(section, components, create_directories)?;
+        } else if section.meta.auto_list {
+            // So the URL of the section isn't a 404
+            let output = section.render_html_with_template(
+                "auto_list.html",
+                &self.tera,
+                &self.config,
+                &self.library.read().unwrap(),
+            )?;
+            let content = self.inject_html(self.postprocess_html(output, None)?);
+            self.write_content(&components, "index.html", content, create_directories)?;
         }
 
+        Ok(())
+    }
+
+    /// Writes the HTML of a section, paginated or not, or its redirect if it has a `redirect_to`
+    fn render_section_html(
+        &self,
+        section: &Section,
+        components: Vec<&str>,
+        create_directories: bool,
+    ) -> Result<()> {
         if let Some(ref redirect_to) = section.meta.redirect_to {
             let permalink = self.config.make_permalink(redirect_to);
             self.write_content(
@@ -1018,16 +2308,31 @@ impl Site {
 
         if section.meta.is_paginated() {
             self.render_paginated(
-                components,
+                components.clone(),
                 &Paginator::from_section(&section, &self.library.read().unwrap()),
             )?;
         } else {
-            let output =
-                section.render_html(&self.tera, &self.config, &self.library.read().unwrap())?;
-            let content = self.inject_livereload(output);
+            let output = section.render_html_with_template(
+                &self.resolve_template(section.get_template_name()),
+                &self.tera,
+                &self.config,
+                &self.library.read().unwrap(),
+            )?;
+            let content = self.inject_html(self.postprocess_html(output, None)?);
             self.write_content(&components, "index.html", content, false)?;
         }
 
+        for paginate_by in section.meta.paginate_by_variants.iter().filter(|s| **s > 0) {
+            self.render_paginated(
+                components.clone(),
+                &Paginator::from_section_variant(
+                    &section,
+                    &self.library.read().unwrap(),
+                    *paginate_by,
+                ),
+            )?;
+        }
+
         Ok(())
     }
 
@@ -1044,7 +2349,7 @@ impl Site {
 
     /// Renders all pages that do not belong to any sections
     pub fn render_orphan_pages(&self) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
         let library = self.library.read().unwrap();
         for page in library.get_all_orphan_pages() {
             self.render_page(page)?;
@@ -1059,7 +2364,7 @@ impl Site {
         components: Vec<&'a str>,
         paginator: &'a Paginator,
     ) -> Result<()> {
-        ensure_directory_exists(&self.output_path)?;
+        self.ensure_output_directory()?;
 
         let index_components = components.clone();
 
@@ -1077,18 +2382,19 @@ impl Site {
                     &self.tera,
                     &self.library.read().unwrap(),
                 )?;
-                let content = self.inject_livereload(output);
+                let content = self.inject_html(self.postprocess_html(output, None)?);
 
-                if pager.index > 1 {
+                if pager.index > 1 || paginator.is_variant {
                     self.write_content(&pager_components, "index.html", content, false)?;
                 } else {
+                    let page_one = match self.config.paginate_page_one {
+                        PaginatePageOne::Redirect => {
+                            render_redirect_template(&paginator.permalink, &self.tera)?
+                        }
+                        PaginatePageOne::Duplicate => content.clone(),
+                    };
                     self.write_content(&index_components, "index.html", content, false)?;
-                    self.write_content(
-                        &pager_components,
-                        "index.html",
-                        render_redirect_template(&paginator.permalink, &self.tera)?,
-                        false,
-                    )?;
+                    self.write_content(&pager_components, "index.html", page_one, false)?;
                 }
 
                 Ok(())
@@ -1097,6 +2403,27 @@ impl Site {
     }
 }
 
+/// Renders the markdown of a page once the content preprocessors have run on it
+fn render_page_markdown(
+    page: &mut Page,
+    preprocessors: &[ContentPreprocessor],
+    permalinks: &HashMap<String, String>,
+    tera: &Tera,
+    config: &Config,
+    insert_anchor: InsertAnchor,
+) -> Result<()> {
+    if preprocessors.is_empty() {
+        return page.render_markdown(permalinks, tera, config, insert_anchor);
+    }
+
+    let content = preprocessors.iter().fold(page.raw_content.clone(), |c, f| f(&c, page));
+    // Rendered from the preprocessed content but the page keeps its original markdown
+    let raw_content = std::mem::replace(&mut page.raw_content, content);
+    let res = page.render_markdown(permalinks, tera, config, insert_anchor);
+    page.raw_content = raw_content;
+    res
+}
+
 fn log_time(start: Instant, message: &str) -> Instant {
     let do_print = std::env::var("ZOLA_PERF_LOG").is_ok();
     let now = Instant::now();
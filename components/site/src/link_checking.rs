@@ -1,7 +1,65 @@
+use std::path::PathBuf;
+
 use rayon::prelude::*;
 
 use crate::Site;
 use errors::{Error, ErrorKind, Result};
+use rendering::find_internal_links;
+use utils::site::resolve_internal_link;
+
+/// Checks that every internal link (`@/...`) points to an existing page or section.
+/// Runs before the markdown is rendered so we can report all the broken links at once rather
+/// than erroring on the first one.
+pub fn check_internal_links(site: &Site) -> Result<()> {
+    let library = site.library.read().expect("Get lock for check_internal_links");
+    let page_links = library
+        .pages()
+        .values()
+        .map(|p| {
+            let path = &p.file.path;
+            find_internal_links(&p.raw_content).into_iter().map(move |l| (path.clone(), l))
+        })
+        .flatten();
+    let section_links = library
+        .sections()
+        .values()
+        .map(|s| {
+            let path = &s.file.path;
+            find_internal_links(&s.raw_content).into_iter().map(move |l| (path.clone(), l))
+        })
+        .flatten();
+    let all_links = page_links.chain(section_links).collect::<Vec<_>>();
+
+    if site.config.is_in_check_mode() {
+        println!("Checking {} internal link(s).", all_links.len());
+    }
+
+    let errors: Vec<_> = all_links
+        .iter()
+        .filter(|(_, link)| resolve_internal_link(link, &site.permalinks).is_err())
+        .collect();
+
+    if site.config.is_in_check_mode() {
+        println!(
+            "> Checked {} internal link(s): {} error(s) found.",
+            all_links.len(),
+            errors.len()
+        );
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    let msg = errors
+        .into_iter()
+        .map(|(page_path, link)| {
+            format!("Dead internal link in {} to {}", page_path.to_string_lossy(), link)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(Error { kind: ErrorKind::Msg(msg), source: None })
+}
 
 /// Very similar to check_external_links but can't be merged as far as I can see since we always
 /// want to check the internal links but only the external in zola check :/
@@ -95,6 +153,34 @@ pub fn check_internal_links_with_anchors(site: &Site) -> Result<()> {
 }
 
 pub fn check_external_links(site: &Site) -> Result<()> {
+    let dead_links = find_dead_external_links(site)?;
+    if dead_links.is_empty() {
+        return Ok(());
+    }
+
+    let msg = dead_links
+        .into_iter()
+        .map(|(page_path, link, message)| {
+            format!("Dead link in {} to {}: {}", page_path.to_string_lossy(), link, message)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(Error { kind: ErrorKind::Msg(msg), source: None })
+}
+
+/// Checks every external link like `check_external_links` but records the dead ones as warnings
+/// of the site instead of erroring
+pub fn warn_about_external_links(site: &Site) -> Result<()> {
+    for (page_path, link, message) in find_dead_external_links(site)? {
+        let message = format!("Dead link to {}: {}", link, message);
+        println!("{}: {}", page_path.display(), message);
+        site.add_warning(Some(&page_path), message);
+    }
+    Ok(())
+}
+
+/// Returns the path of the content file, the link and why it is dead for every dead external link
+fn find_dead_external_links(site: &Site) -> Result<Vec<(PathBuf, String, String)>> {
     let library = site.library.write().expect("Get lock for check_external_links");
     let page_links = library
         .pages()
@@ -116,7 +202,7 @@ pub fn check_external_links(site: &Site) -> Result<()> {
     println!("Checking {} external link(s).", all_links.len());
 
     if all_links.is_empty() {
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     // create thread pool with lots of threads so we can fetch
@@ -140,7 +226,11 @@ pub fn check_external_links(site: &Site) -> Result<()> {
                 {
                     return None;
                 }
-                let res = link_checker::check_url(&link, &site.config.link_checker);
+                let res = if site.force {
+                    link_checker::recheck_url(&link, &site.config.link_checker)
+                } else {
+                    link_checker::check_url(&link, &site.config.link_checker)
+                };
                 if link_checker::is_valid(&res) {
                     None
                 } else {
@@ -152,21 +242,10 @@ pub fn check_external_links(site: &Site) -> Result<()> {
 
     println!("> Checked {} external link(s): {} error(s) found.", all_links.len(), errors.len());
 
-    if errors.is_empty() {
-        return Ok(());
-    }
-
-    let msg = errors
+    Ok(errors
         .into_iter()
         .map(|(page_path, link, check_res)| {
-            format!(
-                "Dead link in {} to {}: {}",
-                page_path.to_string_lossy(),
-                link,
-                link_checker::message(&check_res)
-            )
+            (page_path.clone(), link.to_string(), link_checker::message(&check_res))
         })
-        .collect::<Vec<_>>()
-        .join("\n");
-    Err(Error { kind: ErrorKind::Msg(msg), source: None })
+        .collect())
 }
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use errors::Result;
+use front_matter::InsertAnchor;
+use library::{Library, Page, Section};
+use rendering::Heading;
+use utils::fs::{create_directory, create_file, read_file};
+
+use crate::Site;
+
+/// Where the rendered content is persisted between two checks
+pub fn cache_path(base_path: &Path) -> PathBuf {
+    base_path.join(".zola-cache").join("render.json")
+}
+
+/// What rendering the markdown of a page or section gives, along with the key of what it was
+/// rendered from
+#[derive(Debug, Serialize, Deserialize)]
+struct RenderedContent {
+    key: String,
+    content: String,
+    summary: Option<String>,
+    toc: Vec<Heading>,
+    internal_links_with_anchors: Vec<(String, String)>,
+    external_links: Vec<String>,
+}
+
+/// The content rendered by the previous check, to only render again the pages and sections
+/// that changed since then.
+/// The key of a file is a hash of the file itself, the config, the templates (as shortcodes can
+/// change the output) and the permalinks of the site (as internal links are resolved while
+/// rendering), so changing anything but the file itself renders everything again.
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    path: PathBuf,
+    /// The key of the current version of every content file, by path
+    keys: HashMap<PathBuf, String>,
+    /// What was rendered by the previous check, by path
+    previous: HashMap<String, RenderedContent>,
+}
+
+impl RenderCache {
+    /// Computes the keys of the pages and sections of the site and loads what was rendered
+    /// previously, unless the site ignores all caches
+    pub fn new(site: &Site, pages_insert_anchors: &HashMap<PathBuf, InsertAnchor>) -> RenderCache {
+        let path = cache_path(&site.base_path);
+        let site_key = site_key(site);
+        let library = site.library.read().unwrap();
+        let mut keys = HashMap::new();
+        for page in library.pages_values() {
+            let insert_anchor = pages_insert_anchors.get(&page.file.path);
+            let extra = format!("{}{:?}", site_key, insert_anchor);
+            if let Some(key) = file_key(&page.file.path, &extra) {
+                keys.insert(page.file.path.clone(), key);
+            }
+        }
+        for section in library.sections_values() {
+            if let Some(key) = file_key(&section.file.path, &site_key) {
+                keys.insert(section.file.path.clone(), key);
+            }
+        }
+
+        let previous = if site.force {
+            HashMap::new()
+        } else {
+            read_file(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default()
+        };
+
+        RenderCache { path, keys, previous }
+    }
+
+    /// What was rendered for that file if it didn't change since
+    fn get(&self, path: &Path) -> Option<&RenderedContent> {
+        let key = self.keys.get(path)?;
+        self.previous.get(&*path.to_string_lossy()).filter(|r| &r.key == key)
+    }
+
+    /// Fills the rendered fields of the page from the cache, returns whether it was found there
+    pub fn restore_page(&self, page: &mut Page) -> bool {
+        match self.get(&page.file.path) {
+            Some(rendered) => {
+                page.content = rendered.content.clone();
+                page.summary = rendered.summary.clone();
+                page.toc = rendered.toc.clone();
+                page.internal_links_with_anchors = rendered.internal_links_with_anchors.clone();
+                page.external_links = rendered.external_links.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Same as `restore_page` for sections
+    pub fn restore_section(&self, section: &mut Section) -> bool {
+        match self.get(&section.file.path) {
+            Some(rendered) => {
+                section.content = rendered.content.clone();
+                section.toc = rendered.toc.clone();
+                section.internal_links_with_anchors = rendered.internal_links_with_anchors.clone();
+                section.external_links = rendered.external_links.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes what was rendered for every page and section to disk for the next check
+    pub fn save(&self, library: &Library) -> Result<()> {
+        let mut rendered = BTreeMap::new();
+        let pages = library.pages_values().into_iter().map(|p| {
+            (
+                &p.file.path,
+                RenderedContent {
+                    key: String::new(),
+                    content: p.content.clone(),
+                    summary: p.summary.clone(),
+                    toc: p.toc.clone(),
+                    internal_links_with_anchors: p.internal_links_with_anchors.clone(),
+                    external_links: p.external_links.clone(),
+                },
+            )
+        });
+        let sections = library.sections_values().into_iter().map(|s| {
+            (
+                &s.file.path,
+                RenderedContent {
+                    key: String::new(),
+                    content: s.content.clone(),
+                    summary: None,
+                    toc: s.toc.clone(),
+                    internal_links_with_anchors: s.internal_links_with_anchors.clone(),
+                    external_links: s.external_links.clone(),
+                },
+            )
+        });
+        for (path, mut content) in pages.chain(sections) {
+            if let Some(key) = self.keys.get(path) {
+                content.key = key.clone();
+                rendered.insert(path.to_string_lossy().to_string(), content);
+            }
+        }
+
+        let json = serde_json::to_string(&rendered).expect("Couldn't serialize the render cache");
+        if let Some(parent) = self.path.parent() {
+            create_directory(parent)?;
+        }
+        create_file(&self.path, &json)
+    }
+}
+
+/// Hashes everything besides the content files that changes how they are rendered
+fn site_key(site: &Site) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_string(&site.config).expect("Couldn't serialize the config"));
+    let permalinks: BTreeMap<_, _> = site.permalinks.iter().collect();
+    for (path, permalink) in permalinks {
+        hasher.update(path);
+        hasher.update(permalink);
+    }
+
+    let mut template_dirs = vec![site.base_path.join("templates")];
+    if let Some(ref theme) = site.config.theme {
+        template_dirs.push(site.base_path.join("themes").join(theme).join("templates"));
+    }
+    for dir in template_dirs {
+        for entry in WalkDir::new(dir).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+            let entry = match entry {
+                Ok(e) if e.file_type().is_file() => e,
+                _ => continue,
+            };
+            hasher.update(entry.path().to_string_lossy().as_bytes());
+            if let Ok(content) = std::fs::read(entry.path()) {
+                hasher.update(content);
+            }
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hashes the content file with what else changes how it is rendered, `None` if the file
+/// can't be read, eg a fallback translation or a default index section
+fn file_key(path: &Path, extra: &str) -> Option<String> {
+    let content = std::fs::read(path).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(extra);
+    hasher.update(content);
+    Some(format!("{:x}", hasher.finalize()))
+}
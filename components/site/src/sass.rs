@@ -1,15 +1,80 @@
-use std::fs::create_dir_all;
+use std::fs::{create_dir_all, remove_dir_all};
 use std::path::{Path, PathBuf};
 
 use glob::glob;
 use sass_rs::{compile_file, Options, OutputStyle};
+use walkdir::WalkDir;
 
-use errors::{bail, Result};
-use utils::fs::{create_file, ensure_directory_exists};
+use errors::{bail, Error, Result};
+use utils::fs::{copy_file_if_needed, create_file, ensure_directory_exists, file_stale};
+
+/// Written in the cache directory once the compilation is done, to know whether the Sass
+/// files changed since
+const CACHE_STAMP: &str = ".stamp";
+
+/// Where `warm_sass_cache` compiles the Sass files of a site or theme
+pub fn cache_path(base_path: &Path) -> PathBuf {
+    base_path.join(".zola-cache").join("sass")
+}
+
+/// Whether the cache has been compiled since the Sass files were last modified
+fn is_cache_fresh(base_path: &Path, cache_path: &Path) -> bool {
+    let stamp = cache_path.join(CACHE_STAMP);
+    stamp.exists()
+        && WalkDir::new(base_path.join("sass"))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .all(|e| !file_stale(e.path(), &stamp))
+}
+
+/// Compiles the Sass files in the cache directory, for `compile_sass_with_cache` to reuse them
+pub fn warm_sass_cache(base_path: &Path) -> Result<()> {
+    let cache_path = cache_path(base_path);
+    if cache_path.exists() {
+        remove_dir_all(&cache_path)
+            .map_err(|e| Error::chain("Couldn't delete the Sass cache directory", e))?;
+    }
+    compile_sass(base_path, &cache_path)?;
+    create_file(&cache_path.join(CACHE_STAMP), "")
+}
+
+/// Copies the CSS files from the cache if it is up to date, compiles the Sass files otherwise
+pub fn compile_sass_with_cache(base_path: &Path, output_path: &Path) -> Result<()> {
+    let cache_path = cache_path(base_path);
+    if !is_cache_fresh(base_path, &cache_path) {
+        return compile_sass(base_path, output_path);
+    }
+
+    ensure_directory_exists(&output_path)?;
+    for entry in WalkDir::new(&cache_path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() || entry.file_name() == CACHE_STAMP {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(&cache_path).unwrap();
+        copy_file_if_needed(entry.path(), &output_path.join(relative), false)?;
+    }
+
+    Ok(())
+}
 
 pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
     ensure_directory_exists(&output_path)?;
 
+    for (css_path, css) in compile_sass_files(base_path)? {
+        let css_output_path = output_path.join(css_path);
+        if let Some(parent) = css_output_path.parent() {
+            create_dir_all(parent)?;
+        }
+        create_file(&css_output_path, &css)?;
+    }
+
+    Ok(())
+}
+
+/// Compiles the Sass files without writing them, returning the CSS of each file with its path
+/// relative to the output directory
+pub fn compile_sass_files(base_path: &Path) -> Result<Vec<(PathBuf, String)>> {
     let sass_path = {
         let mut sass_path = PathBuf::from(base_path);
         sass_path.push("sass");
@@ -18,10 +83,10 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
 
     let mut options = Options::default();
     options.output_style = OutputStyle::Compressed;
-    let mut compiled_paths = compile_sass_glob(&sass_path, output_path, "scss", &options)?;
+    let mut compiled_paths = compile_sass_glob(&sass_path, "scss", &options)?;
 
     options.indented_syntax = true;
-    compiled_paths.extend(compile_sass_glob(&sass_path, output_path, "sass", &options)?);
+    compiled_paths.extend(compile_sass_glob(&sass_path, "sass", &options)?);
 
     compiled_paths.sort();
     for window in compiled_paths.windows(2) {
@@ -35,15 +100,14 @@ pub fn compile_sass(base_path: &Path, output_path: &Path) -> Result<()> {
         }
     }
 
-    Ok(())
+    Ok(compiled_paths.into_iter().map(|(_, css_path, css)| (css_path, css)).collect())
 }
 
 fn compile_sass_glob(
     sass_path: &Path,
-    output_path: &Path,
     extension: &str,
     options: &Options,
-) -> Result<Vec<(PathBuf, PathBuf)>> {
+) -> Result<Vec<(PathBuf, PathBuf, String)>> {
     let files = get_non_partial_scss(sass_path, extension);
 
     let mut compiled_paths = Vec::new();
@@ -51,15 +115,8 @@ fn compile_sass_glob(
         let css = compile_file(&file, options.clone())?;
 
         let path_inside_sass = file.strip_prefix(&sass_path).unwrap();
-        let parent_inside_sass = path_inside_sass.parent();
-        let css_output_path = output_path.join(path_inside_sass).with_extension("css");
-
-        if parent_inside_sass.is_some() {
-            create_dir_all(&css_output_path.parent().unwrap())?;
-        }
-
-        create_file(&css_output_path, &css)?;
-        compiled_paths.push((path_inside_sass.to_owned(), css_output_path));
+        let css_path = path_inside_sass.with_extension("css");
+        compiled_paths.push((path_inside_sass.to_owned(), css_path, css));
     }
 
     Ok(compiled_paths)
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
 
 use serde_derive::Serialize;
 
@@ -8,6 +9,7 @@ use config::Config;
 use library::{Library, Taxonomy};
 use std::cmp::Ordering;
 use tera::{Map, Value};
+use utils::sitemap::ChangeFreq;
 
 /// The sitemap only needs links, potentially date and extra for pages in case of updates
 /// for examples so we trim down all entries to only that
@@ -16,6 +18,10 @@ pub struct SitemapEntry<'a> {
     pub permalink: Cow<'a, str>,
     pub updated: Option<String>,
     pub extra: Option<&'a Map<String, Value>>,
+    /// Permalinks of the images of the page, only filled if `sitemap.include_images` is set
+    pub images: Vec<String>,
+    pub priority: Option<f64>,
+    pub changefreq: Option<ChangeFreq>,
 }
 
 // Hash/Eq is not implemented for tera::Map but in our case we only care about the permalink
@@ -34,7 +40,14 @@ impl<'a> Eq for SitemapEntry<'a> {}
 
 impl<'a> SitemapEntry<'a> {
     pub fn new(permalink: Cow<'a, str>, updated: Option<String>) -> Self {
-        SitemapEntry { permalink, updated, extra: None }
+        SitemapEntry {
+            permalink,
+            updated,
+            extra: None,
+            images: Vec::new(),
+            priority: None,
+            changefreq: None,
+        }
     }
 
     pub fn add_extra(&mut self, extra: &'a Map<String, Value>) {
@@ -54,6 +67,14 @@ impl<'a> Ord for SitemapEntry<'a> {
     }
 }
 
+const IMAGE_EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "gif", "webp", "svg", "avif"];
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
 /// Finds out all the links to put in a sitemap from the pages/sections/taxonomies
 /// There are no duplicate permalinks in the output vec
 pub fn find_entries<'a>(
@@ -67,9 +88,24 @@ pub fn find_entries<'a>(
         .map(|p| {
             let mut entry = SitemapEntry::new(
                 Cow::Borrowed(&p.permalink),
-                p.meta.updated.clone().or_else(|| p.meta.date.clone()),
+                p.meta
+                    .updated
+                    .as_ref()
+                    .or_else(|| p.meta.date.as_ref())
+                    .map(|d| config.localize_date(d)),
             );
             entry.add_extra(&p.meta.extra);
+            entry.priority = p.meta.sitemap_priority;
+            entry.changefreq = p.meta.sitemap_changefreq;
+            if config.sitemap.include_images {
+                entry.images = p
+                    .assets
+                    .iter()
+                    .filter(|a| is_image(a))
+                    .filter_map(|a| a.file_name())
+                    .map(|name| format!("{}{}", p.permalink, name.to_string_lossy()))
+                    .collect();
+            }
             entry
         })
         .collect::<Vec<_>>();
@@ -99,11 +135,14 @@ pub fn find_entries<'a>(
     let mut taxonomies_entries = vec![];
     for taxonomy in taxonomies {
         let name = &taxonomy.kind.name;
+        let lang = &taxonomy.kind.lang;
         let mut terms = vec![];
-        terms.push(SitemapEntry::new(Cow::Owned(config.make_permalink(name)), None));
+        terms.push(SitemapEntry::new(Cow::Owned(config.make_permalink_for_lang(name, lang)), None));
         for item in &taxonomy.items {
             terms.push(SitemapEntry::new(
-                Cow::Owned(config.make_permalink(&format!("{}/{}", name, item.slug))),
+                Cow::Owned(
+                    config.make_permalink_for_lang(&format!("{}/{}", name, item.slug), lang),
+                ),
                 None,
             ));
 
@@ -112,13 +151,10 @@ pub fn find_entries<'a>(
                     / taxonomy.kind.paginate_by.unwrap() as f64)
                     .ceil() as isize;
                 for i in 1..=number_pagers {
-                    let permalink = config.make_permalink(&format!(
-                        "{}/{}/{}/{}",
-                        name,
-                        item.slug,
-                        taxonomy.kind.paginate_path(),
-                        i
-                    ));
+                    let permalink = config.make_permalink_for_lang(
+                        &format!("{}/{}/{}/{}", name, item.slug, taxonomy.kind.paginate_path(), i),
+                        lang,
+                    );
                     terms.push(SitemapEntry::new(Cow::Owned(permalink), None))
                 }
             }
@@ -141,6 +177,10 @@ pub fn find_entries<'a>(
     }
 
     let mut entries = all_sitemap_entries.into_iter().collect::<Vec<_>>();
+    for entry in entries.iter_mut() {
+        entry.priority = entry.priority.or(config.sitemap.priority);
+        entry.changefreq = entry.changefreq.or(config.sitemap.changefreq);
+    }
     entries.sort();
     entries
 }
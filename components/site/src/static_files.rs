@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use relative_path::{Component, RelativePath};
+
+/// Finds the files of the `static` directories of a site and of its theme, so `zola serve` can
+/// serve them from there rather than copying them to the output directory
+#[derive(Debug, Clone, Default)]
+pub struct StaticResolver {
+    /// The directories to look into, in order of priority
+    roots: Vec<PathBuf>,
+}
+
+impl StaticResolver {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        StaticResolver { roots }
+    }
+
+    /// Returns the file matching the given URL path, eg `/css/site.css`, if there is one.
+    /// Paths going up a directory are never resolved
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let relative = RelativePath::new(path.trim_start_matches('/'));
+        if relative.components().any(|c| c == Component::ParentDir) {
+            return None;
+        }
+
+        self.roots.iter().map(|root| relative.to_path(root)).find(|p| p.is_file())
+    }
+}
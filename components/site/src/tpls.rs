@@ -1,11 +1,15 @@
+use std::collections::HashMap;
 use std::path::Path;
 
-use tera::Tera;
+use tera::{Map, Tera, Value};
+use walkdir::WalkDir;
 
 use crate::Site;
 use config::Config;
 use errors::{bail, Error, Result};
 use templates::{filters, global_fns, ZOLA_TERA};
+use utils::de::fix_toml_dates;
+use utils::fs::read_file;
 use utils::templates::rewrite_theme_paths;
 
 pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
@@ -17,6 +21,19 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
     let mut tera =
         Tera::parse(&tpl_glob).map_err(|e| Error::chain("Error parsing templates", e))?;
 
+    // `extend` doesn't override existing templates so we go from the last directory to the first
+    for dir in config.extra_template_dirs.iter().rev() {
+        let dir_path = path.join(dir);
+        if !dir_path.exists() {
+            bail!("Template directory `{}` of `extra_template_dirs` doesn't exist", dir);
+        }
+        let dir_glob =
+            format!("{}/{}", dir_path.to_string_lossy().replace("\\", "/"), "**/*.{*ml,md}");
+        let tera_extra = Tera::parse(&dir_glob)
+            .map_err(|e| Error::chain(format!("Error parsing templates from `{}`", dir), e))?;
+        tera.extend(&tera_extra)?;
+    }
+
     if let Some(ref theme) = config.theme {
         // Test that the templates folder exist for that theme
         let theme_path = path.join("themes").join(&theme);
@@ -36,6 +53,12 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
         if theme_path.join("templates").join("robots.txt").exists() {
             tera_theme.add_template_file(theme_path.join("templates").join("robots.txt"), None)?;
         }
+        if theme_path.join("templates").join("humans.txt").exists() {
+            tera_theme.add_template_file(
+                theme_path.join("templates").join("humans.txt"),
+                Some(&format!("{}/templates/humans.txt", theme)),
+            )?;
+        }
         tera.extend(&tera_theme)?;
     }
     tera.extend(&ZOLA_TERA)?;
@@ -44,13 +67,109 @@ pub fn load_tera(path: &Path, config: &Config) -> Result<Tera> {
     if path.join("templates").join("robots.txt").exists() {
         tera.add_template_file(path.join("templates").join("robots.txt"), Some("robots.txt"))?;
     }
+    if path.join("templates").join("humans.txt").exists() {
+        tera.add_template_file(path.join("templates").join("humans.txt"), Some("humans.txt"))?;
+    }
 
     Ok(tera)
 }
 
+/// Parses a single file of the `data` directory according to its extension.
+/// Returns `None` for files we don't know how to parse.
+fn parse_data_file(path: &Path) -> Result<Option<Value>> {
+    let ext = match path.extension().and_then(|e| e.to_str()) {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let content = match ext {
+        "toml" | "json" | "yaml" | "yml" => read_file(path)?,
+        _ => return Ok(None),
+    };
+
+    let value = match ext {
+        "toml" => {
+            let toml_value: toml::Value = toml::from_str(&content)
+                .map_err(|e| Error::chain(format!("Failed to parse {}", path.display()), e))?;
+            match tera::to_value(toml_value)? {
+                Value::Object(m) => fix_toml_dates(m),
+                v => v,
+            }
+        }
+        "json" => serde_json::from_str(&content)
+            .map_err(|e| Error::chain(format!("Failed to parse {}", path.display()), e))?,
+        _ => serde_yaml::from_str(&content)
+            .map_err(|e| Error::chain(format!("Failed to parse {}", path.display()), e))?,
+    };
+
+    Ok(Some(value))
+}
+
+/// Loads all the TOML/JSON/YAML files of the `data` directory into a map keyed by filename
+/// (without extension). Files in sub-directories end up in nested maps, eg `data/nav/main.toml`
+/// is available as `data.nav.main`.
+pub fn load_data_directory(path: &Path) -> Result<HashMap<String, Value>> {
+    let mut data = HashMap::new();
+    if !path.exists() {
+        return Ok(data);
+    }
+
+    for entry in WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry.map_err(|e| Error::chain("Failed to read the data directory", e))?;
+        let file_path = entry.path();
+        if !file_path.is_file() {
+            continue;
+        }
+        let value = match parse_data_file(file_path)? {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let relative = file_path.strip_prefix(path).unwrap().with_extension("");
+        let mut keys: Vec<String> =
+            relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+        let last = keys.pop().unwrap();
+        if keys.is_empty() {
+            data.insert(last, value);
+            continue;
+        }
+
+        let first = keys.remove(0);
+        let mut current = data.entry(first).or_insert_with(|| Value::Object(Map::new()));
+        for key in keys {
+            current = match current {
+                Value::Object(m) => m.entry(key).or_insert_with(|| Value::Object(Map::new())),
+                _ => bail!(
+                    "`{}` conflicts with another file in the data directory",
+                    file_path.display()
+                ),
+            };
+        }
+        match current {
+            Value::Object(m) => {
+                m.insert(last, value);
+            }
+            _ => {
+                bail!("`{}` conflicts with another file in the data directory", file_path.display())
+            }
+        }
+    }
+
+    Ok(data)
+}
+
 /// Adds global fns that are to be available to shortcodes while rendering markdown
 pub fn register_early_global_fns(site: &mut Site) {
+    let build_hash = Value::String(site.build_hash.clone());
+    site.tera
+        .register_function("build_hash", move |_: &HashMap<String, Value>| Ok(build_hash.clone()));
+    let build_time = Value::String(site.build_time.clone());
+    site.tera
+        .register_function("build_time", move |_: &HashMap<String, Value>| Ok(build_time.clone()));
     site.tera.register_filter("markdown", filters::MarkdownFilter::new(site.config.clone()));
+    site.tera.register_filter(
+        "resolve_internal_links",
+        filters::ResolveInternalLinksFilter::new(site.permalinks.clone()),
+    );
 
     site.tera.register_function(
         "get_url",
@@ -62,12 +181,14 @@ pub fn register_early_global_fns(site: &mut Site) {
     );
     site.tera
         .register_function("resize_image", global_fns::ResizeImage::new(site.imageproc.clone()));
+    site.tera.register_function("image_lqip", global_fns::ImageLqip::new(site.imageproc.clone()));
     site.tera.register_function(
         "get_image_metadata",
         global_fns::GetImageMeta::new(site.content_path.clone()),
     );
     site.tera.register_function("load_data", global_fns::LoadData::new(site.base_path.clone()));
     site.tera.register_function("trans", global_fns::Trans::new(site.config.clone()));
+    site.tera.register_function("extra", global_fns::GetExtra::new(&site.config));
     site.tera.register_function(
         "get_taxonomy_url",
         global_fns::GetTaxonomyUrl::new(
@@ -84,6 +205,50 @@ pub fn register_early_global_fns(site: &mut Site) {
             site.content_path.clone(),
         ]),
     );
+    site.tera.register_function(
+        "get_sri_hash",
+        global_fns::GetSriHash::new(
+            vec![site.static_path.clone(), site.output_path.clone()],
+            site.sri_hashes.clone(),
+        ),
+    );
+    site.tera.register_function(
+        "load_css",
+        global_fns::LoadCss::new(vec![site.static_path.clone(), site.output_path.clone()]),
+    );
+    site.tera.register_function(
+        "bundle",
+        global_fns::Bundle::new(
+            site.config.clone(),
+            vec![site.static_path.clone(), site.output_path.clone()],
+            site.bundles.clone(),
+        ),
+    );
+    let opensearch_url = if site.config.search.opensearch {
+        Some(Value::String(
+            site.config.make_permalink("opensearch.xml").trim_end_matches('/').to_string(),
+        ))
+    } else {
+        None
+    };
+    site.tera.register_function("get_opensearch_url", move |_: &HashMap<String, Value>| {
+        match opensearch_url {
+            Some(ref url) => Ok(url.clone()),
+            None => {
+                Err("`get_opensearch_url` requires `search.opensearch = true` in the config".into())
+            }
+        }
+    });
+    site.tera.register_function(
+        "include_md",
+        global_fns::IncludeMd::new(
+            &site.shared_tera,
+            site.config.clone(),
+            site.permalinks.clone(),
+            site.content_path.clone(),
+        ),
+    );
+    site.share_tera();
 }
 
 /// Functions filled once we have parsed all the pages/sections only, so not available in shortcodes
@@ -96,6 +261,22 @@ pub fn register_tera_global_fns(site: &mut Site) {
         "get_section",
         global_fns::GetSection::new(site.base_path.clone(), site.library.clone()),
     );
+    site.tera.register_function(
+        "get_section_page_count",
+        global_fns::GetSectionPageCount::new(site.base_path.clone(), site.library.clone()),
+    );
+    site.tera.register_function(
+        "get_breadcrumbs",
+        global_fns::GetBreadcrumbs::new(site.base_path.clone(), site.library.clone()),
+    );
+    site.tera.register_function(
+        "get_languages",
+        global_fns::GetLanguages::new(
+            site.base_path.clone(),
+            site.config.clone(),
+            site.library.clone(),
+        ),
+    );
     site.tera.register_function(
         "get_taxonomy",
         global_fns::GetTaxonomy::new(
@@ -104,4 +285,31 @@ pub fn register_tera_global_fns(site: &mut Site) {
             site.library.clone(),
         ),
     );
+    site.tera.register_function(
+        "get_page_siblings",
+        global_fns::GetPageSiblings::new(
+            site.base_path.clone(),
+            site.taxonomies.clone(),
+            site.library.clone(),
+            site.config.slugify.taxonomies,
+        ),
+    );
+    site.tera.register_function(
+        "get_related_pages",
+        global_fns::GetRelatedPages::new(
+            site.base_path.clone(),
+            site.taxonomies.clone(),
+            site.library.clone(),
+        ),
+    );
+    site.tera.register_function(
+        "get_taxonomy_term",
+        global_fns::GetTaxonomyTerm::new(
+            &site.config.default_language,
+            site.taxonomies.clone(),
+            site.library.clone(),
+            site.config.slugify.taxonomies,
+        ),
+    );
+    site.share_tera();
 }
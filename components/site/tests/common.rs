@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use std::env;
+use std::fs;
 use std::path::PathBuf;
 
 use site::Site;
@@ -67,3 +68,43 @@ where
     site.build().expect("Couldn't build the site");
     (site, tmp_dir, public.clone())
 }
+
+/// Creates a site in a temporary directory from the given files, with `/`-separated paths
+/// relative to the site directory, and a `config.toml` made of a `base_url` of
+/// `https://example.com` followed by `config`.
+/// We return the tmpdir otherwise it would get out of scope and be deleted
+pub fn create_tmp_site(config: &str, files: &[(&str, &str)]) -> TempDir {
+    let tmp_dir = tempdir().expect("create temp dir");
+    fs::create_dir_all(tmp_dir.path().join("content")).unwrap();
+    fs::create_dir_all(tmp_dir.path().join("templates")).unwrap();
+    for (file, content) in files {
+        let path = file.split('/').fold(tmp_dir.path().to_path_buf(), |p, c| p.join(c));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+    }
+    fs::write(
+        tmp_dir.path().join("config.toml"),
+        format!("base_url = \"https://example.com\"\n{}", config),
+    )
+    .unwrap();
+    tmp_dir
+}
+
+/// Same as `create_tmp_site` but also loads the site, with its output path set to the `public`
+/// directory of the temporary directory
+pub fn load_tmp_site(config: &str, files: &[(&str, &str)]) -> (Site, TempDir, PathBuf) {
+    let tmp_dir = create_tmp_site(config, files);
+    let path = tmp_dir.path();
+    let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    let public = path.join("public");
+    site.set_output_path(&public);
+    (site, tmp_dir, public)
+}
+
+/// Same as `load_tmp_site` but also builds the site
+pub fn build_tmp_site(config: &str, files: &[(&str, &str)]) -> (Site, TempDir, PathBuf) {
+    let (site, tmp_dir, public) = load_tmp_site(config, files);
+    site.build().expect("Couldn't build the site");
+    (site, tmp_dir, public)
+}
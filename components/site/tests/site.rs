@@ -2,12 +2,23 @@ mod common;
 
 use std::collections::HashMap;
 use std::env;
-use std::path::Path;
-
-use common::{build_site, build_site_with_setup};
-use config::Taxonomy;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use chrono::Local;
+use common::{build_site, build_site_with_setup, build_tmp_site, create_tmp_site, load_tmp_site};
+use config::{ExtraFeed, PaginatePageOne, SortTiesBy, Taxonomy};
+use flate2::read::GzDecoder;
+use library::Page;
+use relative_path::RelativePath;
+use site::link_checking;
 use site::sitemap;
 use site::Site;
+use tempfile::tempdir;
+use utils::sitemap::ChangeFreq;
 
 #[test]
 fn can_parse_site() {
@@ -268,6 +279,56 @@ fn can_build_site_with_live_reload_and_drafts() {
     assert!(file_exists!(public, "secret_section/secret_sub_section/hello/index.html"));
 }
 
+#[test]
+fn can_hide_section_from_parent_subsections() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let devops_path = site.base_path.join("content/posts/tutorials/devops/_index.md");
+            library.get_section_mut(&devops_path).unwrap().meta.in_section_list = false;
+            library.populate_sections(&site.config);
+        }
+        (site, false)
+    });
+
+    let library = site.library.read().unwrap();
+    let tutorials =
+        library.get_section(&site.base_path.join("content/posts/tutorials/_index.md")).unwrap();
+    assert_eq!(tutorials.subsections.len(), 1);
+    assert!(!file_contains!(public, "posts/tutorials/index.html", "DevOps"));
+    assert!(file_contains!(public, "posts/tutorials/index.html", "Programming"));
+    // The hidden section and its pages are still rendered
+    assert!(file_exists!(public, "posts/tutorials/devops/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
+}
+
+#[test]
+fn can_build_site_without_pretty_urls() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.pretty_urls = false;
+        site.include_drafts();
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "hello.html"));
+    assert!(!file_exists!(public, "hello/index.html"));
+    assert!(file_exists!(public, "a-fixed-url.html"));
+    assert!(file_exists!(public, "posts/tutorials/devops/nix.html"));
+    // Assets stay in a directory of their own page
+    assert!(file_exists!(public, "posts/with-assets.html"));
+    assert!(file_exists!(public, "posts/with-assets/zola.png"));
+    assert!(!file_exists!(public, "posts/zola.png"));
+    // Sections keep their directory
+    assert!(file_exists!(public, "posts/index.html"));
+    // Internal links point to the flat file
+    assert!(file_contains!(
+        public,
+        "posts/draft.html",
+        "https://replace-this-with-your-url.com/hello.html"
+    ));
+}
+
 #[test]
 fn can_build_site_with_taxonomies() {
     let (site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
@@ -474,6 +535,101 @@ fn can_build_site_with_pagination_for_section() {
     ));
 }
 
+#[test]
+fn can_build_site_with_several_pagination_sizes() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let section = library
+                .get_section_mut(&site.base_path.join("content").join("posts").join("_index.md"))
+                .unwrap();
+            section.meta.paginate_by = Some(2);
+            section.meta.paginate_by_variants = vec![5];
+            section.meta.template = Some("section_paginated.html".to_string());
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(public, "posts/index.html", "Num pagers: 5"));
+    assert!(file_exists!(public, "posts/page/5/index.html"));
+
+    // The variant pagers all live under their own path, the first one included
+    assert!(file_contains!(public, "posts/page5/1/index.html", "Num pagers: 2"));
+    assert!(file_contains!(public, "posts/page5/1/index.html", "Page size: 5"));
+    assert!(file_contains!(public, "posts/page5/1/index.html", "Current index: 1"));
+    assert!(file_contains!(
+        public,
+        "posts/page5/1/index.html",
+        "First: https://replace-this-with-your-url.com/posts/page5/1/"
+    ));
+    assert!(file_contains!(public, "posts/page5/2/index.html", "Current index: 2"));
+    assert!(!file_exists!(public, "posts/page5/3/index.html"));
+}
+
+#[cfg(unix)]
+#[test]
+fn can_dedupe_identical_assets() {
+    use std::os::unix::fs::MetadataExt;
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.dedupe_assets = true;
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let posts_path = site.base_path.join("content").join("posts");
+            let asset = posts_path.join("with-assets").join("zola.png");
+            let key = library.paths_to_pages[&posts_path.join("python.md")];
+            library.get_page_mut_by_key(key).assets.push(asset);
+        }
+        (site, false)
+    });
+
+    assert!(file_exists!(public, "posts/with-assets/zola.png"));
+    assert!(file_exists!(public, "posts/python/zola.png"));
+    let first = fs::metadata(public.join("posts/with-assets/zola.png")).unwrap();
+    let second = fs::metadata(public.join("posts/python/zola.png")).unwrap();
+    assert_eq!(first.ino(), second.ino());
+}
+
+#[test]
+fn can_list_all_permalinks() {
+    let (mut site, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let section = library
+                .get_section_mut(&site.base_path.join("content").join("posts").join("_index.md"))
+                .unwrap();
+            section.meta.paginate_by = Some(2);
+            section.meta.template = Some("section_paginated.html".to_string());
+        }
+        (site, false)
+    });
+
+    let permalinks = site.all_permalinks().unwrap();
+    let base_url = "https://replace-this-with-your-url.com";
+    assert!(permalinks.contains(&format!("{}/posts/python/", base_url)));
+    assert!(permalinks.contains(&format!("{}/posts/", base_url)));
+    assert!(permalinks.contains(&format!("{}/posts/page/2/", base_url)));
+    assert!(permalinks.contains(&format!("{}/categories/", base_url)));
+    assert!(permalinks.contains(&format!("{}/categories/a-category/", base_url)));
+    assert!(permalinks.contains(&format!("{}/categories/a-category/atom.xml", base_url)));
+    assert!(permalinks.contains(&format!("{}/atom.xml", base_url)));
+    assert!(!permalinks.contains(&format!("{}/404.html", base_url)));
+    assert!(!permalinks.iter().any(|p| p.ends_with("sitemap.xml")));
+    // Every HTML file written by the build is listed
+    for entry in walkdir::WalkDir::new(&public).into_iter().filter_map(|e| e.ok()) {
+        let relative =
+            entry.path().strip_prefix(&public).unwrap().to_string_lossy().replace('\\', "/");
+        if !relative.ends_with("/index.html") {
+            continue;
+        }
+        let permalink = format!("{}/{}", base_url, relative.trim_end_matches("index.html"));
+        assert!(permalinks.contains(&permalink), "{} is missing", permalink);
+    }
+}
+
 #[test]
 fn can_build_site_with_pagination_for_index() {
     let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
@@ -648,6 +804,219 @@ fn can_build_feeds() {
     assert!(!file_contains!(public, "posts/tutorials/programming/atom.xml", "Extra Syntax"));
 }
 
+#[test]
+fn can_limit_feeds_to_recent_pages() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed.max_age_days = Some(30);
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let path = site.base_path.join("content").join("posts").join("python.md");
+            let key = library.paths_to_pages[&path];
+            let page = library.get_page_mut_by_key(key);
+            let now = Local::now().naive_local();
+            page.meta.date = Some(now.format("%Y-%m-%d").to_string());
+            page.meta.datetime = Some(now);
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(public, "atom.xml", "Python in posts"));
+    // posts/extra_syntax.md is dated 2018
+    assert!(!file_contains!(public, "atom.xml", "Extra Syntax"));
+}
+
+#[test]
+fn can_build_extra_feeds() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.extra_feeds = vec![ExtraFeed {
+            path: "programming-feed".to_string(),
+            section: Some("posts/tutorials/programming/_index.md".to_string()),
+            ..ExtraFeed::default()
+        }];
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "programming-feed/atom.xml"));
+    assert!(file_contains!(public, "programming-feed/atom.xml", "Python tutorial"));
+    assert!(file_contains!(public, "programming-feed/atom.xml", "Rust"));
+    // Only the pages of that section are in it
+    assert!(!file_contains!(public, "programming-feed/atom.xml", "Extra Syntax"));
+    assert!(!file_contains!(public, "programming-feed/atom.xml", "Nix"));
+}
+
+#[test]
+fn can_use_timezone_for_naive_dates_in_feeds_and_sitemap() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.timezone = Some("Europe/Paris".to_string());
+        (site, true)
+    });
+
+    // posts/extra_syntax.md has `date = 2018-08-14`
+    assert!(file_contains!(public, "atom.xml", "<published>2018-08-14T00:00:00+02:00</published>"));
+    assert!(file_contains!(public, "sitemap.xml", "<lastmod>2018-08-14T00:00:00+02:00</lastmod>"));
+}
+
+#[test]
+fn can_include_images_in_sitemap() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.sitemap.include_images = true;
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        r#"xmlns:image="http://www.google.com/schemas/sitemap-image/1.1""#
+    ));
+    assert!(file_contains!(
+        public,
+        "sitemap.xml",
+        "<image:loc>https://replace-this-with-your-url.com/posts/with-assets/zola.png</image:loc>"
+    ));
+    // Only images are listed
+    assert!(!file_contains!(public, "sitemap.xml", "with.js"));
+}
+
+#[test]
+fn can_render_section_as_single_page() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        // The library is empty until the site is loaded
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let section = library
+                .get_section_mut(
+                    &site
+                        .base_path
+                        .join("content")
+                        .join("posts")
+                        .join("tutorials")
+                        .join("_index.md"),
+                )
+                .unwrap();
+            section.meta.single_page = true;
+            section.meta.template = Some("book.html".to_string());
+        }
+        (site, false)
+    });
+
+    // It contains the content of the 4 pages of its subsections
+    let content =
+        fs::read_to_string(public.join("posts").join("tutorials").join("index.html")).unwrap();
+    assert!(content.starts_with("Single page:"));
+    assert_eq!(content.matches("<p>A simple page</p>").count(), 4);
+    assert!(content.contains("Link to some rust-lang"));
+    // The pages are still rendered on their own
+    assert!(file_exists!(public, "posts/tutorials/programming/python/index.html"));
+}
+
+#[test]
+fn can_write_page_with_custom_output_filename() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            ("content/_index.md", "+++\n+++\n"),
+            ("content/hello.md", "+++\noutput_filename = \"feed.html\"\n+++\nHello"),
+            ("content/other.md", "+++\n+++\n[hello](@/hello.md)"),
+            ("templates/index.html", "Index"),
+            ("templates/page.html", "{{ page.content | safe }}"),
+        ],
+    );
+
+    assert!(file_exists!(public, "hello/feed.html"));
+    assert!(!file_exists!(public, "hello/index.html"));
+    assert!(file_contains!(public, "other/index.html", "https://example.com/hello/feed.html"));
+}
+
+#[test]
+fn can_use_macros_from_extra_template_dirs() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "extra_template_dirs = [\"shared\", \"overrides\"]\n",
+        &[
+            ("content/hello.md", "+++\ntitle = \"Hello\"\n+++\n"),
+            ("templates/index.html", "Index"),
+            (
+                "templates/page.html",
+                "{% import \"macros.html\" as macros %}{{ macros::heading(title=page.title) }}",
+            ),
+            ("shared/macros.html", "{% macro heading(title) %}<h1>{{ title }}</h1>{% endmacro %}"),
+            (
+                "overrides/macros.html",
+                "{% macro heading(title) %}<h1 class=\"shared\">{{ title }}</h1>{% endmacro %}",
+            ),
+        ],
+    );
+
+    assert!(file_contains!(public, "hello/index.html", "<h1 class=\"shared\">Hello</h1>"));
+}
+
+#[test]
+fn can_warm_cache_before_building() {
+    let (mut site, tmp_dir, public) = load_tmp_site(
+        "compile_sass = true\n",
+        &[
+            ("content/_index.md", "+++\n+++\n"),
+            (
+                "templates/index.html",
+                r#"{{ resize_image(path="zola.png", width=10, height=10, op="fill") }}"#,
+            ),
+            ("sass/style.scss", "body { p { color: red; } }"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let mut fixtures =
+        env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    fixtures.push("test_site");
+    fs::copy(
+        fixtures.join("content").join("posts").join("with-assets").join("zola.png"),
+        path.join("content").join("zola.png"),
+    )
+    .unwrap();
+
+    site.warm_cache().unwrap();
+    assert_eq!(site.num_processed_images(), 1);
+    assert!(path.join(".zola-cache").join("sass").join("style.css").exists());
+    // Nothing is written by warming the cache
+    assert!(!public.exists());
+
+    site.load().unwrap();
+    site.build().unwrap();
+    assert_eq!(site.num_processed_images(), 0);
+    assert!(file_contains!(public, "style.css", "color:red"));
+}
+
+#[test]
+fn can_verify_theme_hash() {
+    let tmp_dir = create_tmp_site(
+        "",
+        &[
+            ("themes/vendored/theme.toml", "name = \"vendored\"\n"),
+            ("themes/vendored/templates/index.html", "Hello"),
+            ("templates/page.html", "{{ page.title }}"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let theme_path = path.join("themes").join("vendored");
+
+    let hash = site::compute_theme_hash(&theme_path).unwrap();
+    let config_file = path.join("config.toml");
+    fs::write(
+        &config_file,
+        format!(
+            "base_url = \"https://example.com\"\ntheme = \"vendored\"\ntheme_expected_hash = \"{}\"\n",
+            hash
+        ),
+    )
+    .unwrap();
+    assert!(Site::new(&path, &config_file).is_ok());
+
+    fs::write(theme_path.join("templates").join("index.html"), "Hello, tampered").unwrap();
+    let err = Site::new(&path, &config_file).err().unwrap().to_string();
+    assert!(err.contains("theme_expected_hash"));
+    assert!(err.contains(&hash));
+}
+
 #[test]
 fn can_build_search_index() {
     let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
@@ -658,6 +1027,21 @@ fn can_build_search_index() {
     assert!(Path::new(&public).exists());
     assert!(file_exists!(public, "elasticlunr.min.js"));
     assert!(file_exists!(public, "search_index.en.js"));
+    assert!(!file_exists!(public, "search_index.en.js.gz"));
+}
+
+#[test]
+fn can_gzip_search_index() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.build_search_index = true;
+        site.config.search.gzip_index = true;
+        (site, true)
+    });
+
+    let compressed = fs::read(public.join("search_index.en.js.gz")).unwrap();
+    let mut decompressed = String::new();
+    GzDecoder::new(&compressed[..]).read_to_string(&mut decompressed).unwrap();
+    assert_eq!(decompressed, fs::read_to_string(public.join("search_index.en.js")).unwrap());
 }
 
 #[test]
@@ -740,6 +1124,17 @@ fn can_build_site_with_html_minified() {
     ));
 }
 
+#[test]
+fn can_disable_minification_at_runtime() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.minify_html = true;
+        site.set_minify(false);
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "index.html", "<!DOCTYPE html>\n<html lang=\"en\">"));
+}
+
 #[test]
 fn can_ignore_markdown_content() {
     let (_, _tmp_dir, public) = build_site("test_site");
@@ -783,3 +1178,1284 @@ fn check_site() {
 fn current_path(path: &str) -> String {
     format!("[current_path]({})", path)
 }
+
+#[test]
+fn can_collect_build_warnings() {
+    let (site, _tmp_dir, _public) = build_site("test_site");
+    let warnings = site.warnings();
+    assert!(!warnings.is_empty());
+    assert!(warnings
+        .iter()
+        .any(|w| w.message.contains("Expected a section filename, got `_index.de.md`")));
+}
+
+#[test]
+fn can_replace_build_warnings_when_building_again() {
+    let tmp_dir = create_tmp_site(
+        "[imageproc]\nmax_dimension = 20\nclamp_to_max_dimension = true\n",
+        &[(
+            "templates/index.html",
+            "{{ resize_image(path=\"zola.png\", width=30, height=10, op=\"scale\") }}",
+        )],
+    );
+    let path = tmp_dir.path();
+    let test_site =
+        env::current_dir().unwrap().parent().unwrap().parent().unwrap().join("test_site");
+    fs::copy(
+        test_site.join("content").join("posts").join("with-assets").join("zola.png"),
+        path.join("content").join("zola.png"),
+    )
+    .unwrap();
+
+    let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    site.set_output_path(path.join("public"));
+    let clamped = |site: &Site| {
+        site.warnings().iter().filter(|w| w.message.contains("`max_dimension` of 20px")).count()
+    };
+    site.build().unwrap();
+    assert_eq!(clamped(&site), 1);
+    site.build().unwrap();
+    assert_eq!(clamped(&site), 1);
+}
+
+#[test]
+fn can_use_data_directory_in_templates() {
+    let (site, _tmp_dir, public) = build_site("test_site");
+    assert!(site.config.data.contains_key("menu"));
+    assert!(file_contains!(public, "index.html", r#"<a href="/posts/">Posts</a>"#));
+}
+
+#[test]
+fn can_build_section_feeds_without_site_feed() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_feed = false;
+        (site, true)
+    });
+
+    assert!(!file_exists!(public, "atom.xml"));
+    assert!(file_exists!(public, "posts/atom.xml"));
+    assert!(file_exists!(public, "posts/tutorials/programming/atom.xml"));
+    assert!(file_contains!(public, "posts/tutorials/programming/atom.xml", "Python tutorial"));
+}
+
+#[test]
+fn can_build_only_a_subtree() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.load().unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = &tmp_dir.path().join("public");
+    site.set_output_path(&public);
+
+    site.build_subtree("/posts/tutorials/").expect("Couldn't build the subtree");
+    assert!(file_exists!(public, "posts/tutorials/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/programming/index.html"));
+    assert!(file_exists!(public, "posts/tutorials/devops/nix/index.html"));
+    assert!(!file_exists!(public, "index.html"));
+    assert!(!file_exists!(public, "posts/index.html"));
+    assert!(!file_exists!(public, "posts/simple/index.html"));
+    assert!(!file_exists!(public, "categories/a-category/index.html"));
+
+    // Taxonomy terms of the pages in the subtree are rendered
+    site.build_subtree("/rebuild/").expect("Couldn't build the subtree");
+    assert!(file_exists!(public, "rebuild/first/index.html"));
+    assert!(file_exists!(public, "categories/a-category/index.html"));
+    assert!(!file_exists!(public, "categories/index.html"));
+}
+
+#[test]
+fn can_render_site_to_vec() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.config.build_search_index = true;
+    site.load().unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = &tmp_dir.path().join("public");
+    site.set_output_path(&public);
+
+    let outputs = site.render_to_vec().expect("Couldn't render the site");
+    let get = |p: &str| {
+        outputs
+            .iter()
+            .find(|(path, _)| path.as_str() == p)
+            .map(|(_, content)| String::from_utf8(content.clone()).unwrap())
+    };
+
+    assert!(get("index.html").unwrap().contains("<!DOCTYPE html>"));
+    assert!(get("posts/python/index.html").unwrap().contains("Basic shortcode"));
+    assert!(get("posts/with-assets/index.html").is_some());
+    assert!(get("posts/with-assets/with.js").is_some());
+    assert!(get("sitemap.xml").is_some());
+    assert!(get("scripts/hello.js").is_some());
+    assert!(get("blog.css").is_some());
+    assert!(get("nested_sass/sass.css").is_some());
+    assert!(get("search_index.en.js").unwrap().starts_with("window.searchIndex = "));
+    assert!(get("elasticlunr.min.js").is_some());
+    // Nothing got written to disk
+    assert!(!public.exists());
+}
+
+#[test]
+fn can_build_several_sites_in_memory_concurrently() {
+    let handles: Vec<_> = vec!["test_site", "test_site_i18n"]
+        .into_iter()
+        .map(|name| {
+            thread::spawn(move || {
+                let mut path =
+                    env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+                path.push(name);
+                let config_file = path.join("config.toml");
+                let mut site = Site::new(&path, &config_file).unwrap();
+                site.enable_serve_mode();
+                site.load().unwrap();
+                let tmp_dir = tempdir().expect("create temp dir");
+                site.set_output_path(&tmp_dir.path().join("public"));
+                site.build().unwrap();
+                site
+            })
+        })
+        .collect();
+    let sites: Vec<Site> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    let content = sites[0].content_map.read().unwrap();
+    let content_i18n = sites[1].content_map.read().unwrap();
+    assert!(content.contains_key(RelativePath::new("posts/python")));
+    assert!(!content.contains_key(RelativePath::new("fr/blog")));
+    assert!(content_i18n.contains_key(RelativePath::new("fr/blog")));
+    assert!(!content_i18n.contains_key(RelativePath::new("posts/python")));
+}
+
+#[test]
+fn can_inject_extra_head_and_body_end() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.extra_head = Some(r#"<meta name="zola-head">"#.to_string());
+        site.config.extra_body_end = Some("<script>zolaBodyEnd()</script>".to_string());
+        (site, true)
+    });
+
+    assert!(file_contains!(public, "index.html", r#"<meta name="zola-head"></head>"#));
+    assert!(file_contains!(public, "index.html", "<script>zolaBodyEnd()</script></body>"));
+    assert!(file_contains!(public, "posts/python/index.html", r#"<meta name="zola-head">"#));
+    // Only HTML pages get them
+    for path in &["atom.xml", "sitemap.xml", "robots.txt"] {
+        assert!(!file_contains!(public, path, "zola-head"));
+        assert!(!file_contains!(public, path, "zolaBodyEnd"));
+    }
+}
+
+#[test]
+fn can_duplicate_first_pager_content() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.paginate_page_one = PaginatePageOne::Duplicate;
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "posts/page/1/index.html"));
+    assert!(!file_contains!(public, "posts/page/1/index.html", "http-equiv=\"refresh\""));
+    assert!(file_contains!(public, "posts/page/1/index.html", "Current index: 1"));
+    // The first pager still links to the index as its URL
+    assert!(file_contains!(
+        public,
+        "posts/page/1/index.html",
+        "First: https://replace-this-with-your-url.com/posts/"
+    ));
+    assert_eq!(
+        fs::read_to_string(public.join("posts/page/1/index.html")).unwrap(),
+        fs::read_to_string(public.join("posts/index.html")).unwrap()
+    );
+}
+
+#[test]
+fn can_write_aliases_in_redirects_file() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.aliases_as_redirects_file = true;
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/an-old-url/old-page https://replace-this-with-your-url.com/posts/something-else/ 301"
+    ));
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/an-old-url/an-old-alias.html https://replace-this-with-your-url.com/posts/something-else/ 301"
+    ));
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/top-level.html https://replace-this-with-your-url.com/posts/top-level-alias/ 301"
+    ));
+    assert!(file_contains!(
+        public,
+        "_redirects",
+        "/another-old-url/index.html https://replace-this-with-your-url.com/posts/ 301"
+    ));
+    // No HTML redirect pages are written
+    assert!(!file_exists!(public, "an-old-url/old-page/index.html"));
+    assert!(!file_exists!(public, "top-level.html"));
+}
+
+#[test]
+fn can_set_sitemap_priority_and_changefreq() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.sitemap.priority = Some(0.5);
+        site.config.sitemap.changefreq = Some(ChangeFreq::Monthly);
+        (site, true)
+    });
+    let sitemap = fs::read_to_string(public.join("sitemap.xml")).unwrap();
+    let entry = |permalink: &str| {
+        let start = sitemap.find(&format!("<loc>{}</loc>", permalink)).unwrap();
+        let end = start + sitemap[start..].find("</url>").unwrap();
+        sitemap[start..end].to_string()
+    };
+
+    let page = entry("https://replace-this-with-your-url.com/posts/simple/");
+    assert!(page.contains("<priority>0.8</priority>"));
+    assert!(page.contains("<changefreq>monthly</changefreq>"));
+    let other = entry("https://replace-this-with-your-url.com/posts/python/");
+    assert!(other.contains("<priority>0.5</priority>"));
+
+    // Nothing is added without defaults in the config
+    let (_, _tmp_dir, public) = build_site("test_site");
+    let sitemap = fs::read_to_string(public.join("sitemap.xml")).unwrap();
+    assert!(!sitemap.contains("<changefreq>"));
+    assert_eq!(sitemap.matches("<priority>").count(), 1);
+}
+
+#[test]
+fn can_split_sitemap_with_same_entries() {
+    fn locs(content: &str) -> Vec<String> {
+        content
+            .split("<loc>")
+            .skip(1)
+            .map(|s| s.split("</loc>").next().unwrap().to_string())
+            .collect()
+    }
+
+    let (_, _tmp_dir, public) = build_site("test_site");
+    let single = locs(&fs::read_to_string(public.join("sitemap.xml")).unwrap());
+
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.sitemap.max_entries = 10;
+        (site, true)
+    });
+    let index = fs::read_to_string(public.join("sitemap.xml")).unwrap();
+    let number_sitemaps = locs(&index).len();
+    assert_eq!(number_sitemaps, (single.len() + 9) / 10);
+
+    let mut split = vec![];
+    for i in 1..=number_sitemaps {
+        let content = fs::read_to_string(public.join(format!("sitemap{}.xml", i))).unwrap();
+        assert!(locs(&content).len() <= 10);
+        split.extend(locs(&content));
+    }
+    assert!(!public.join(format!("sitemap{}.xml", number_sitemaps + 1)).exists());
+    assert_eq!(split, single);
+}
+
+#[test]
+fn can_report_build_status_in_serve_mode() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.enable_serve_mode();
+    site.load().unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    site.set_output_path(&tmp_dir.path().join("public"));
+    site.build().unwrap();
+
+    let content = site.content_map.read().unwrap();
+    let status = content.get(RelativePath::new("__zola/status.json")).unwrap();
+    let status: serde_json::Value = serde_json::from_str(status).unwrap();
+    assert_eq!(status["success"], true);
+    assert_eq!(status["errors"], 0);
+    assert!(status["error"].is_null());
+    assert!(status["timestamp"].is_string());
+}
+
+#[test]
+fn can_resolve_static_files_without_copying_them_in_serve_mode() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.enable_serve_mode();
+    site.load().unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = tmp_dir.path().join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+
+    let static_files = site.static_resolver();
+    assert_eq!(static_files.resolve("/site.css"), Some(path.join("static").join("site.css")));
+    assert_eq!(
+        static_files.resolve("/some.js"),
+        Some(path.join("themes").join("sample").join("static").join("some.js"))
+    );
+    assert_eq!(static_files.resolve("/nope.css"), None);
+    assert_eq!(static_files.resolve("/../config.toml"), None);
+    assert!(!public.join("site.css").exists());
+    assert!(!public.join("some.js").exists());
+}
+
+#[test]
+fn can_report_all_broken_internal_links() {
+    let (mut site, _tmp_dir, _public) = build_site("test_site");
+    let content = r#"+++
+title = "Broken links"
++++
+A [valid link](@/posts/simple.md), a [broken one](@/posts/nope.md)
+and [another one](@/nothing/here.md#anchor).
+"#;
+    let page = Page::parse(
+        &site.base_path.join("content").join("posts").join("broken-links.md"),
+        content,
+        &site.config,
+        &site.base_path,
+    )
+    .unwrap();
+    site.add_page(page, false).unwrap();
+
+    let err = link_checking::check_internal_links(&site).unwrap_err().to_string();
+    assert!(err.contains("@/posts/nope.md"));
+    assert!(err.contains("@/nothing/here.md#anchor"));
+    assert!(!err.contains("@/posts/simple.md"));
+}
+
+#[test]
+fn can_link_to_deduplicated_heading_anchors() {
+    let (mut site, _tmp_dir, _public) = build_site("test_site");
+    let add_page = |site: &mut Site, filename: &str, content: &str| {
+        let page = Page::parse(
+            &site.base_path.join("content").join("posts").join(filename),
+            content,
+            &site.config,
+            &site.base_path,
+        )
+        .unwrap();
+        site.add_page(page, true).unwrap();
+    };
+    add_page(
+        &mut site,
+        "headings.md",
+        "+++\ntitle = \"Headings\"\n+++\n# Introduction\n# Introduction\n",
+    );
+    let headings = site.base_path.join("content").join("posts").join("headings.md");
+    let ids: Vec<_> = site
+        .library
+        .read()
+        .unwrap()
+        .get_page(&headings)
+        .unwrap()
+        .toc
+        .iter()
+        .map(|h| h.id.clone())
+        .collect();
+    assert_eq!(ids, vec!["introduction", "introduction-1"]);
+
+    add_page(
+        &mut site,
+        "links.md",
+        "+++\ntitle = \"Links\"\n+++\n[second](@/posts/headings.md#introduction-1)\n",
+    );
+    assert!(link_checking::check_internal_links_with_anchors(&site).is_ok());
+
+    add_page(
+        &mut site,
+        "broken-links.md",
+        "+++\ntitle = \"Broken\"\n+++\n[third](@/posts/headings.md#introduction-2)\n",
+    );
+    let err = link_checking::check_internal_links_with_anchors(&site).unwrap_err().to_string();
+    assert!(err.contains("introduction-2"));
+}
+
+#[test]
+fn can_render_feeds_for_all_sections() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed.all_sections = true;
+        (site, true)
+    });
+
+    // devops doesn't have `generate_feed = true`
+    assert!(file_exists!(public, "posts/tutorials/devops/atom.xml"));
+    assert!(file_contains!(public, "posts/tutorials/devops/atom.xml", "Nix"));
+    // sections opting in still get their feed
+    assert!(file_exists!(public, "posts/tutorials/programming/atom.xml"));
+}
+
+#[test]
+fn doesnt_render_feeds_with_too_few_items() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed.all_sections = true;
+        site.config.feed.min_items = 3;
+        (site, true)
+    });
+
+    // Both sections only have 2 dated pages
+    assert!(!file_exists!(public, "posts/tutorials/devops/atom.xml"));
+    assert!(!file_exists!(public, "posts/tutorials/programming/atom.xml"));
+    assert!(file_exists!(public, "atom.xml"));
+}
+
+#[test]
+fn can_configure_livereload_url() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.enable_live_reload_with_port(35729);
+        site.config.serve.livereload_url = Some("http://192.168.1.10:1111/".to_string());
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "index.html",
+        r#"<script src="http://192.168.1.10:1111/livereload.js?port=35729&amp;mindelay=10">"#
+    ));
+}
+
+#[test]
+fn can_validate_front_matter_against_section_schema() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    let schema = config::Schema { required: vec!["description".to_string()], ..Default::default() };
+    site.config.schema.insert("posts/tutorials/devops".to_string(), schema);
+
+    let err = site.load().unwrap_err().to_string();
+    assert!(err.contains("missing required field `description`"));
+    assert!(err.contains("nix.md"));
+    assert!(err.contains("docker.md"));
+}
+
+#[test]
+fn can_preserve_files_when_cleaning() {
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.config.preserve_on_clean = vec!["CNAME".to_string(), ".git/".to_string()];
+    site.load().unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let public = &tmp_dir.path().join("public");
+    fs::create_dir_all(public.join(".git")).unwrap();
+    fs::write(public.join(".git").join("HEAD"), "ref: refs/heads/main").unwrap();
+    fs::write(public.join("CNAME"), "example.com").unwrap();
+    fs::write(public.join("stale.html"), "old").unwrap();
+    site.set_output_path(&public);
+    site.build().expect("Couldn't build the site");
+
+    assert!(file_exists!(public, "CNAME"));
+    assert!(file_exists!(public, ".git/HEAD"));
+    assert!(!file_exists!(public, "stale.html"));
+    assert!(file_exists!(public, "index.html"));
+}
+
+#[test]
+fn can_generate_headers_file_with_preloads() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_headers = true;
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "_headers"));
+    assert!(file_contains!(public, "_headers", "/\n  Link: </site.css?h="));
+    assert!(file_contains!(
+        public,
+        "_headers",
+        "  Link: </scripts/hello.js>; rel=preload; as=script"
+    ));
+    // Stylesheets from other origins are not preloaded
+    assert!(!file_contains!(public, "_headers", "fonts.googleapis.com"));
+}
+
+#[test]
+fn can_generate_headers_file_with_preloads_of_minified_html() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.generate_headers = true;
+        site.config.minify_html = true;
+        (site, true)
+    });
+
+    // The minifier removes the quotes around the attribute values
+    assert!(file_contains!(
+        public,
+        "index.html",
+        "href=https://replace-this-with-your-url.com/site.css?h="
+    ));
+    assert!(file_contains!(public, "_headers", "/\n  Link: </site.css?h="));
+    assert!(file_contains!(
+        public,
+        "_headers",
+        "  Link: </scripts/hello.js>; rel=preload; as=script"
+    ));
+}
+
+#[test]
+fn can_use_build_hash_in_templates() {
+    let (mut site, _tmp_dir, _public) = build_site("test_site");
+    assert!(!site.build_hash.is_empty());
+    assert!(!site.build_time.is_empty());
+
+    let rendered = site.tera.render_str("{{ build_hash() }}", &tera::Context::new()).unwrap();
+    assert_eq!(rendered, site.build_hash);
+    // Stable as long as the content doesn't change
+    assert_eq!(site.compute_build_hash(), site.build_hash);
+
+    let page = Page::parse(
+        &site.base_path.join("content").join("new-page.md"),
+        "+++\ntitle = \"New\"\n+++\nNew content",
+        &site.config,
+        &site.base_path,
+    )
+    .unwrap();
+    site.add_page(page, false).unwrap();
+    assert_ne!(site.compute_build_hash(), site.build_hash);
+}
+
+#[test]
+fn can_use_build_hash_in_shortcodes() {
+    let (site, tmp_dir, _public) = load_tmp_site(
+        "",
+        &[
+            ("templates/shortcodes/built.html", "Built from {{ build_hash() }}"),
+            ("content/hello.md", "+++\n+++\n{{ built() }}"),
+        ],
+    );
+    let library = site.library.read().unwrap();
+    let page = library.get_page(&tmp_dir.path().join("content").join("hello.md")).unwrap();
+    assert!(page.content.contains(&format!("Built from {}", site.build_hash)));
+}
+
+#[test]
+fn can_render_humans_txt_from_template() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+    assert!(file_exists!(public, "humans.txt"));
+    assert!(file_contains!(public, "humans.txt", "Author: Vincent Prouillet"));
+
+    // No built-in template so nothing is written without one
+    let (_, _tmp_dir, public) = build_site("test_site_i18n");
+    assert!(!file_exists!(public, "humans.txt"));
+}
+
+#[test]
+fn can_use_reading_time_in_feeds() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.feed_filename = "reading_time.xml".to_string();
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "reading_time.xml"));
+    assert!(file_contains!(public, "reading_time.xml", "Nix: 3 words, 1 min"));
+}
+
+#[test]
+fn can_compute_stable_etags() {
+    let (first, _tmp_dir, _) = build_site("test_site");
+    let (second, _tmp_dir, _) = build_site("test_site");
+    let etags = first.etags();
+    assert!(!etags.is_empty());
+    assert_eq!(etags, second.etags());
+
+    let index_etag = &etags[RelativePath::new("index.html")];
+    assert!(index_etag.starts_with('"') && index_etag.ends_with('"'));
+    assert_eq!(index_etag.len(), 66);
+
+    let (changed, _tmp_dir, _) = build_site_with_setup("test_site", |mut site| {
+        site.config.extra_body_end = Some("<p>Added</p>".to_string());
+        (site, true)
+    });
+    let changed_etags = changed.etags();
+    assert_ne!(&changed_etags[RelativePath::new("index.html")], index_etag);
+    assert_eq!(
+        changed_etags[RelativePath::new("robots.txt")],
+        etags[RelativePath::new("robots.txt")]
+    );
+}
+
+#[test]
+fn can_rebuild_only_taxonomies() {
+    let (mut site, _tmp_dir, public) = build_site("test_site");
+    fs::remove_file(public.join("categories").join("a-category").join("index.html")).unwrap();
+    fs::remove_file(public.join("categories").join("a-category").join("atom.xml")).unwrap();
+    fs::remove_file(public.join("posts").join("simple").join("index.html")).unwrap();
+
+    site.rebuild_taxonomies().unwrap();
+
+    assert!(file_exists!(public, "categories/index.html"));
+    assert!(file_exists!(public, "categories/a-category/index.html"));
+    assert!(file_exists!(public, "categories/a-category/atom.xml"));
+    assert!(!file_exists!(public, "posts/simple/index.html"));
+}
+
+#[test]
+fn can_build_site_into_archive() {
+    fn read_all_files(dir: &Path) -> Vec<(PathBuf, Vec<u8>)> {
+        let mut files: Vec<_> = walkdir::WalkDir::new(dir)
+            .into_iter()
+            .map(|e| e.unwrap())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| {
+                (e.path().strip_prefix(dir).unwrap().to_path_buf(), fs::read(e.path()).unwrap())
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    let (_, _tmp_dir, public) = build_site("test_site");
+
+    let mut path = env::current_dir().unwrap().parent().unwrap().parent().unwrap().to_path_buf();
+    path.push("test_site");
+    let config_file = path.join("config.toml");
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.load().unwrap();
+    let tmp_dir = tempdir().expect("create temp dir");
+    let target = tmp_dir.path().join("site.tar.gz");
+    site.build_archive(&target).unwrap();
+
+    let extracted = tmp_dir.path().join("extracted");
+    let mut archive = tar::Archive::new(GzDecoder::new(fs::File::open(&target).unwrap()));
+    archive.unpack(&extracted).unwrap();
+    assert_eq!(read_all_files(&extracted), read_all_files(&public));
+
+    assert!(site.build_archive(&tmp_dir.path().join("site.zip")).is_err());
+}
+
+#[test]
+fn can_order_same_dated_pages_in_feeds() {
+    let tmp_dir = create_tmp_site(
+        "generate_feed = true\n",
+        &[
+            ("content/c.md", "+++\ntitle = \"Alpha\"\ndate = 2020-01-01\n+++\n"),
+            ("content/a.md", "+++\ntitle = \"Charlie\"\ndate = 2020-01-01\n+++\n"),
+            ("content/b.md", "+++\ntitle = \"Bravo\"\ndate = 2020-01-01\n+++\n"),
+            ("templates/index.html", "Index"),
+            ("templates/page.html", "Page"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let titles_in_feed = |sort_ties_by: SortTiesBy| {
+        let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+        site.config.feed.sort_ties_by = sort_ties_by;
+        site.load().unwrap();
+        let public = path.join("public");
+        site.set_output_path(&public);
+        site.build().unwrap();
+        let feed = fs::read_to_string(public.join("atom.xml")).unwrap();
+        feed.split("<title>")
+            .skip(2)
+            .map(|s| s.split('<').next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let by_permalink = titles_in_feed(SortTiesBy::Permalink);
+    assert_eq!(by_permalink, vec!["Charlie", "Bravo", "Alpha"]);
+    for _ in 0..3 {
+        assert_eq!(titles_in_feed(SortTiesBy::Permalink), by_permalink);
+    }
+    assert_eq!(titles_in_feed(SortTiesBy::Title), vec!["Alpha", "Bravo", "Charlie"]);
+}
+
+#[test]
+fn can_list_pages_in_404_when_enabled() {
+    let tmp_dir = create_tmp_site(
+        "",
+        &[
+            ("content/hello.md", "+++\ntitle = \"Hello\"\n+++\n"),
+            ("content/world.md", "+++\ntitle = \"World\"\n+++\n"),
+            ("templates/index.html", "Index"),
+            ("templates/page.html", "Page"),
+            (
+                "templates/404.html",
+                "{% if pages %}{% for p in pages %}[{{ p.title }} {{ p.permalink }}]{% endfor %}{% else %}No pages{% endif %}",
+            ),
+        ],
+    );
+    let path = tmp_dir.path();
+
+    let build = |pages_in_404: bool| {
+        let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+        site.config.pages_in_404 = pages_in_404;
+        site.load().unwrap();
+        let public = path.join("public");
+        site.set_output_path(&public);
+        site.build().unwrap();
+        fs::read_to_string(public.join("404.html")).unwrap()
+    };
+
+    assert_eq!(build(false), "No pages");
+    assert_eq!(build(true), "[Hello https://example.com/hello/][World https://example.com/world/]");
+}
+
+#[test]
+fn can_cascade_section_front_matter_to_pages() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            (
+                "content/blog/_index.md",
+                "+++\n[cascade]\ntemplate = \"post.html\"\n[cascade.extra]\nauthor = \"Blog\"\n+++\n",
+            ),
+            ("content/blog/nested/_index.md", "+++\n[cascade.extra]\nauthor = \"Nested\"\n+++\n"),
+            ("content/blog/default.md", "+++\n+++\n"),
+            (
+                "content/blog/explicit.md",
+                "+++\ntemplate = \"page.html\"\n[extra]\nauthor = \"Me\"\n+++\n",
+            ),
+            ("content/blog/nested/deep.md", "+++\n+++\n"),
+            // The closest section decides the template, whichever way it sets it
+            ("content/blog/docs/_index.md", "+++\npage_template = \"doc.html\"\n+++\n"),
+            ("content/blog/docs/guide.md", "+++\n+++\n"),
+            ("content/docs/_index.md", "+++\npage_template = \"doc.html\"\n+++\n"),
+            (
+                "content/docs/posts/_index.md",
+                "+++\n[cascade]\ntemplate = \"post.html\"\n[cascade.extra]\nauthor = \"Docs\"\n+++\n",
+            ),
+            ("content/docs/posts/first.md", "+++\n+++\n"),
+            ("templates/index.html", "Index"),
+            ("templates/section.html", "Section"),
+            ("templates/page.html", "Page by {{ page.extra.author }}"),
+            ("templates/post.html", "Post by {{ page.extra.author }}"),
+            ("templates/doc.html", "Doc"),
+        ],
+    );
+
+    assert!(file_contains!(public, "blog/default/index.html", "Post by Blog"));
+    assert!(file_contains!(public, "blog/explicit/index.html", "Page by Me"));
+    assert!(file_contains!(public, "blog/nested/deep/index.html", "Post by Nested"));
+    assert!(file_contains!(public, "blog/docs/guide/index.html", "Doc"));
+    assert!(file_contains!(public, "docs/posts/first/index.html", "Post by Docs"));
+}
+
+#[test]
+fn can_render_amp_versions_of_pages() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            ("content/hello.md", "+++\ntitle = \"Hello\"\namp = true\n+++\n"),
+            ("content/plain.md", "+++\ntitle = \"Plain\"\n+++\n"),
+            ("templates/index.html", "Index"),
+            (
+                "templates/page.html",
+                "{% if page.amp_permalink %}<link rel=\"amphtml\" href=\"{{ page.amp_permalink }}\">{% endif %}{{ page.title }}",
+            ),
+            (
+                "templates/amp.html",
+                "<html amp><link rel=\"canonical\" href=\"{{ canonical_url }}\">AMP {{ page.title }}</html>",
+            ),
+        ],
+    );
+
+    assert!(file_contains!(
+        public,
+        "hello/index.html",
+        "<link rel=\"amphtml\" href=\"https://example.com/hello/amp/\">"
+    ));
+    assert!(file_contains!(public, "hello/amp/index.html", "AMP Hello"));
+    assert!(file_contains!(
+        public,
+        "hello/amp/index.html",
+        "<link rel=\"canonical\" href=\"https://example.com/hello/\">"
+    ));
+    assert!(!file_contains!(public, "plain/index.html", "amphtml"));
+    assert!(!file_exists!(public, "plain/amp/index.html"));
+}
+
+#[test]
+fn can_generate_feed_of_section_not_rendered() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            ("content/blog/_index.md", "+++\nrender = false\ngenerate_feed = true\n+++\n"),
+            ("content/blog/post.md", "+++\ntitle = \"Post\"\ndate = 2020-01-01\n+++\n"),
+            ("templates/index.html", "Index"),
+            ("templates/section.html", "Section"),
+            ("templates/page.html", "{{ page.title }}"),
+        ],
+    );
+
+    assert!(file_contains!(public, "blog/atom.xml", "https://example.com/blog/post/"));
+    assert!(file_exists!(public, "blog/post/index.html"));
+    assert!(!file_exists!(public, "blog/index.html"));
+}
+
+#[test]
+fn can_warn_about_dead_external_links_in_build_only() {
+    let (site, tmp_dir, _public) = load_tmp_site(
+        "[link_checker]\nexternal_level = \"warn\"\n",
+        &[
+            ("content/page.md", "+++\n+++\n[Dead](http://127.0.0.1:1/dead-link)\n"),
+            ("templates/index.html", "Index"),
+            ("templates/page.html", "{{ page.content | safe }}"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let warnings = site.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].path, Some(path.join("content").join("page.md")));
+    assert!(warnings[0].message.starts_with("Dead link to http://127.0.0.1:1/dead-link"));
+
+    let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+    site.config.enable_check_mode();
+    let err = site.load().unwrap_err();
+    assert!(format!("{}", err).contains("http://127.0.0.1:1/dead-link"));
+}
+
+#[test]
+fn can_build_site_with_a_single_thread() {
+    let (_, _tmp_dir, public) = build_site("test_site");
+    let (_, _single_tmp_dir, single_public) = build_site_with_setup("test_site", |mut site| {
+        site.set_threads(Some(1)).unwrap();
+        (site, true)
+    });
+
+    let list_files = |root: &Path| {
+        let mut files: Vec<_> = walkdir::WalkDir::new(root)
+            .into_iter()
+            .map(|e| e.unwrap().path().strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        files.sort();
+        files
+    };
+    assert_eq!(list_files(&single_public), list_files(&public));
+    assert!(file_contains!(single_public, "posts/simple/index.html", "A simple page"));
+}
+
+#[test]
+fn can_build_extra_feed_for_several_taxonomy_terms() {
+    let page = |tags: &str| {
+        format!("+++\ntitle = \"Page\"\ndate = 2020-01-01\n[taxonomies]\ntags = {}\n+++\n", tags)
+    };
+    let (_, _tmp_dir, public) = build_tmp_site(
+        r#"taxonomies = [{ name = "tags" }]
+
+[[extra_feeds]]
+path = "rust-or-go"
+taxonomy_terms = [{ taxonomy = "tags", term = "rust" }, { taxonomy = "tags", term = "go" }]
+
+[[extra_feeds]]
+path = "rust-and-go"
+taxonomy_terms = [{ taxonomy = "tags", term = "rust" }, { taxonomy = "tags", term = "go" }]
+taxonomy_terms_match = "all"
+"#,
+        &[
+            ("content/rust.md", &page("[\"rust\"]")),
+            ("content/go.md", &page("[\"go\"]")),
+            ("content/both.md", &page("[\"rust\", \"go\"]")),
+            ("content/python.md", &page("[\"python\"]")),
+            ("templates/index.html", "Index"),
+            ("templates/page.html", "{{ page.title }}"),
+            ("templates/tags/list.html", "Tags"),
+            ("templates/tags/single.html", "Tag"),
+        ],
+    );
+
+    let mut feed = String::new();
+    fs::File::open(public.join("rust-or-go").join("atom.xml"))
+        .unwrap()
+        .read_to_string(&mut feed)
+        .unwrap();
+    for slug in &["rust", "go", "both"] {
+        let link = format!("https://example.com/{}/", slug);
+        assert_eq!(feed.matches(&format!("<id>{}</id>", link)).count(), 1);
+    }
+    assert!(!feed.contains("https://example.com/python/"));
+
+    assert!(file_contains!(public, "rust-and-go/atom.xml", "https://example.com/both/"));
+    assert!(!file_contains!(public, "rust-and-go/atom.xml", "https://example.com/rust/"));
+    assert!(!file_contains!(public, "rust-and-go/atom.xml", "https://example.com/go/"));
+}
+
+#[test]
+fn can_disable_automatic_index_section() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "auto_index_section = false\n",
+        &[
+            ("content/api/_index.md", "+++\n+++\n"),
+            ("content/api/users.md", "+++\n+++\n"),
+            ("templates/index.html", "Index"),
+            ("templates/section.html", "Section"),
+            ("templates/page.html", "Page"),
+        ],
+    );
+
+    assert!(!file_exists!(public, "index.html"));
+    assert!(file_exists!(public, "api/index.html"));
+    assert!(file_exists!(public, "api/users/index.html"));
+}
+
+#[test]
+fn can_normalize_line_endings_of_rendered_files() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "normalize_line_endings = \"lf\"\n",
+        &[
+            ("content/_index.md", "+++\r\n+++\r\nHello\r\n"),
+            ("templates/index.html", "<p>\r\n{{ section.content }}</p>\r\n"),
+        ],
+    );
+
+    let index = fs::read_to_string(public.join("index.html")).unwrap();
+    assert!(index.contains("<p>\n"));
+    assert!(!index.contains('\r'));
+}
+
+#[test]
+fn can_build_a_preview_of_the_drafts() {
+    let (mut site, tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.include_drafts();
+        (site, true)
+    });
+    let preview = tmp_dir.path().join("preview");
+    site.build_drafts_preview(&preview).unwrap();
+
+    assert!(file_exists!(preview, "posts/draft/index.html"));
+    // With the sections leading to it
+    assert!(file_exists!(preview, "index.html"));
+    assert!(file_exists!(preview, "posts/index.html"));
+    // But nothing else
+    assert!(!file_exists!(preview, "posts/python/index.html"));
+    assert!(!file_exists!(preview, "posts/tutorials/index.html"));
+    assert!(!file_exists!(preview, "sitemap.xml"));
+    assert!(!file_exists!(preview, "site.css"));
+    // The usual output is still there
+    assert!(file_exists!(public, "posts/python/index.html"));
+}
+
+#[test]
+fn can_include_every_page_in_feed_with_a_limit_of_zero() {
+    let pages: Vec<_> = (0..200)
+        .map(|i| {
+            (
+                format!("content/page-{}.md", i),
+                format!("+++\ntitle = \"Page {}\"\ndate = 2020-01-01\n+++\n", i),
+            )
+        })
+        .collect();
+    let mut files: Vec<_> = pages.iter().map(|(p, c)| (p.as_str(), c.as_str())).collect();
+    files.push(("templates/index.html", "Index"));
+    files.push(("templates/page.html", "Page"));
+    let (_, _tmp_dir, public) = build_tmp_site("generate_feed = true\nfeed_limit = 0\n", &files);
+
+    let feed = fs::read_to_string(public.join("atom.xml")).unwrap();
+    assert_eq!(feed.matches("<entry").count(), 200);
+}
+
+#[test]
+fn can_preprocess_the_content_of_pages() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.add_content_preprocessor(|content, page| {
+            content.replace("Same filename", &format!("Preprocessed {}", page.file.name))
+        });
+        (site, true)
+    });
+
+    assert!(file_contains!(
+        public,
+        "posts/python/index.html",
+        "Preprocessed python but different path"
+    ));
+    assert!(!file_contains!(public, "posts/python/index.html", "Same filename"));
+}
+
+#[test]
+fn can_postprocess_the_html_of_pages_and_sections() {
+    let tmp_dir = create_tmp_site(
+        "",
+        &[
+            ("content/_index.md", "+++\n+++\n"),
+            ("content/hello.md", "+++\n+++\n![A cat](cat.jpg)\n"),
+            ("templates/index.html", "<img src=\"logo.png\">"),
+            ("templates/page.html", "{{ page.content | safe }}"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let config_file = path.join("config.toml");
+
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.add_html_postprocessor(|html, _| Ok(html.replace("<img ", "<img loading=\"lazy\" ")));
+    site.add_html_postprocessor(|html, page| match page {
+        Some(page) => Ok(format!("{}<!-- {} -->", html, page.file.name)),
+        None => Ok(html),
+    });
+    site.load().unwrap();
+    let public = path.join("public");
+    site.set_output_path(&public);
+    site.build().unwrap();
+
+    assert!(file_contains!(
+        public,
+        "hello/index.html",
+        r#"<img loading="lazy" src="cat.jpg" alt="A cat" />"#
+    ));
+    assert!(file_contains!(public, "hello/index.html", "<!-- hello -->"));
+    assert!(file_contains!(public, "index.html", r#"<img loading="lazy" src="logo.png">"#));
+
+    // An error of a postprocessor fails the build
+    let mut site = Site::new(&path, &config_file).unwrap();
+    site.add_html_postprocessor(|_, _| Err("Nope".into()));
+    site.load().unwrap();
+    site.set_output_path(&public);
+    assert!(site.build().is_err());
+}
+
+static RENDERED_PAGES: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn can_reuse_the_rendered_content_of_unchanged_pages() {
+    let tmp_dir = create_tmp_site(
+        "",
+        &[
+            ("content/a.md", "+++\n+++\n# Hello\n"),
+            ("content/b.md", "+++\n+++\n[Hello](@/a.md#hello)\n"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let load = || {
+        let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+        // Only called when the markdown of a page is rendered
+        site.add_content_preprocessor(|content, _| {
+            RENDERED_PAGES.fetch_add(1, Ordering::SeqCst);
+            content.to_string()
+        });
+        site.use_render_cache();
+        site.load().map(|_| site)
+    };
+
+    load().unwrap();
+    assert_eq!(RENDERED_PAGES.load(Ordering::SeqCst), 2);
+
+    // Nothing changed so nothing is rendered and the links are still checked
+    let site = load().unwrap();
+    assert_eq!(RENDERED_PAGES.load(Ordering::SeqCst), 2);
+    {
+        let library = site.library.read().unwrap();
+        let page = library.get_page(&path.join("content").join("b.md")).unwrap();
+        assert!(page.content.contains("https://example.com/a/#hello"));
+        assert_eq!(
+            page.internal_links_with_anchors,
+            vec![("a.md".to_string(), "hello".to_string())]
+        );
+    }
+
+    // Only the page that changed is rendered again, and the anchor it lost is caught
+    fs::write(path.join("content").join("a.md"), "+++\n+++\n# Bye\n").unwrap();
+    assert!(load().is_err());
+    assert_eq!(RENDERED_PAGES.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn can_error_on_terms_with_the_same_slug() {
+    let config = "taxonomies = [{ name = \"tags\" }]\n";
+    // A warning by default
+    let (mut site, tmp_dir, _public) = load_tmp_site(
+        config,
+        &[
+            ("content/a.md", "+++\n[taxonomies]\ntags = [\"Rust\"]\n+++\n"),
+            ("content/b.md", "+++\n[taxonomies]\ntags = [\"rust\"]\n+++\n"),
+        ],
+    );
+    let collisions = |site: &Site| {
+        site.warnings().iter().filter(|w| w.message.contains("`Rust`, `rust`")).count()
+    };
+    assert_eq!(collisions(&site), 1);
+    // Populating the taxonomies again, like `zola serve` does, doesn't duplicate the warning
+    site.populate_taxonomies().unwrap();
+    assert_eq!(collisions(&site), 1);
+
+    let path = tmp_dir.path();
+    let config_file = path.join("config.toml");
+    fs::write(
+        &config_file,
+        format!("base_url = \"https://example.com\"\nterm_slug_collisions = \"error\"\n{}", config),
+    )
+    .unwrap();
+    let mut site = Site::new(&path, &config_file).unwrap();
+    let err = site.load().unwrap_err();
+    assert!(err.to_string().contains("have the same slug `rust`"));
+}
+
+#[test]
+fn can_render_opensearch_description() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site", |mut site| {
+        site.config.search.opensearch = true;
+        site.config.search.opensearch_url = Some(
+            "https://replace-this-with-your-url.com/search?q={searchTerms}&lang=en".to_string(),
+        );
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "opensearch.xml"));
+    assert!(file_contains!(
+        public,
+        "opensearch.xml",
+        r#"template="https://replace-this-with-your-url.com/search?q={searchTerms}&amp;lang=en""#
+    ));
+    assert!(file_contains!(
+        public,
+        "opensearch.xml",
+        r#"template="https://replace-this-with-your-url.com/opensearch.xml""#
+    ));
+}
+
+#[test]
+fn can_bundle_css_files() {
+    let template = r#"<link rel="stylesheet" href="{{ bundle(paths=["a.css", "b.css"], type="css") | safe }}">"#;
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            ("static/a.css", "a { color: red; }\n"),
+            ("static/b.css", "b { color: blue; }\n"),
+            ("templates/index.html", template),
+            ("templates/section.html", template),
+            ("content/_index.md", "+++\n+++\n"),
+            ("content/blog/_index.md", "+++\n+++\n"),
+        ],
+    );
+
+    // Both sections use the same bundle
+    let bundles: Vec<_> =
+        fs::read_dir(public.join("bundles")).unwrap().map(|e| e.unwrap()).collect();
+    assert_eq!(bundles.len(), 1);
+    let filename = bundles[0].file_name().to_string_lossy().to_string();
+    assert!(filename.ends_with(".css"));
+    assert_eq!(
+        fs::read_to_string(bundles[0].path()).unwrap(),
+        "a { color: red; }\nb { color: blue; }\n"
+    );
+    let link = format!("https://example.com/bundles/{}", filename);
+    assert!(file_contains!(public, "index.html", &link));
+    assert!(file_contains!(public, "blog/index.html", &link));
+}
+
+#[test]
+fn can_write_diagnostics_as_json_lines() {
+    let tmp_dir = create_tmp_site(
+        "taxonomies = [{ name = \"tags\" }]\n",
+        &[
+            ("content/a.md", "+++\n[taxonomies]\ntags = [\"Rust\"]\n+++\n"),
+            ("content/b.md", "+++\n[taxonomies]\ntags = [\"rust\"]\n+++\n"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let diagnostics = path.join("diagnostics.jsonl");
+
+    let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+    site.set_diagnostics_output(&diagnostics).unwrap();
+    site.load().unwrap();
+    site.set_output_path(&path.join("public"));
+    site.build().unwrap();
+
+    let content = fs::read_to_string(&diagnostics).unwrap();
+    let lines: Vec<serde_json::Value> =
+        content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let collision = lines
+        .iter()
+        .find(|l| l["message"].as_str().unwrap().contains("`Rust`, `rust`"))
+        .expect("a diagnostic for the term slug collision");
+    assert_eq!(collision["phase"], "load");
+    assert_eq!(collision["severity"], "warning");
+    assert!(collision.get("path").is_some());
+}
+
+#[test]
+fn can_build_with_a_template_variant() {
+    let (mut site, tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            ("content/hello.md", "+++\n+++\n"),
+            ("templates/index.html", "Index A"),
+            ("templates/page.html", "Page A"),
+            ("templates/variant-b/page.html", "Page B"),
+        ],
+    );
+
+    let variant = tmp_dir.path().join("public-variant-b");
+    site.set_template_variant(Some("variant-b".to_string()));
+    site.set_output_path(&variant);
+    site.build().unwrap();
+
+    assert!(file_contains!(public, "hello/index.html", "Page A"));
+    assert!(file_contains!(variant, "hello/index.html", "Page B"));
+    // Templates without a variant are used as they are
+    assert!(file_contains!(variant, "index.html", "Index A"));
+}
+
+#[test]
+fn can_prune_images_not_used_anymore() {
+    let tmp_dir = create_tmp_site(
+        "",
+        &[
+            (
+                "templates/shortcodes/thumb.html",
+                "{{ resize_image(path=\"zola.png\", width=width, height=10, op=\"scale\") }}",
+            ),
+            ("templates/page.html", "{{ page.content | safe }}"),
+            // Only requested when rendering the templates, which `load` doesn't do
+            (
+                "templates/index.html",
+                "{{ resize_image(path=\"zola.png\", width=30, height=10, op=\"scale\") }}",
+            ),
+            ("content/a.md", "+++\n+++\n{{ thumb(width=10) }}"),
+            ("content/b.md", "+++\n+++\n{{ thumb(width=20) }}"),
+        ],
+    );
+    let path = tmp_dir.path();
+    let test_site =
+        env::current_dir().unwrap().parent().unwrap().parent().unwrap().join("test_site");
+    fs::copy(
+        test_site.join("content").join("posts").join("with-assets").join("zola.png"),
+        path.join("content").join("zola.png"),
+    )
+    .unwrap();
+
+    let mut site = Site::new(&path, &path.join("config.toml")).unwrap();
+    site.load().unwrap();
+    site.set_output_path(path.join("public"));
+    site.build().unwrap();
+    let processed = path.join("static").join("processed_images");
+    assert_eq!(fs::read_dir(&processed).unwrap().count(), 3);
+
+    fs::write(path.join("content").join("b.md"), "+++\n+++\nNo image anymore").unwrap();
+    site.load().unwrap();
+    site.prune_images().unwrap();
+    assert_eq!(fs::read_dir(&processed).unwrap().count(), 2);
+
+    fs::write(path.join("templates").join("index.html"), "No image anymore").unwrap();
+    site.load().unwrap();
+    site.reload_templates().unwrap();
+    assert_eq!(fs::read_dir(&processed).unwrap().count(), 1);
+}
+
+#[test]
+fn can_list_children_of_section_not_rendered() {
+    let (_, _tmp_dir, public) = build_tmp_site(
+        "",
+        &[
+            ("content/docs/_index.md", "+++\nrender = false\nauto_list = true\n+++\n"),
+            ("content/docs/guides/_index.md", "+++\ntitle = \"Guides\"\n+++\n"),
+            ("content/docs/install.md", "+++\ntitle = \"Install\"\n+++\n"),
+            ("templates/index.html", "Index"),
+            ("templates/section.html", "Section"),
+            ("templates/page.html", "Page"),
+        ],
+    );
+
+    assert!(file_contains!(
+        public,
+        "docs/index.html",
+        r#"<a href="https://example.com/docs/install/">Install</a>"#
+    ));
+    assert!(file_contains!(
+        public,
+        "docs/index.html",
+        r#"<a href="https://example.com/docs/guides/">Guides</a>"#
+    ));
+}
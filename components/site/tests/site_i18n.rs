@@ -1,8 +1,10 @@
 mod common;
 
+use std::collections::HashMap;
 use std::env;
+use std::fs;
 
-use common::build_site;
+use common::{build_site, build_site_with_setup, load_tmp_site};
 use site::Site;
 
 #[test]
@@ -174,3 +176,170 @@ fn can_build_multilingual_site() {
     assert!(file_exists!(public, "search_index.it.js"));
     assert!(!file_exists!(public, "search_index.fr.js"));
 }
+
+#[test]
+fn can_write_default_language_feed_in_subdir() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site_i18n", |mut site| {
+        site.config.feed.default_language_in_subdir = true;
+        (site, true)
+    });
+
+    assert!(file_exists!(public, "atom.xml"));
+    assert!(file_exists!(public, "en/atom.xml"));
+    assert!(file_contains!(public, "en/atom.xml", "https://example.com/blog/something-else/"));
+    assert!(!file_contains!(public, "en/atom.xml", "https://example.com/fr/blog/something-else/"));
+    assert!(file_exists!(public, "fr/atom.xml"));
+}
+
+#[test]
+fn can_use_language_base_url() {
+    let (site, _tmp_dir, public) = build_site_with_setup("test_site_i18n", |mut site| {
+        site.config.languages[0].base_url = Some("https://exemple.fr".to_string());
+        (site, true)
+    });
+
+    let library = site.library.read().unwrap();
+    for page in library.pages_values() {
+        if page.lang == "fr" {
+            assert!(page.permalink.starts_with("https://exemple.fr/fr/"));
+        } else {
+            assert!(page.permalink.starts_with("https://example.com/"));
+        }
+    }
+    let fr_blog = library
+        .get_section(&site.base_path.join("content").join("blog").join("_index.fr.md"))
+        .unwrap();
+    assert_eq!(fr_blog.permalink, "https://exemple.fr/fr/blog/");
+
+    assert!(file_contains!(public, "fr/atom.xml", "https://exemple.fr/fr/blog/something/"));
+    assert!(file_contains!(public, "sitemap.xml", "https://exemple.fr/fr/blog/something/"));
+    assert!(file_contains!(public, "sitemap.xml", "https://example.com/blog/something/"));
+}
+
+#[test]
+fn can_set_explicit_translations() {
+    let (_, _tmp_dir, public) = build_site_with_setup("test_site_i18n", |mut site| {
+        site.load().unwrap();
+        {
+            let mut library = site.library.write().unwrap();
+            let path = site.base_path.join("content").join("blog").join("not-translated.md");
+            let key = library.paths_to_pages[&path];
+            let page = library.get_page_mut_by_key(key);
+            let mut translations = HashMap::new();
+            translations.insert("fr".to_string(), "@/blog/something.fr.md".to_string());
+            page.meta.translations = translations;
+            library.populate_sections(&site.config);
+        }
+        (site, false)
+    });
+
+    assert!(file_contains!(
+        public,
+        "blog/not-translated/index.html",
+        "Translated in fr: Quelque chose https://example.com/fr/blog/something/"
+    ));
+    assert!(file_contains!(
+        public,
+        "fr/blog/something/index.html",
+        "https://example.com/blog/not-translated/"
+    ));
+    // The explicit translation replaces the one found from the filename
+    assert!(!file_contains!(
+        public,
+        "fr/blog/something/index.html",
+        "Translated in en: Something https://example.com/blog/something/"
+    ));
+    assert!(!file_contains!(public, "blog/something/index.html", "Quelque chose"));
+}
+
+#[test]
+fn can_build_site_with_language_content_dirs() {
+    let mut pages = vec![];
+    for dir in &["en", "fr"] {
+        pages.push((format!("content/{}/_index.md", dir), "+++\n+++\n".to_string()));
+        pages.push((format!("content/{}/blog/_index.md", dir), "+++\n+++\n".to_string()));
+        pages.push((
+            format!("content/{}/blog/hello.md", dir),
+            format!("+++\ntitle = \"{}\"\n+++\n", dir),
+        ));
+    }
+    let mut files: Vec<_> = pages.iter().map(|(p, c)| (p.as_str(), c.as_str())).collect();
+    files.push(("templates/index.html", "{{ section.permalink }}"));
+    files.push(("templates/section.html", "{{ section.permalink }}"));
+    files.push((
+        "templates/page.html",
+        "{{ page.permalink }}|{% for t in page.translations %}{{ t.permalink }} {% endfor %}",
+    ));
+    let (mut site, _tmp_dir, public) = load_tmp_site(
+        r#"languages = [{ code = "fr" }]
+
+[language_content_dirs]
+en = "en"
+fr = "fr"
+"#,
+        &files,
+    );
+    {
+        let library = site.library.read().unwrap();
+        assert_eq!(library.pages().len(), 2);
+        let fr_page = &library.pages_values().into_iter().find(|p| p.lang == "fr").unwrap();
+        assert_eq!(fr_page.permalink, "https://example.com/fr/blog/hello/");
+        assert_eq!(fr_page.ancestors.len(), 2);
+    }
+    site.build().unwrap();
+
+    assert!(file_contains!(public, "index.html", "https://example.com/"));
+    assert!(file_contains!(public, "fr/index.html", "https://example.com/fr/"));
+    assert!(file_contains!(public, "blog/index.html", "https://example.com/blog/"));
+    assert!(file_contains!(public, "fr/blog/index.html", "https://example.com/fr/blog/"));
+    // Pages at the same place in both directories are translations of each other
+    assert!(file_contains!(public, "blog/hello/index.html", "https://example.com/fr/blog/hello/ "));
+    assert!(file_contains!(public, "fr/blog/hello/index.html", "https://example.com/blog/hello/ "));
+    assert!(!file_exists!(public, "en/index.html"));
+}
+
+#[test]
+fn can_fall_back_to_default_language_for_missing_translations() {
+    let (mut site, tmp_dir, public) = load_tmp_site(
+        "languages = [{ code = \"fr\" }]\ntranslation_fallback = true\n",
+        &[
+            ("content/_index.md", "+++\n+++\n"),
+            ("content/blog/_index.md", "+++\n+++\n"),
+            ("content/blog/_index.fr.md", "+++\n+++\n"),
+            ("content/blog/hello.md", "+++\ntitle = \"Hello\"\n+++\nHello world"),
+            ("content/blog/other.md", "+++\ntitle = \"Other\"\n+++\nAnother page"),
+            ("content/blog/other.fr.md", "+++\ntitle = \"Autre\"\n+++\nUne autre page"),
+            ("templates/index.html", "{{ section.permalink }}"),
+            (
+                "templates/section.html",
+                "{% for p in section.pages %}{{ p.permalink }} {% endfor %}",
+            ),
+            (
+                "templates/page.html",
+                "{{ page.lang }}|{% if page.is_fallback %}Not translated yet|{% endif %}{{ page.content | safe }}",
+            ),
+        ],
+    );
+    let blog = tmp_dir.path().join("content").join("blog");
+    {
+        let library = site.library.read().unwrap();
+        // Only `hello.md` is missing in French
+        assert_eq!(library.pages().len(), 4);
+        let fallback = library.get_page(&blog.join("hello.fr.md")).unwrap();
+        assert!(fallback.is_fallback);
+        assert_eq!(fallback.lang, "fr");
+        assert_eq!(fallback.permalink, "https://example.com/fr/blog/hello/");
+        assert!(!library.get_page(&blog.join("other.fr.md")).unwrap().is_fallback);
+    }
+    site.build().unwrap();
+
+    assert!(file_contains!(
+        public,
+        "fr/blog/hello/index.html",
+        "fr|Not translated yet|<p>Hello world</p>"
+    ));
+    assert!(file_contains!(public, "blog/hello/index.html", "en|<p>Hello world</p>"));
+    assert!(file_contains!(public, "fr/blog/other/index.html", "fr|<p>Une autre page</p>"));
+    // The fallback is listed in the French section
+    assert!(file_contains!(public, "fr/blog/index.html", "https://example.com/fr/blog/hello/"));
+}
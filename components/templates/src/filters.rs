@@ -5,6 +5,7 @@ use base64::{decode, encode};
 use config::Config;
 use rendering::{render_content, RenderContext};
 use tera::{to_value, try_get_value, Filter as TeraFilter, Result as TeraResult, Value};
+use utils::site::resolve_internal_link;
 
 #[derive(Debug)]
 pub struct MarkdownFilter {
@@ -42,6 +43,50 @@ impl TeraFilter for MarkdownFilter {
     }
 }
 
+/// Replaces the internal links (`@/posts/hello.md`) found in a string with their permalinks
+#[derive(Debug)]
+pub struct ResolveInternalLinksFilter {
+    permalinks: HashMap<String, String>,
+}
+
+impl ResolveInternalLinksFilter {
+    pub fn new(permalinks: HashMap<String, String>) -> Self {
+        Self { permalinks }
+    }
+}
+
+impl TeraFilter for ResolveInternalLinksFilter {
+    fn filter(&self, value: &Value, _: &HashMap<String, Value>) -> TeraResult<Value> {
+        let s = try_get_value!("resolve_internal_links", "value", String, value);
+        let mut res = String::with_capacity(s.len());
+        let mut rest = s.as_str();
+
+        while let Some(start) = rest.find("@/") {
+            res.push_str(&rest[..start]);
+            let candidate = &rest[start..];
+            let end = candidate
+                .find(|c: char| c.is_whitespace() || "\"'()<>[]".contains(c))
+                .unwrap_or_else(|| candidate.len());
+            // Don't take the punctuation ending a sentence as part of the link
+            let link = candidate[..end].trim_end_matches(|c| ".,;:!?".contains(c));
+            match resolve_internal_link(link, &self.permalinks) {
+                Ok(resolved) => res.push_str(&resolved.permalink),
+                Err(_) => {
+                    return Err(format!(
+                        "`resolve_internal_links`: could not find the page or section for `{}`",
+                        link
+                    )
+                    .into())
+                }
+            }
+            rest = &candidate[link.len()..];
+        }
+        res.push_str(rest);
+
+        Ok(to_value(&res).unwrap())
+    }
+}
+
 pub fn base64_encode<S: BuildHasher>(
     value: &Value,
     _: &HashMap<String, Value, S>,
@@ -64,7 +109,7 @@ mod tests {
 
     use tera::{to_value, Filter};
 
-    use super::{base64_decode, base64_encode, MarkdownFilter};
+    use super::{base64_decode, base64_encode, MarkdownFilter, ResolveInternalLinksFilter};
     use config::Config;
 
     #[test]
@@ -166,4 +211,29 @@ mod tests {
             assert_eq!(result.unwrap(), to_value(expected).unwrap());
         }
     }
+
+    #[test]
+    fn resolve_internal_links_filter() {
+        let mut permalinks = HashMap::new();
+        permalinks.insert("pages/about.md".to_string(), "https://vincent.is/about/".to_string());
+        let filter = ResolveInternalLinksFilter::new(permalinks);
+
+        let result = filter.filter(
+            &to_value(&r#"Read <a href="@/pages/about.md#team">this</a> or @/pages/about.md."#)
+                .unwrap(),
+            &HashMap::new(),
+        );
+        assert_eq!(
+            result.unwrap(),
+            to_value(&r#"Read <a href="https://vincent.is/about/#team">this</a> or https://vincent.is/about/."#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn resolve_internal_links_filter_errors_on_unknown_link() {
+        let filter = ResolveInternalLinksFilter::new(HashMap::new());
+        let result = filter.filter(&to_value(&"See @/pages/nope.md").unwrap(), &HashMap::new());
+        assert!(result.unwrap_err().to_string().contains("@/pages/nope.md"));
+    }
 }
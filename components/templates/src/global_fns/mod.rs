@@ -1,16 +1,21 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
+use std::io::Read;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::{fs, io, result};
 
 use sha2::{Digest, Sha256, Sha384, Sha512};
 use svg_metadata as svg;
-use tera::{from_value, to_value, Error, Function as TeraFn, Result, Value};
+use tera::{from_value, to_value, Error, Function as TeraFn, Map, Result, Tera, Value};
 
 use config::Config;
 use image::GenericImageView;
 use library::{Library, Taxonomy};
+use rendering::{render_content, RenderContext};
+use utils::fs::read_file;
 use utils::site::resolve_internal_link;
 use utils::slugs::{slugify_paths, SlugifyStrategy};
 
@@ -45,6 +50,41 @@ impl TeraFn for Trans {
     }
 }
 
+#[derive(Debug)]
+pub struct GetExtra {
+    extra: Value,
+}
+impl GetExtra {
+    pub fn new(config: &Config) -> Self {
+        Self { extra: to_value(&config.extra).unwrap() }
+    }
+}
+impl TeraFn for GetExtra {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(String, args.get("path"), "`extra` requires a `path` argument.");
+
+        // Each part of the path is either a key of a table or an index in an array
+        let mut current = Some(&self.extra);
+        for part in path.split('.') {
+            current = match current {
+                Some(Value::Object(map)) => map.get(part),
+                Some(Value::Array(items)) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            };
+        }
+
+        match (current, args.get("default")) {
+            (Some(val), _) => Ok(val.clone()),
+            (None, Some(default)) => Ok(default.clone()),
+            (None, None) => Err(format!(
+                "`extra`: `{}` was not found in `config.extra` and no `default` was given.",
+                path
+            )
+            .into()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GetUrl {
     config: Config,
@@ -209,6 +249,138 @@ impl TeraFn for GetFileHash {
     }
 }
 
+/// Returns the Subresource Integrity value (`sha384-{base64 digest}`) of a local file, to be used
+/// in the `integrity` attribute of `<script>` and `<link>` tags.
+/// The hashes are cached in `cache`, which the site clears at the start of every build.
+#[derive(Debug)]
+pub struct GetSriHash {
+    search_paths: Vec<PathBuf>,
+    cache: Arc<Mutex<HashMap<String, String>>>,
+}
+impl GetSriHash {
+    pub fn new(search_paths: Vec<PathBuf>, cache: Arc<Mutex<HashMap<String, String>>>) -> Self {
+        Self { search_paths, cache }
+    }
+}
+
+impl TeraFn for GetSriHash {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_sri_hash` requires a `path` argument with a string value"
+        );
+
+        if let Some(hash) = self.cache.lock().unwrap().get(&path) {
+            return Ok(to_value(hash).unwrap());
+        }
+
+        let digest = open_file(&self.search_paths, &path).and_then(|mut file| {
+            let mut hasher = Sha384::new();
+            io::copy(&mut file, &mut hasher)?;
+            Ok(hasher.finalize())
+        });
+
+        match digest {
+            Ok(digest) => {
+                let hash = format!("sha384-{}", base64::encode(&digest));
+                self.cache.lock().unwrap().insert(path, hash.clone());
+                Ok(to_value(hash).unwrap())
+            }
+            Err(_) => file_not_found_err(&self.search_paths, &path),
+        }
+    }
+}
+
+/// Returns the content of a CSS file, to inline it in a `<style>` tag for example
+#[derive(Debug)]
+pub struct LoadCss {
+    search_paths: Vec<PathBuf>,
+}
+impl LoadCss {
+    pub fn new(search_paths: Vec<PathBuf>) -> Self {
+        Self { search_paths }
+    }
+}
+
+impl TeraFn for LoadCss {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`load_css` requires a `path` argument with a string value"
+        );
+
+        let mut css = String::new();
+        match open_file(&self.search_paths, &path).and_then(|mut f| f.read_to_string(&mut css)) {
+            Ok(_) => Ok(to_value(css).unwrap()),
+            Err(_) => file_not_found_err(&self.search_paths, &path),
+        }
+    }
+}
+
+/// Concatenates local CSS or JS files in a single file named after the hash of its content and
+/// returns its permalink. The bundles are recorded in `bundles`, by path relative to the output
+/// directory, for the site to write them once rendering is done: pages using the same files
+/// share the same bundle.
+#[derive(Debug)]
+pub struct Bundle {
+    config: Config,
+    search_paths: Vec<PathBuf>,
+    bundles: Arc<Mutex<HashMap<String, String>>>,
+}
+impl Bundle {
+    pub fn new(
+        config: Config,
+        search_paths: Vec<PathBuf>,
+        bundles: Arc<Mutex<HashMap<String, String>>>,
+    ) -> Self {
+        Self { config, search_paths, bundles }
+    }
+}
+
+impl TeraFn for Bundle {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let paths = required_arg!(
+            Vec<String>,
+            args.get("paths"),
+            "`bundle` requires a `paths` argument with an array of strings"
+        );
+        let kind = required_arg!(
+            String,
+            args.get("type"),
+            "`bundle` requires a `type` argument with a string value"
+        );
+        if kind != "css" && kind != "js" {
+            return Err("`bundle`: `type` must be `css` or `js`".into());
+        }
+        if paths.is_empty() {
+            return Err("`bundle` requires at least one path in `paths`".into());
+        }
+
+        let mut content = String::new();
+        for path in &paths {
+            // Files without a trailing newline could otherwise end in a comment swallowing
+            // the start of the next one
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            if open_file(&self.search_paths, path)
+                .and_then(|mut f| f.read_to_string(&mut content))
+                .is_err()
+            {
+                return file_not_found_err(&self.search_paths, path);
+            }
+        }
+
+        let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+        let path = format!("bundles/{}.{}", &hash[..20], kind);
+        let permalink = format!("{}/{}", self.config.base_url.trim_end_matches('/'), path);
+        self.bundles.lock().unwrap().entry(path).or_insert(content);
+        Ok(to_value(permalink).unwrap())
+    }
+}
+
 #[derive(Debug)]
 pub struct ResizeImage {
     imageproc: Arc<Mutex<imageproc::Processor>>,
@@ -260,6 +432,7 @@ impl TeraFn for ResizeImage {
         }
 
         let imageop = imageproc::ImageOp::from_args(path, &op, width, height, &format, quality)
+            .and_then(|imageop| imageproc.limit(imageop))
             .map_err(|e| format!("`resize_image`: {}", e))?;
         let url = imageproc.insert(imageop);
 
@@ -267,6 +440,37 @@ impl TeraFn for ResizeImage {
     }
 }
 
+#[derive(Debug)]
+pub struct ImageLqip {
+    imageproc: Arc<Mutex<imageproc::Processor>>,
+}
+impl ImageLqip {
+    pub fn new(imageproc: Arc<Mutex<imageproc::Processor>>) -> Self {
+        Self { imageproc }
+    }
+}
+
+impl TeraFn for ImageLqip {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`image_lqip` requires a `path` argument with a string value"
+        );
+
+        let imageproc = self.imageproc.lock().unwrap();
+        if !imageproc.source_exists(&path) {
+            return Err(format!("`image_lqip`: Cannot find path: {}", path).into());
+        }
+
+        match imageproc.lqip(&path) {
+            Ok(Some(data_uri)) => Ok(to_value(data_uri).unwrap()),
+            Ok(None) => Ok(Value::Null),
+            Err(e) => Err(format!("`image_lqip`: {}", e).into()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GetImageMeta {
     content_path: PathBuf,
@@ -290,13 +494,45 @@ impl TeraFn for GetImageMeta {
             return Err(format!("`get_image_metadata`: Cannot find path: {}", path).into());
         }
         let (height, width) = image_dimensions(&src_path)?;
-        let mut map = tera::Map::new();
+        let mut map = image_description(&src_path)?;
         map.insert(String::from("height"), Value::Number(tera::Number::from(height)));
         map.insert(String::from("width"), Value::Number(tera::Number::from(width)));
         Ok(Value::Object(map))
     }
 }
 
+/// The file giving an `alt` and a `title` to the images of its directory, keyed by filename
+static IMAGES_DESCRIPTION_FILENAME: &str = "images.toml";
+
+// Read the `alt` and `title` given to an image in the `images.toml` of its directory, if any
+fn image_description(path: &PathBuf) -> Result<Map<String, Value>> {
+    let mut description = Map::new();
+    let (dir, filename) = match (path.parent(), path.file_name().and_then(OsStr::to_str)) {
+        (Some(dir), Some(filename)) => (dir, filename),
+        _ => return Ok(description),
+    };
+    let description_path = dir.join(IMAGES_DESCRIPTION_FILENAME);
+    if !description_path.exists() {
+        return Ok(description);
+    }
+
+    let content = read_file(&description_path).map_err(|e| {
+        format!("`get_image_metadata`: failed to read `{}`: {}", description_path.display(), e)
+    })?;
+    let images: toml::Value = toml::from_str(&content).map_err(|e| {
+        format!("`get_image_metadata`: failed to parse `{}`: {}", description_path.display(), e)
+    })?;
+    if let Some(image) = images.get(filename) {
+        for key in &["alt", "title"] {
+            if let Some(value) = image.get(key).and_then(|v| v.as_str()) {
+                description.insert(key.to_string(), to_value(value).unwrap());
+            }
+        }
+    }
+
+    Ok(description)
+}
+
 // Try to read the image dimensions for a given image
 fn image_dimensions(path: &PathBuf) -> Result<(u32, u32)> {
     if let Some("svg") = path.extension().and_then(OsStr::to_str) {
@@ -433,6 +669,183 @@ impl TeraFn for GetSection {
     }
 }
 
+#[derive(Debug)]
+pub struct GetSectionPageCount {
+    base_path: PathBuf,
+    library: Arc<RwLock<Library>>,
+}
+impl GetSectionPageCount {
+    pub fn new(base_path: PathBuf, library: Arc<RwLock<Library>>) -> Self {
+        Self { base_path: base_path.join("content"), library }
+    }
+}
+impl TeraFn for GetSectionPageCount {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_section_page_count` requires a `path` argument with a string value"
+        );
+        let recursive = optional_arg!(
+            bool,
+            args.get("recursive"),
+            "`get_section_page_count`: `recursive` must be a boolean (true or false)"
+        )
+        .unwrap_or(false);
+
+        let full_path = self.base_path.join(&path);
+        let library = self.library.read().unwrap();
+        let section = match library.get_section(&full_path) {
+            Some(s) => s,
+            None => return Err(format!("Section `{}` not found.", path).into()),
+        };
+        if !recursive {
+            return Ok(to_value(section.pages.len()).unwrap());
+        }
+
+        // The pages of transparent sections are also in their parent so we dedupe them
+        let mut pages = HashSet::new();
+        let mut sections = vec![section];
+        while let Some(s) = sections.pop() {
+            pages.extend(s.pages.iter().copied());
+            sections.extend(s.subsections.iter().map(|k| library.get_section_by_key(*k)));
+        }
+        Ok(to_value(pages.len()).unwrap())
+    }
+}
+
+#[derive(Debug)]
+pub struct GetBreadcrumbs {
+    base_path: PathBuf,
+    library: Arc<RwLock<Library>>,
+}
+impl GetBreadcrumbs {
+    pub fn new(base_path: PathBuf, library: Arc<RwLock<Library>>) -> Self {
+        Self { base_path: base_path.join("content"), library }
+    }
+}
+impl TeraFn for GetBreadcrumbs {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_breadcrumbs` requires a `path` argument with a string value"
+        );
+
+        let full_path = self.base_path.join(&path);
+        let library = self.library.read().unwrap();
+        let (ancestors, title, permalink) = if let Some(p) = library.get_page(&full_path) {
+            (&p.ancestors, &p.meta.title, &p.permalink)
+        } else if let Some(s) = library.get_section(&full_path) {
+            (&s.ancestors, &s.meta.title, &s.permalink)
+        } else {
+            return Err(format!("`get_breadcrumbs`: `{}` not found.", path).into());
+        };
+
+        let crumb = |title: &Option<String>, permalink: &str| {
+            let mut crumb = Map::new();
+            crumb.insert("title".to_string(), to_value(title).unwrap());
+            crumb.insert("permalink".to_string(), to_value(permalink).unwrap());
+            Value::Object(crumb)
+        };
+        // The ancestors go from the index section to the parent
+        let mut breadcrumbs: Vec<_> = ancestors
+            .iter()
+            .map(|k| library.get_section_by_key(*k))
+            .map(|s| crumb(&s.meta.title, &s.permalink))
+            .collect();
+        breadcrumbs.push(crumb(title, permalink));
+
+        Ok(Value::Array(breadcrumbs))
+    }
+}
+
+/// Returns every language of the site, the default one first, with its code, its name (the
+/// `language_name` translation of that language, falling back to its code) and a permalink:
+/// the one of the translation of the page or section at `path` if given, `null` if there is no
+/// translation in that language, or the one of the homepage of that language otherwise.
+#[derive(Debug)]
+pub struct GetLanguages {
+    base_path: PathBuf,
+    config: Config,
+    library: Arc<RwLock<Library>>,
+}
+impl GetLanguages {
+    pub fn new(base_path: PathBuf, config: Config, library: Arc<RwLock<Library>>) -> Self {
+        Self { base_path: base_path.join("content"), config, library }
+    }
+}
+impl TeraFn for GetLanguages {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path =
+            optional_arg!(String, args.get("path"), "`get_languages`: `path` must be a string");
+
+        let library = self.library.read().unwrap();
+        // The permalink of the content at `path` in each language it exists in
+        let translations = match path {
+            Some(ref path) => {
+                let full_path = self.base_path.join(path);
+                // The translations of a page are pages and the ones of a section are sections
+                let mut translations = HashMap::new();
+                if let Some(p) = library.get_page(&full_path) {
+                    translations.insert(p.lang.as_str(), (p.permalink.as_str(), true));
+                    for key in library.translations.get(&p.file.canonical).into_iter().flatten() {
+                        let other = library.get_page_by_key(*key);
+                        translations.entry(&other.lang).or_insert((&other.permalink, false));
+                    }
+                } else if let Some(s) = library.get_section(&full_path) {
+                    translations.insert(s.lang.as_str(), (s.permalink.as_str(), true));
+                    for key in library.translations.get(&s.file.canonical).into_iter().flatten() {
+                        let other = library.get_section_by_key(*key);
+                        translations.entry(&other.lang).or_insert((&other.permalink, false));
+                    }
+                } else {
+                    return Err(format!("`get_languages`: `{}` not found.", path).into());
+                }
+                Some(translations)
+            }
+            None => None,
+        };
+
+        let mut codes = vec![self.config.default_language.as_str()];
+        codes.extend(self.config.languages_codes());
+        let languages = codes
+            .into_iter()
+            .map(|code| {
+                let is_default = code == self.config.default_language;
+                let (permalink, current) = match translations {
+                    Some(ref t) => match t.get(code) {
+                        Some((permalink, current)) => (Some(permalink.to_string()), *current),
+                        None => (None, false),
+                    },
+                    None if is_default => (Some(self.config.make_permalink("")), false),
+                    None => (
+                        Some(self.config.make_permalink_for_lang(&format!("{}/", code), code)),
+                        false,
+                    ),
+                };
+                let mut language = Map::new();
+                language.insert("code".to_string(), to_value(code).unwrap());
+                language.insert(
+                    "name".to_string(),
+                    to_value(
+                        self.config
+                            .get_translation(code, "language_name")
+                            .unwrap_or_else(|_| code.to_string()),
+                    )
+                    .unwrap(),
+                );
+                language.insert("is_default".to_string(), to_value(is_default).unwrap());
+                language.insert("permalink".to_string(), to_value(permalink).unwrap());
+                language.insert("current".to_string(), to_value(current).unwrap());
+                Value::Object(language)
+            })
+            .collect();
+
+        Ok(Value::Array(languages))
+    }
+}
+
 #[derive(Debug)]
 pub struct GetTaxonomy {
     library: Arc<RwLock<Library>>,
@@ -473,22 +886,321 @@ impl TeraFn for GetTaxonomy {
     }
 }
 
+#[derive(Debug)]
+pub struct GetTaxonomyTerm {
+    library: Arc<RwLock<Library>>,
+    taxonomies: HashMap<String, Taxonomy>,
+    default_lang: String,
+    slugify: SlugifyStrategy,
+}
+impl GetTaxonomyTerm {
+    pub fn new(
+        default_lang: &str,
+        all_taxonomies: Vec<Taxonomy>,
+        library: Arc<RwLock<Library>>,
+        slugify: SlugifyStrategy,
+    ) -> Self {
+        let mut taxonomies = HashMap::new();
+        for taxo in all_taxonomies {
+            taxonomies.insert(format!("{}-{}", taxo.kind.name, taxo.kind.lang), taxo);
+        }
+        Self { taxonomies, library, default_lang: default_lang.to_string(), slugify }
+    }
+}
+impl TeraFn for GetTaxonomyTerm {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let kind = required_arg!(
+            String,
+            args.get("kind"),
+            "`get_taxonomy_term` requires a `kind` argument with a string value"
+        );
+        let term = required_arg!(
+            String,
+            args.get("term"),
+            "`get_taxonomy_term` requires a `term` argument with a string value"
+        );
+        let lang =
+            optional_arg!(String, args.get("lang"), "`get_taxonomy_term`: `lang` must be a string")
+                .unwrap_or_else(|| self.default_lang.clone());
+
+        let taxonomy = match self.taxonomies.get(&format!("{}-{}", kind, lang)) {
+            Some(t) => t,
+            None => {
+                return Err(format!(
+                    "`get_taxonomy_term` received an unknown taxonomy as kind: {}",
+                    kind
+                )
+                .into());
+            }
+        };
+
+        let slug = slugify_paths(&term, self.slugify);
+        match taxonomy.items.iter().find(|i| i.slug == slug) {
+            Some(item) => Ok(to_value(item.serialize(&self.library.read().unwrap())).unwrap()),
+            None => {
+                Err(format!("`get_taxonomy_term`: couldn't find `{}` in `{}` taxonomy", term, kind)
+                    .into())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GetPageSiblings {
+    base_path: PathBuf,
+    library: Arc<RwLock<Library>>,
+    taxonomies: HashMap<String, Taxonomy>,
+    slugify: SlugifyStrategy,
+}
+impl GetPageSiblings {
+    pub fn new(
+        base_path: PathBuf,
+        all_taxonomies: Vec<Taxonomy>,
+        library: Arc<RwLock<Library>>,
+        slugify: SlugifyStrategy,
+    ) -> Self {
+        let mut taxonomies = HashMap::new();
+        for taxo in all_taxonomies {
+            taxonomies.insert(format!("{}-{}", taxo.kind.name, taxo.kind.lang), taxo);
+        }
+        Self { base_path: base_path.join("content"), taxonomies, library, slugify }
+    }
+}
+impl TeraFn for GetPageSiblings {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_page_siblings` requires a `path` argument with a string value"
+        );
+        let kind = required_arg!(
+            String,
+            args.get("taxonomy"),
+            "`get_page_siblings` requires a `taxonomy` argument with a string value"
+        );
+        let term = required_arg!(
+            String,
+            args.get("term"),
+            "`get_page_siblings` requires a `term` argument with a string value"
+        );
+
+        let library = self.library.read().unwrap();
+        let page_key = match library.paths_to_pages.get(&self.base_path.join(&path)) {
+            Some(k) => *k,
+            None => return Err(format!("Page `{}` not found.", path).into()),
+        };
+        let lang = &library.get_page_by_key(page_key).lang;
+
+        let taxonomy = match self.taxonomies.get(&format!("{}-{}", kind, lang)) {
+            Some(t) => t,
+            None => {
+                return Err(
+                    format!("`get_page_siblings` received an unknown taxonomy: {}", kind).into()
+                );
+            }
+        };
+        let slug = slugify_paths(&term, self.slugify);
+        let item = match taxonomy.items.iter().find(|i| i.slug == slug) {
+            Some(item) => item,
+            None => {
+                return Err(format!(
+                    "`get_page_siblings`: couldn't find `{}` in `{}` taxonomy",
+                    term, kind
+                )
+                .into());
+            }
+        };
+        let index = match item.pages.iter().position(|k| *k == page_key) {
+            Some(i) => i,
+            None => {
+                return Err(format!(
+                    "`get_page_siblings`: page `{}` doesn't have `{}` in its `{}` taxonomy",
+                    path, term, kind
+                )
+                .into());
+            }
+        };
+
+        // The pages of a term are sorted from the newest to the oldest
+        let previous = item.pages.get(index + 1);
+        let next = if index > 0 { item.pages.get(index - 1) } else { None };
+        let mut siblings = Map::new();
+        for (name, key) in &[("previous", previous), ("next", next)] {
+            let value = match key {
+                Some(k) => {
+                    to_value(library.get_page_by_key(**k).to_serialized_basic(&library)).unwrap()
+                }
+                None => Value::Null,
+            };
+            siblings.insert(name.to_string(), value);
+        }
+
+        Ok(Value::Object(siblings))
+    }
+}
+
+#[derive(Debug)]
+pub struct GetRelatedPages {
+    base_path: PathBuf,
+    library: Arc<RwLock<Library>>,
+    taxonomies: Vec<Taxonomy>,
+}
+impl GetRelatedPages {
+    pub fn new(
+        base_path: PathBuf,
+        taxonomies: Vec<Taxonomy>,
+        library: Arc<RwLock<Library>>,
+    ) -> Self {
+        Self { base_path: base_path.join("content"), taxonomies, library }
+    }
+}
+
+const DEFAULT_RELATED_PAGES_LIMIT: usize = 5;
+
+impl TeraFn for GetRelatedPages {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`get_related_pages` requires a `path` argument with a string value"
+        );
+        let limit = optional_arg!(
+            usize,
+            args.get("limit"),
+            "`get_related_pages`: `limit` must be a non-negative integer"
+        )
+        .unwrap_or(DEFAULT_RELATED_PAGES_LIMIT);
+
+        let library = self.library.read().unwrap();
+        let page_key = match library.paths_to_pages.get(&self.base_path.join(&path)) {
+            Some(k) => *k,
+            None => return Err(format!("Page `{}` not found.", path).into()),
+        };
+        let lang = &library.get_page_by_key(page_key).lang;
+
+        // Each term shared with the page is worth one point
+        let mut scores = HashMap::new();
+        for taxonomy in self.taxonomies.iter().filter(|t| &t.kind.lang == lang) {
+            for item in taxonomy.items.iter().filter(|i| i.pages.contains(&page_key)) {
+                for key in item.pages.iter().filter(|k| **k != page_key) {
+                    *scores.entry(*key).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut related: Vec<_> = scores
+            .into_iter()
+            .map(|(key, score)| (library.get_page_by_key(key), score))
+            .filter(|(page, _)| !page.meta.draft)
+            .collect();
+        // Most shared terms first, then the most recent pages
+        related.sort_by(|(a, score_a), (b, score_b)| {
+            score_b
+                .cmp(score_a)
+                .then_with(|| b.meta.datetime.cmp(&a.meta.datetime))
+                .then_with(|| a.permalink.cmp(&b.permalink))
+        });
+
+        let pages: Vec<_> = related
+            .into_iter()
+            .take(limit)
+            .map(|(page, _)| page.to_serialized_basic(&library))
+            .collect();
+        Ok(to_value(pages).unwrap())
+    }
+}
+
+/// How many `include_md` calls can be nested before we assume a file is including itself
+const MAX_INCLUDE_DEPTH: usize = 5;
+
+thread_local! {
+    /// How many `include_md` calls are being rendered on this thread
+    static INCLUDE_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+/// The templates `include_md` renders the shortcodes of the included files with.
+/// The site updates it whenever its templates or their functions change so it always has all
+/// of them, without being cloned on every call.
+pub type SharedTera = Arc<RwLock<Arc<Tera>>>;
+
+/// Renders a markdown file from the content directory, shortcodes included
+#[derive(Debug)]
+pub struct IncludeMd {
+    /// Weak as the shared templates contain this function
+    tera: Weak<RwLock<Arc<Tera>>>,
+    config: Config,
+    permalinks: HashMap<String, String>,
+    content_path: PathBuf,
+}
+impl IncludeMd {
+    pub fn new(
+        tera: &SharedTera,
+        config: Config,
+        permalinks: HashMap<String, String>,
+        content_path: PathBuf,
+    ) -> Self {
+        Self { tera: Arc::downgrade(tera), config, permalinks, content_path }
+    }
+}
+impl TeraFn for IncludeMd {
+    fn call(&self, args: &HashMap<String, Value>) -> Result<Value> {
+        let path = required_arg!(
+            String,
+            args.get("path"),
+            "`include_md` requires a `path` argument with a string value"
+        );
+        let depth = INCLUDE_DEPTH.with(|d| d.get());
+        if depth >= MAX_INCLUDE_DEPTH {
+            return Err(format!(
+                "`include_md`: too many nested includes when including `{}`, is a file including itself?",
+                path
+            )
+            .into());
+        }
+
+        let full_path = self.content_path.join(path.trim_start_matches("@/"));
+        let content = read_file(&full_path)
+            .map_err(|e| format!("`include_md`: failed to read `{}`: {}", path, e))?;
+
+        let tera = match self.tera.upgrade() {
+            Some(tera) => Arc::clone(&tera.read().unwrap()),
+            None => return Err("`include_md`: the templates are not available anymore".into()),
+        };
+        let mut context = RenderContext::from_config(&self.config);
+        context.tera = Cow::Borrowed(&*tera);
+        context.permalinks = Cow::Borrowed(&self.permalinks);
+
+        // The included file can itself include other files, one level deeper
+        INCLUDE_DEPTH.with(|d| d.set(depth + 1));
+        let res = render_content(&content, &context);
+        INCLUDE_DEPTH.with(|d| d.set(depth));
+        match res {
+            Ok(res) => Ok(to_value(res.body).unwrap()),
+            Err(e) => Err(format!("`include_md`: failed to render `{}`: {}", path, e).into()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{GetFileHash, GetTaxonomy, GetTaxonomyUrl, GetUrl, Trans};
+    use super::{
+        Bundle, GetBreadcrumbs, GetExtra, GetFileHash, GetImageMeta, GetLanguages, GetPageSiblings,
+        GetRelatedPages, GetSectionPageCount, GetSriHash, GetTaxonomy, GetTaxonomyTerm,
+        GetTaxonomyUrl, GetUrl, ImageLqip, IncludeMd, LoadCss, Trans,
+    };
 
     use std::collections::HashMap;
-    use std::env::temp_dir;
+    use std::env::{self, temp_dir};
     use std::fs::remove_dir_all;
     use std::path::PathBuf;
-    use std::sync::{Arc, RwLock};
+    use std::sync::{Arc, Mutex, RwLock};
 
     use lazy_static::lazy_static;
 
-    use tera::{to_value, Function, Value};
+    use tera::{to_value, Function, Tera, Value};
 
     use config::{Config, Taxonomy as TaxonomyConfig};
-    use library::{Library, Taxonomy, TaxonomyItem};
+    use library::{Library, Page, Section, Taxonomy, TaxonomyItem};
     use utils::fs::{create_directory, create_file};
     use utils::slugs::SlugifyStrategy;
 
@@ -635,6 +1347,297 @@ mod tests {
         assert!(static_fn.call(&args).is_err());
     }
 
+    #[test]
+    fn can_get_taxonomy_term() {
+        let mut config = Config::default();
+        config.slugify.taxonomies = SlugifyStrategy::On;
+        let taxo_config = TaxonomyConfig {
+            name: "tags".to_string(),
+            lang: config.default_language.clone(),
+            ..TaxonomyConfig::default()
+        };
+        let mut library = Library::new(2, 0, false);
+        let mut page1 = Page::default();
+        page1.file.path = PathBuf::from("content/hello.md");
+        page1.meta.title = Some("Hello".to_string());
+        let mut page2 = Page::default();
+        page2.file.path = PathBuf::from("content/world.md");
+        page2.meta.title = Some("World".to_string());
+        let key1 = library.insert_page(page1);
+        library.insert_page(page2);
+        let tag = TaxonomyItem::new("Rust", &taxo_config, "tags", &config, vec![key1], &library);
+        let other_tag =
+            TaxonomyItem::new("Python", &taxo_config, "tags", &config, vec![], &library);
+        let tags =
+            Taxonomy { kind: taxo_config, slug: "tags".to_string(), items: vec![tag, other_tag] };
+
+        let static_fn = GetTaxonomyTerm::new(
+            &config.default_language,
+            vec![tags],
+            Arc::new(RwLock::new(library)),
+            config.slugify.taxonomies,
+        );
+        let mut args = HashMap::new();
+        args.insert("kind".to_string(), to_value("tags").unwrap());
+        args.insert("term".to_string(), to_value("Rust").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        let res_obj = res.as_object().unwrap();
+        assert_eq!(res_obj["name"], Value::String("Rust".to_string()));
+        assert_eq!(
+            res_obj["permalink"],
+            Value::String("http://a-website.com/tags/rust/".to_string())
+        );
+        let pages = res_obj["pages"].as_array().unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].as_object().unwrap()["title"], Value::String("Hello".to_string()));
+
+        // errors on unknown terms and taxonomies
+        args.insert("term".to_string(), to_value("Go").unwrap());
+        assert!(static_fn.call(&args).is_err());
+        args.insert("kind".to_string(), to_value("categories").unwrap());
+        args.insert("term".to_string(), to_value("Rust").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_get_page_siblings_in_taxonomy_term() {
+        let config = Config::default();
+        let taxo_config = TaxonomyConfig {
+            name: "series".to_string(),
+            lang: config.default_language.clone(),
+            ..TaxonomyConfig::default()
+        };
+        let mut library = Library::new(3, 0, false);
+        let mut keys = vec![];
+        for (name, date) in &[("one", "2020-01-01"), ("two", "2020-02-01"), ("three", "2020-03-01")]
+        {
+            let content = format!("+++\ntitle = \"{}\"\ndate = {}\n+++\n", name, date);
+            let page = Page::parse(
+                &PathBuf::from(format!("content/{}.md", name)),
+                &content,
+                &config,
+                &PathBuf::new(),
+            )
+            .unwrap();
+            keys.push(library.insert_page(page));
+        }
+        let item = TaxonomyItem::new("Rust", &taxo_config, "series", &config, keys, &library);
+        let series = Taxonomy { kind: taxo_config, slug: "series".to_string(), items: vec![item] };
+        let library = Arc::new(RwLock::new(library));
+
+        let static_fn = GetPageSiblings::new(
+            PathBuf::new(),
+            vec![series],
+            library.clone(),
+            SlugifyStrategy::On,
+        );
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("two.md").unwrap());
+        args.insert("taxonomy".to_string(), to_value("series").unwrap());
+        args.insert("term".to_string(), to_value("Rust").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        assert_eq!(res["previous"]["title"], "one");
+        assert_eq!(res["next"]["title"], "three");
+
+        args.insert("path".to_string(), to_value("three.md").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        assert_eq!(res["previous"]["title"], "two");
+        assert_eq!(res["next"], Value::Null);
+
+        args.insert("term".to_string(), to_value("Python").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_get_related_pages_by_shared_terms() {
+        let config = Config::default();
+        let tags_config = TaxonomyConfig {
+            name: "tags".to_string(),
+            lang: config.default_language.clone(),
+            ..TaxonomyConfig::default()
+        };
+        let mut library = Library::new(5, 0, false);
+        let mut keys = HashMap::new();
+        for (name, draft) in
+            &[("main", false), ("close", false), ("far", false), ("none", false), ("draft", true)]
+        {
+            let content =
+                format!("+++\ntitle = \"{}\"\ndate = 2020-01-01\ndraft = {}\n+++\n", name, draft);
+            let page = Page::parse(
+                &PathBuf::from(format!("content/{}.md", name)),
+                &content,
+                &config,
+                &PathBuf::new(),
+            )
+            .unwrap();
+            keys.insert(*name, library.insert_page(page));
+        }
+        let mut items = vec![];
+        for (term, pages) in &[
+            ("rust", vec!["main", "close", "far", "draft"]),
+            ("web", vec!["main", "close", "draft"]),
+            ("python", vec!["far", "none"]),
+        ] {
+            let pages = pages.iter().map(|p| keys[p]).collect();
+            items.push(TaxonomyItem::new(term, &tags_config, "tags", &config, pages, &library));
+        }
+        let tags = Taxonomy { kind: tags_config, slug: "tags".to_string(), items };
+        let library = Arc::new(RwLock::new(library));
+
+        let static_fn = GetRelatedPages::new(PathBuf::new(), vec![tags], library.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("main.md").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        let titles: Vec<_> = res.as_array().unwrap().iter().map(|p| p["title"].clone()).collect();
+        assert_eq!(titles, vec!["close", "far"]);
+
+        args.insert("limit".to_string(), to_value(1).unwrap());
+        let res = static_fn.call(&args).unwrap();
+        assert_eq!(res.as_array().unwrap().len(), 1);
+        assert_eq!(res[0]["title"], "close");
+    }
+
+    #[test]
+    fn can_get_breadcrumbs_of_nested_page() {
+        let config = Config::default();
+        let mut library = Library::new(1, 3, false);
+        for (path, title) in &[
+            ("content/_index.md", "Home"),
+            ("content/docs/_index.md", "Docs"),
+            ("content/docs/guide/_index.md", "Guide"),
+        ] {
+            let content = format!("+++\ntitle = \"{}\"\n+++\n", title);
+            let section =
+                Section::parse(&PathBuf::from(path), &content, &config, &PathBuf::new()).unwrap();
+            library.insert_section(section);
+        }
+        let page = Page::parse(
+            &PathBuf::from("content/docs/guide/install.md"),
+            "+++\ntitle = \"Install\"\n+++\n",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        library.insert_page(page);
+        library.populate_sections(&config);
+        let library = Arc::new(RwLock::new(library));
+
+        let static_fn = GetBreadcrumbs::new(PathBuf::new(), library.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("docs/guide/install.md").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        let trail: Vec<_> = res
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| (c["title"].as_str().unwrap(), c["permalink"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            trail,
+            vec![
+                ("Home", "http://a-website.com/"),
+                ("Docs", "http://a-website.com/docs/"),
+                ("Guide", "http://a-website.com/docs/guide/"),
+                ("Install", "http://a-website.com/docs/guide/install/"),
+            ]
+        );
+
+        args.insert("path".to_string(), to_value("docs/_index.md").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        assert_eq!(res.as_array().unwrap().len(), 2);
+        assert_eq!(res[1]["title"], "Docs");
+
+        args.insert("path".to_string(), to_value("docs/missing.md").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_count_pages_of_section_recursively() {
+        let config = Config::default();
+        let mut library = Library::new(3, 3, false);
+        for path in &["content/_index.md", "content/blog/_index.md", "content/blog/2021/_index.md"]
+        {
+            let section =
+                Section::parse(&PathBuf::from(path), "+++\n+++\n", &config, &PathBuf::new())
+                    .unwrap();
+            library.insert_section(section);
+        }
+        for path in &["content/blog/hello.md", "content/blog/2021/a.md", "content/blog/2021/b.md"] {
+            let page =
+                Page::parse(&PathBuf::from(path), "+++\n+++\n", &config, &PathBuf::new()).unwrap();
+            library.insert_page(page);
+        }
+        library.populate_sections(&config);
+        let library = Arc::new(RwLock::new(library));
+
+        let static_fn = GetSectionPageCount::new(PathBuf::new(), library.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("blog/_index.md").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), to_value(1).unwrap());
+
+        args.insert("recursive".to_string(), to_value(true).unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), to_value(3).unwrap());
+
+        args.insert("path".to_string(), to_value("blog/2021/_index.md").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), to_value(2).unwrap());
+
+        args.insert("path".to_string(), to_value("nope/_index.md").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_get_languages_with_available_translations() {
+        let config = Config::parse(
+            r#"
+base_url = "http://a-website.com"
+languages = [{ code = "fr" }, { code = "it" }]
+
+[translations.fr]
+language_name = "Français"
+        "#,
+        )
+        .unwrap();
+        let mut library = Library::new(2, 1, true);
+        let section = Section::parse(
+            &PathBuf::from("content/_index.md"),
+            "+++\n+++\n",
+            &config,
+            &PathBuf::new(),
+        )
+        .unwrap();
+        library.insert_section(section);
+        for path in &["content/post.md", "content/post.fr.md"] {
+            let page =
+                Page::parse(&PathBuf::from(path), "+++\n+++\n", &config, &PathBuf::new()).unwrap();
+            library.insert_page(page);
+        }
+        library.populate_sections(&config);
+        let library = Arc::new(RwLock::new(library));
+
+        let static_fn = GetLanguages::new(PathBuf::new(), config, library.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("post.md").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        let languages = res.as_array().unwrap();
+        assert_eq!(languages.len(), 3);
+        assert_eq!(languages[0]["code"], "en");
+        assert_eq!(languages[0]["name"], "en");
+        assert_eq!(languages[0]["is_default"], true);
+        assert_eq!(languages[0]["current"], true);
+        assert_eq!(languages[0]["permalink"], "http://a-website.com/post/");
+        assert_eq!(languages[1]["code"], "fr");
+        assert_eq!(languages[1]["name"], "Français");
+        assert_eq!(languages[1]["current"], false);
+        assert_eq!(languages[1]["permalink"], "http://a-website.com/fr/post/");
+        assert_eq!(languages[2]["code"], "it");
+        assert_eq!(languages[2]["permalink"], Value::Null);
+
+        // Without a path, the homepage of each language
+        let res = static_fn.call(&HashMap::new()).unwrap();
+        assert_eq!(res[0]["permalink"], "http://a-website.com/");
+        assert_eq!(res[2]["permalink"], "http://a-website.com/it/");
+    }
+
     #[test]
     fn can_get_taxonomy_url() {
         let mut config = Config::default();
@@ -749,6 +1752,39 @@ title = "A title"
         assert_eq!("Failed to retrieve term translation", format!("{}", error));
     }
 
+    const EXTRA_CONFIG: &str = r#"
+base_url = "https://remplace-par-ton-url.fr"
+
+[extra.nav]
+items = [
+    { name = "Home", url = "/" },
+    { name = "Blog", url = "/blog/" },
+]
+        "#;
+
+    #[test]
+    fn can_get_nested_extra_value() {
+        let config = Config::parse(EXTRA_CONFIG).unwrap();
+        let static_fn = GetExtra::new(&config);
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("nav.items.1.url").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "/blog/");
+    }
+
+    #[test]
+    fn can_fallback_to_default_for_missing_extra_value() {
+        let config = Config::parse(EXTRA_CONFIG).unwrap();
+        let static_fn = GetExtra::new(&config);
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("nav.items.5.url").unwrap());
+        assert!(static_fn.call(&args).is_err());
+
+        args.insert("default".to_string(), to_value("/missing/").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "/missing/");
+        args.insert("path".to_string(), to_value("footer.text").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "/missing/");
+    }
+
     #[test]
     fn error_when_language_not_available() {
         let config = Config::parse(TRANS_CONFIG).unwrap();
@@ -836,6 +1872,72 @@ title = "A title"
         assert_eq!(static_fn.call(&args).unwrap(), "379dfab35123b9159d9e4e92dc90e2be44cf3c2f7f09b2e2df80a1b219b461de3556c93e1a9ceb3008e999e2d6a54b4f1d65ee9be9be63fa45ec88931623372f");
     }
 
+    #[test]
+    fn can_get_sri_hash() {
+        let cache = Arc::new(Mutex::new(HashMap::new()));
+        let static_fn = GetSriHash::new(vec![TEST_CONTEXT.static_path.clone()], cache.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("app.css").unwrap());
+        // `openssl dgst -sha384 -binary app.css | openssl base64 -A`
+        let expected = "sha384-FBwJvSiJl3O3crvgZNi3GPodbyhSt+r9XtZonSa3SIO3ni6BTNadW1KrR2qihMQU";
+        assert_eq!(static_fn.call(&args).unwrap(), expected);
+        assert_eq!(cache.lock().unwrap().get("app.css").map(|s| s.as_str()), Some(expected));
+        // Served from the cache the second time
+        assert_eq!(static_fn.call(&args).unwrap(), expected);
+
+        args.insert("path".to_string(), to_value("doesnt-exist.js").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_load_css() {
+        let static_fn = LoadCss::new(vec![TEST_CONTEXT.static_path.clone()]);
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("/app.css").unwrap());
+        assert_eq!(static_fn.call(&args).unwrap(), "// Hello world!");
+    }
+
+    #[test]
+    fn error_when_css_file_not_found() {
+        let static_fn = LoadCss::new(vec![TEST_CONTEXT.static_path.clone()]);
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("doesnt-exist.css").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_bundle_files() {
+        let dir = temp_dir().join("zola_bundle_test");
+        create_directory(&dir).unwrap();
+        create_file(&dir.join("a.css"), "a { color: red; }").unwrap();
+        create_file(&dir.join("b.css"), "b { color: blue; }\n").unwrap();
+        let bundles = Arc::new(Mutex::new(HashMap::new()));
+        let static_fn = Bundle::new(Config::default(), vec![dir.clone()], bundles.clone());
+        let mut args = HashMap::new();
+        args.insert("paths".to_string(), to_value(vec!["a.css", "/b.css"]).unwrap());
+        args.insert("type".to_string(), to_value("css").unwrap());
+
+        let url = static_fn.call(&args).unwrap();
+        let url = url.as_str().unwrap();
+        assert!(url.starts_with("http://a-website.com/bundles/"));
+        assert!(url.ends_with(".css"));
+        // The same files give the same bundle
+        assert_eq!(static_fn.call(&args).unwrap(), url);
+        {
+            let bundles = bundles.lock().unwrap();
+            assert_eq!(bundles.len(), 1);
+            let content = &bundles[url.trim_start_matches("http://a-website.com/")];
+            assert_eq!(content, "a { color: red; }\nb { color: blue; }\n");
+        }
+
+        args.insert("type".to_string(), to_value("scss").unwrap());
+        assert!(static_fn.call(&args).is_err());
+        args.insert("type".to_string(), to_value("css").unwrap());
+        args.insert("paths".to_string(), to_value(vec!["a.css", "doesnt-exist.css"]).unwrap());
+        assert!(static_fn.call(&args).is_err());
+        remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn error_when_file_not_found_for_hash() {
         let static_fn = GetFileHash::new(vec![TEST_CONTEXT.static_path.clone()]);
@@ -849,4 +1951,104 @@ title = "A title"
             format!("{}", static_fn.call(&args).unwrap_err())
         );
     }
+
+    #[test]
+    fn can_include_markdown_file() {
+        let dir = temp_dir().join("include_md");
+        create_directory(&dir).unwrap();
+        create_file(&dir.join("snippet.md"), "# Hello\n\nSee [the post](@/posts/a.md).").unwrap();
+        let mut permalinks = HashMap::new();
+        permalinks.insert("posts/a.md".to_string(), "https://example.com/posts/a/".to_string());
+
+        let tera = Arc::new(RwLock::new(Arc::new(Tera::default())));
+        let static_fn = IncludeMd::new(&tera, Config::default(), permalinks, dir.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("@/snippet.md").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            res,
+            to_value("<h1 id=\"hello\">Hello</h1>\n<p>See <a href=\"https://example.com/posts/a/\">the post</a>.</p>\n")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn errors_on_recursive_include() {
+        let dir = temp_dir().join("include_md_recursive");
+        create_directory(&dir).unwrap();
+        create_file(&dir.join("loop.md"), "Again\n\n{{ include_md(path=\"loop.md\") }}").unwrap();
+        let mut tera = Tera::default();
+        tera.add_raw_template("shortcodes/include_md.html", "{{ include_md(path=path) | safe }}")
+            .unwrap();
+
+        let shared = Arc::new(RwLock::new(Arc::new(Tera::default())));
+        tera.register_function(
+            "include_md",
+            IncludeMd::new(&shared, Config::default(), HashMap::new(), dir.clone()),
+        );
+        *shared.write().unwrap() = Arc::new(tera);
+
+        let static_fn = IncludeMd::new(&shared, Config::default(), HashMap::new(), dir.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("loop.md").unwrap());
+        let res = static_fn.call(&args);
+        remove_dir_all(&dir).unwrap();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn can_generate_image_placeholder() {
+        let content_path = env::current_dir()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("test_site")
+            .join("content");
+        let processor = imageproc::Processor::new(
+            content_path,
+            &temp_dir(),
+            "https://example.com",
+            "processed_images",
+        );
+        let static_fn = ImageLqip::new(Arc::new(Mutex::new(processor)));
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("posts/with-assets/zola.png").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        let data_uri = res.as_str().unwrap();
+        assert!(data_uri.starts_with("data:image/png;base64,"));
+        assert!(data_uri.len() < 2048);
+
+        args.insert("path".to_string(), to_value("posts/with-assets/missing.png").unwrap());
+        assert!(static_fn.call(&args).is_err());
+    }
+
+    #[test]
+    fn can_get_image_description() {
+        let test_site =
+            env::current_dir().unwrap().parent().unwrap().parent().unwrap().join("test_site");
+        let dir = temp_dir().join("image_description");
+        create_directory(&dir.join("gallery")).unwrap();
+        std::fs::copy(
+            test_site.join("content").join("posts").join("with-assets").join("zola.png"),
+            dir.join("gallery").join("zola.png"),
+        )
+        .unwrap();
+        create_file(
+            &dir.join("gallery").join("images.toml"),
+            "[\"zola.png\"]\nalt = \"The Zola logo\"\ntitle = \"Zola\"\n",
+        )
+        .unwrap();
+
+        let static_fn = GetImageMeta::new(dir.clone());
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), to_value("gallery/zola.png").unwrap());
+        let res = static_fn.call(&args).unwrap();
+        assert_eq!(res["alt"], "The Zola logo");
+        assert_eq!(res["title"], "Zola");
+        assert!(res["width"].as_u64().is_some());
+        remove_dir_all(&dir).unwrap();
+    }
 }
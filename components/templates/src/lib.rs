@@ -11,10 +11,12 @@ lazy_static! {
         let mut tera = Tera::default();
         tera.add_raw_templates(vec![
             ("__zola_builtins/404.html", include_str!("builtins/404.html")),
+            ("__zola_builtins/auto_list.html", include_str!("builtins/auto_list.html")),
             ("__zola_builtins/atom.xml", include_str!("builtins/atom.xml")),
             ("__zola_builtins/rss.xml", include_str!("builtins/rss.xml")),
             ("__zola_builtins/sitemap.xml", include_str!("builtins/sitemap.xml")),
             ("__zola_builtins/robots.txt", include_str!("builtins/robots.txt")),
+            ("__zola_builtins/opensearch.xml", include_str!("builtins/opensearch.xml")),
             (
                 "__zola_builtins/split_sitemap_index.xml",
                 include_str!("builtins/split_sitemap_index.xml"),
@@ -33,6 +35,10 @@ lazy_static! {
                 "__zola_builtins/shortcodes/streamable.html",
                 include_str!("builtins/shortcodes/streamable.html"),
             ),
+            (
+                "__zola_builtins/shortcodes/include_md.html",
+                include_str!("builtins/shortcodes/include_md.html"),
+            ),
             ("internal/alias.html", include_str!("builtins/internal/alias.html")),
         ])
         .unwrap();
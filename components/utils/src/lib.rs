@@ -3,6 +3,7 @@ pub mod fs;
 pub mod minify;
 pub mod net;
 pub mod site;
+pub mod sitemap;
 pub mod slugs;
 pub mod templates;
 pub mod vec;
@@ -0,0 +1,14 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// How often the content of a URL is expected to change, the `<changefreq>` of a sitemap entry
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeFreq {
+    Always,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
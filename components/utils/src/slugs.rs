@@ -63,6 +63,9 @@ mod tests {
             ("日本", ("ri-ben", "日本", "日本")),
             ("héhé", ("hehe", "héhé", "héhé")),
             ("test (hey)", ("test-hey", "test (hey)", "test (hey)")),
+            // `safe` keeps the case and unicode but not the path separators
+            ("Hello/World", ("hello-world", "HelloWorld", "Hello/World")),
+            ("Ünïcode\\Päth ", ("unicode-path", "ÜnïcodePäth", "Ünïcode\\Päth ")),
         ];
 
         for (input, (on, safe, off)) in tests {
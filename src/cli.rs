@@ -46,10 +46,23 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("output-dir")
                         .takes_value(true)
                         .help("Outputs the generated site in the given path"),
+                    Arg::with_name("archive")
+                        .long("archive")
+                        .takes_value(true)
+                        .conflicts_with("output_dir")
+                        .help("Writes the generated site in a .tar.gz archive at the given path instead of a directory"),
                     Arg::with_name("drafts")
                         .long("drafts")
                         .takes_value(false)
                         .help("Include drafts when loading the site"),
+                    Arg::with_name("force")
+                        .long("force")
+                        .takes_value(false)
+                        .help("Ignore all caches (processed images, checked links) and rebuild everything"),
+                    Arg::with_name("diagnostics")
+                        .long("diagnostics")
+                        .takes_value(true)
+                        .help("Also writes the warnings and errors to that file as JSON lines"),
                 ]),
             SubCommand::with_name("serve")
                 .about("Serve the site. Rebuild and reload on change automatically")
@@ -97,6 +110,14 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("drafts")
                         .takes_value(false)
                         .help("Include drafts when loading the site"),
+                    Arg::with_name("force")
+                        .long("force")
+                        .takes_value(false)
+                        .help("Ignore all caches (processed images, checked links) and rebuild everything"),
+                    Arg::with_name("check_links_only")
+                        .long("check-links-only")
+                        .takes_value(false)
+                        .help("Reuse the content rendered by the previous check for the files that didn't change"),
                 ])
         ])
 }
@@ -10,7 +10,10 @@ pub fn build(
     config_file: &Path,
     base_url: Option<&str>,
     output_dir: Option<&Path>,
+    archive: Option<&Path>,
     include_drafts: bool,
+    force: bool,
+    diagnostics: Option<&Path>,
 ) -> Result<()> {
     let mut site = Site::new(root_dir, config_file)?;
     if let Some(output_dir) = output_dir {
@@ -22,8 +25,17 @@ pub fn build(
     if include_drafts {
         site.include_drafts();
     }
+    if force {
+        site.force_rebuild();
+    }
+    if let Some(path) = diagnostics {
+        site.set_diagnostics_output(path)?;
+    }
     site.load()?;
     console::notify_site_size(&site);
     console::warn_about_ignored_pages(&site);
-    site.build()
+    match archive {
+        Some(target) => site.build_archive(target),
+        None => site.build(),
+    }
 }
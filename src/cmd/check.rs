@@ -11,6 +11,8 @@ pub fn check(
     base_path: Option<&str>,
     base_url: Option<&str>,
     include_drafts: bool,
+    force: bool,
+    check_links_only: bool,
 ) -> Result<()> {
     let bp = base_path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(root_dir));
     let mut site = Site::new(bp, config_file)?;
@@ -22,6 +24,12 @@ pub fn check(
     if include_drafts {
         site.include_drafts();
     }
+    if force {
+        site.force_rebuild();
+    }
+    if check_links_only {
+        site.use_render_cache();
+    }
     site.load()?;
     console::check_site_summary(&site);
     console::warn_about_ignored_pages(&site);
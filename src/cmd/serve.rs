@@ -25,6 +25,7 @@ use std::fs::{read_dir, remove_dir_all};
 use std::net::{SocketAddrV4, TcpListener};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::channel;
+use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -38,11 +39,10 @@ use notify::{watcher, RecursiveMode, Watcher};
 use ws::{Message, Sender, WebSocket};
 
 use errors::{Error as ZolaError, Result};
-use globset::GlobSet;
 use relative_path::{RelativePath, RelativePathBuf};
 use site::sass::compile_sass;
-use site::{Site, SITE_CONTENT};
-use utils::fs::copy_file;
+use site::static_files::StaticResolver;
+use site::{Site, SiteContent};
 
 use crate::console;
 use std::ffi::OsStr;
@@ -54,6 +54,7 @@ enum ChangeKind {
     Themes,
     StaticFiles,
     Sass,
+    Data,
     Config,
 }
 
@@ -70,12 +71,17 @@ static NOT_FOUND_TEXT: &[u8] = b"Not Found";
 // This is dist/livereload.min.js from the LiveReload.js v3.2.4 release
 const LIVE_RELOAD: &str = include_str!("livereload.js");
 
-async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Response<Body>> {
+async fn handle_request(
+    req: Request<Body>,
+    mut root: PathBuf,
+    content: SiteContent,
+    static_files: Arc<RwLock<StaticResolver>>,
+) -> Result<Response<Body>> {
     let mut path = RelativePathBuf::new();
     // https://zola.discourse.group/t/percent-encoding-for-slugs/736
     let decoded = match percent_encoding::percent_decode_str(req.uri().path()).decode_utf8() {
         Ok(d) => d,
-        Err(_) => return Ok(not_found()),
+        Err(_) => return Ok(not_found(&content)),
     };
 
     for c in decoded.split('/') {
@@ -91,8 +97,11 @@ async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Respons
         }
     }
 
-    if let Some(content) = SITE_CONTENT.read().unwrap().get(&path) {
-        return Ok(in_memory_html(content));
+    if let Some(html) = content.read().unwrap().get(&path) {
+        if path.extension() == Some("json") {
+            return Ok(in_memory_json(html));
+        }
+        return Ok(in_memory_html(html));
     }
 
     // Handle only `GET`/`HEAD` requests
@@ -103,17 +112,24 @@ async fn handle_request(req: Request<Body>, mut root: PathBuf) -> Result<Respons
 
     // Handle only simple path requests
     if req.uri().scheme_str().is_some() || req.uri().host().is_some() {
-        return Ok(not_found());
+        return Ok(not_found(&content));
     }
 
-    // Remove the trailing slash from the request path
-    // otherwise `PathBuf` will interpret it as an absolute path
-    root.push(&req.uri().path()[1..]);
+    // The static files are not copied to the output directory when serving and, like when they
+    // are copied, they take precedence over what was rendered
+    let static_file = static_files.read().unwrap().resolve(&decoded);
+    if let Some(file) = static_file {
+        root = file;
+    } else {
+        // Remove the trailing slash from the request path
+        // otherwise `PathBuf` will interpret it as an absolute path
+        root.push(&req.uri().path()[1..]);
+    }
     let result = tokio::fs::read(root).await;
 
     let contents = match result {
         Err(err) => match err.kind() {
-            std::io::ErrorKind::NotFound => return Ok(not_found()),
+            std::io::ErrorKind::NotFound => return Ok(not_found(&content)),
             std::io::ErrorKind::PermissionDenied => {
                 return Ok(Response::builder()
                     .status(StatusCode::FORBIDDEN)
@@ -144,6 +160,14 @@ fn in_memory_html(content: &str) -> Response<Body> {
         .expect("Could not build HTML response")
 }
 
+fn in_memory_json(content: &str) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .status(StatusCode::OK)
+        .body(content.to_owned().into())
+        .expect("Could not build JSON response")
+}
+
 fn method_not_allowed() -> Response<Body> {
     Response::builder()
         .header(header::CONTENT_TYPE, "text/plain")
@@ -152,11 +176,11 @@ fn method_not_allowed() -> Response<Body> {
         .expect("Could not build Method Not Allowed response")
 }
 
-fn not_found() -> Response<Body> {
+fn not_found(content: &SiteContent) -> Response<Body> {
     let not_found_path = RelativePath::new("404.html");
-    let content = SITE_CONTENT.read().unwrap().get(not_found_path).cloned();
+    let body = content.read().unwrap().get(not_found_path).cloned();
 
-    if let Some(body) = content {
+    if let Some(body) = body {
         return Response::builder()
             .header(header::CONTENT_TYPE, "text/html")
             .status(StatusCode::NOT_FOUND)
@@ -203,8 +227,11 @@ fn create_new_site(
     config_file: &Path,
     include_drafts: bool,
     ws_port: Option<u16>,
+    content: &SiteContent,
 ) -> Result<(Site, String)> {
     let mut site = Site::new(root_dir, config_file)?;
+    // The web server keeps serving from the same map when the site is recreated
+    site.content_map = content.clone();
 
     let base_address = format!("{}:{}", base_url, interface_port);
     let address = format!("{}:{}", interface, interface_port);
@@ -247,6 +274,7 @@ pub fn serve(
     fast_rebuild: bool,
 ) -> Result<()> {
     let start = Instant::now();
+    let content = SiteContent::default();
     let (mut site, address) = create_new_site(
         root_dir,
         interface,
@@ -256,6 +284,7 @@ pub fn serve(
         config_file,
         include_drafts,
         None,
+        &content,
     )?;
     console::report_elapsed_time(start);
 
@@ -273,6 +302,7 @@ pub fn serve(
         ("sass", WatchMode::Condition(site.config.compile_sass)),
         ("static", WatchMode::Optional),
         ("templates", WatchMode::Optional),
+        ("data", WatchMode::Optional),
         ("themes", WatchMode::Condition(site.config.theme.is_some())),
     ];
 
@@ -308,6 +338,10 @@ pub fn serve(
     // output path is going to need to be moved later on, so clone it for the
     // http closure to avoid contention.
     let static_root = output_path.clone();
+    let served_content = content.clone();
+    // Replaced when the site is recreated since the config or the theme might have changed
+    let static_files = Arc::new(RwLock::new(site.static_resolver()));
+    let served_static_files = static_files.clone();
     let broadcaster = {
         thread::spawn(move || {
             let addr = address.parse().unwrap();
@@ -320,10 +354,17 @@ pub fn serve(
             rt.block_on(async {
                 let make_service = make_service_fn(move |_| {
                     let static_root = static_root.clone();
+                    let served_content = served_content.clone();
+                    let static_files = served_static_files.clone();
 
                     async {
                         Ok::<_, hyper::Error>(service_fn(move |req| {
-                            handle_request(req, static_root.clone())
+                            handle_request(
+                                req,
+                                static_root.clone(),
+                                served_content.clone(),
+                                static_files.clone(),
+                            )
                         }))
                     }
                 });
@@ -406,12 +447,16 @@ pub fn serve(
         rebuild_done_handling(&broadcaster, site.reload_templates(), &path.to_string_lossy());
     };
 
-    let copy_static = |site: &Site, path: &Path, partial_path: &Path| {
-        // Do nothing if the file/dir was deleted
-        if !path.exists() {
-            return;
-        }
+    let reload_taxonomy_templates = |site: &mut Site, path: &Path| {
+        rebuild_done_handling(
+            &broadcaster,
+            site.reload_taxonomy_templates(),
+            &path.to_string_lossy(),
+        );
+    };
 
+    // Static files are served from where they are so there is nothing to copy, only to reload
+    let reload_static = |path: &Path, partial_path: &Path| {
         let msg = if path.is_dir() {
             format!("-> Directory in `static` folder changed {}", path.display())
         } else {
@@ -419,24 +464,8 @@ pub fn serve(
         };
 
         console::info(&msg);
-        if path.is_dir() {
-            rebuild_done_handling(
-                &broadcaster,
-                site.copy_static_directories(),
-                &path.to_string_lossy(),
-            );
-        } else {
-            rebuild_done_handling(
-                &broadcaster,
-                copy_file(
-                    &path,
-                    &site.output_path,
-                    &site.static_path,
-                    site.config.hard_link_static,
-                ),
-                &partial_path.to_string_lossy(),
-            );
-        }
+        let reload_path = if path.is_dir() { path } else { partial_path };
+        rebuild_done_handling(&broadcaster, Ok(()), &reload_path.to_string_lossy());
     };
 
     let recreate_site = || match create_new_site(
@@ -448,8 +477,10 @@ pub fn serve(
         config_file,
         include_drafts,
         ws_port,
+        &content,
     ) {
         Ok((s, _)) => {
+            *static_files.write().unwrap() = s.static_resolver();
             rebuild_done_handling(&broadcaster, Ok(()), "/x.js");
             Some(s)
         }
@@ -468,7 +499,7 @@ pub fn serve(
                     // Intellij does weird things on edit, chmod is there to count those changes
                     // https://github.com/passcod/notify/issues/150#issuecomment-494912080
                     Rename(_, path) | Create(path) | Write(path) | Remove(path) | Chmod(path) => {
-                        if is_ignored_file(&site.config.ignored_content_globset, &path) {
+                        if site.config.is_ignored_content(&path) {
                             continue;
                         }
                         if is_temp_file(&path) || path.is_dir() {
@@ -542,13 +573,16 @@ pub fn serve(
                                     if let Some(s) = recreate_site() {
                                         site = s;
                                     }
+                                } else if is_taxonomy_template(&site, &partial_path) {
+                                    println!("Reloading only taxonomies");
+                                    reload_taxonomy_templates(&mut site, &path)
                                 } else {
                                     println!("Reloading only template");
                                     // A normal template changed, no need to re-render Markdown.
                                     reload_templates(&mut site, &path)
                                 }
                             }
-                            (ChangeKind::StaticFiles, p) => copy_static(&site, &path, &p),
+                            (ChangeKind::StaticFiles, p) => reload_static(&path, &p),
                             (ChangeKind::Sass, p) => reload_sass(&site, &path, &p),
                             (ChangeKind::Themes, _) => {
                                 console::info("-> Themes changed.");
@@ -557,6 +591,13 @@ pub fn serve(
                                     site = s;
                                 }
                             }
+                            (ChangeKind::Data, _) => {
+                                console::info(&format!("-> Data changed {}", path.display()));
+
+                                if let Some(s) = recreate_site() {
+                                    site = s;
+                                }
+                            }
                             (ChangeKind::Config, _) => {
                                 console::info("-> Config changed. The browser needs to be refreshed to make the changes visible.");
 
@@ -575,11 +616,16 @@ pub fn serve(
     }
 }
 
-fn is_ignored_file(ignored_content_globset: &Option<GlobSet>, path: &Path) -> bool {
-    match ignored_content_globset {
-        Some(gs) => gs.is_match(path),
-        None => false,
-    }
+/// Whether the template is only used by a taxonomy, eg `/templates/tags/single.html`
+fn is_taxonomy_template(site: &Site, partial_path: &Path) -> bool {
+    let relative = match partial_path.strip_prefix("/templates") {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    let parts: Vec<_> = relative.iter().map(|c| c.to_string_lossy()).collect();
+    parts.len() == 2
+        && (parts[1] == "list.html" || parts[1] == "single.html")
+        && site.config.taxonomies.iter().any(|t| t.name == parts[0])
 }
 
 /// Returns whether the path we received corresponds to a temp file created
@@ -625,6 +671,8 @@ fn detect_change_kind(pwd: &Path, path: &Path) -> (ChangeKind, PathBuf) {
         ChangeKind::StaticFiles
     } else if partial_path.starts_with("/sass") {
         ChangeKind::Sass
+    } else if partial_path.starts_with("/data") {
+        ChangeKind::Data
     } else if partial_path == Path::new("/config.toml") {
         ChangeKind::Config
     } else {
@@ -695,6 +743,11 @@ mod tests {
                 Path::new("/home/vincent/site"),
                 Path::new("/home/vincent/site/sass/print.scss"),
             ),
+            (
+                (ChangeKind::Data, PathBuf::from("/data/menu.toml")),
+                Path::new("/home/vincent/site"),
+                Path::new("/home/vincent/site/data/menu.toml"),
+            ),
             (
                 (ChangeKind::Config, PathBuf::from("/config.toml")),
                 Path::new("/home/vincent/site"),
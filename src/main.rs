@@ -38,12 +38,17 @@ fn main() {
             console::info("Building site...");
             let start = Instant::now();
             let output_dir = matches.value_of("output_dir").map(|output_dir| Path::new(output_dir));
+            let archive = matches.value_of("archive").map(|archive| Path::new(archive));
+            let diagnostics = matches.value_of("diagnostics").map(|path| Path::new(path));
             match cmd::build(
                 &root_dir,
                 &config_file,
                 matches.value_of("base_url"),
                 output_dir,
+                archive,
                 matches.is_present("drafts"),
+                matches.is_present("force"),
+                diagnostics,
             ) {
                 Ok(()) => console::report_elapsed_time(start),
                 Err(e) => {
@@ -109,6 +114,8 @@ fn main() {
                 matches.value_of("base_path"),
                 matches.value_of("base_url"),
                 matches.is_present("drafts"),
+                matches.is_present("force"),
+                matches.is_present("check_links_only"),
             ) {
                 Ok(()) => console::report_elapsed_time(start),
                 Err(e) => {